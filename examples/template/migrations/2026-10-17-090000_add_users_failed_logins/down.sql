@@ -0,0 +1 @@
+ALTER TABLE users DROP COLUMN failed_logins;
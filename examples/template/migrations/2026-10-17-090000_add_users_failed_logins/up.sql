@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN failed_logins BIGINT NOT NULL DEFAULT 0;
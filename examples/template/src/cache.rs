@@ -1,5 +1,6 @@
 use deadpool_redis::{redis::IntoConnectionInfo, Config, Pool, Runtime};
 use hextacy::{adapters::cache::redis::RedisConnection, Driver};
+use std::time::Duration;
 
 /// Contains a redis deadpool instance.
 #[derive(Clone)]
@@ -42,4 +43,8 @@ impl Driver for RedisDriver {
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
         self.pool.get().await
     }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.pool.health_check().await
+    }
 }
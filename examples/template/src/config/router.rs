@@ -1,8 +1,13 @@
+use crate::controllers::http::middleware::access_log::access_log;
+use crate::controllers::http::middleware::content_type::require_json;
+use crate::controllers::http::middleware::json_depth::limit_json_depth;
+use crate::controllers::http::middleware::payload::{limit_payload, PAYLOAD};
 use crate::{
     config::state::{AppState, AuthenticationService},
     // controllers::http::middleware::auth::session_check,
 };
 use axum::{
+    extract::DefaultBodyLimit,
     middleware::{self},
     routing::{get, post},
     Router,
@@ -15,15 +20,30 @@ pub async fn router(state: &AppState) -> Router {
     let resource_router = resource_router();
     let auth_router = auth_router(auth_service).await;
 
-    let router = Router::new();
+    let router = Router::new().merge(health_router(state));
+
+    // Requests without a content length are limited while their bodies are read
+    router
+        .merge(auth_router)
+        .merge(resource_router)
+        .layer(DefaultBodyLimit::max(PAYLOAD.max()))
+        .layer(middleware::from_fn(limit_payload))
+        .layer(middleware::from_fn(access_log))
+}
 
-    router.merge(auth_router).merge(resource_router)
+fn health_router(state: &AppState) -> Router {
+    use crate::controllers::http::health::health;
+    Router::new()
+        .route("/health", get(health))
+        .with_state(state.clone())
 }
 
 fn resource_router() -> Router {
     use crate::controllers::http::resources::*;
     let router = Router::new();
-    router.route("/favicon.ico", get(favicon::favicon))
+    router
+        .route("/favicon.ico", get(favicon::favicon))
+        .route("/version", get(version::version))
 }
 
 async fn auth_router(service: AuthenticationService) -> Router<()> {
@@ -35,7 +55,9 @@ async fn auth_router(service: AuthenticationService) -> Router<()> {
         .route(
             "/logout",
             post(logout), /*.layer(middleware::from_fn_with_state(auth_mw, session_check)), */
-        );
+        )
+        .route_layer(middleware::from_fn(limit_json_depth))
+        .route_layer(middleware::from_fn(require_json));
 
     Router::new().nest("/auth", router).with_state(service)
 }
@@ -1,5 +1,7 @@
 use crate::cache::RedisDriver;
 use crate::core::auth::Authentication;
+use crate::core::models::email::EmailDomains;
+use crate::core::models::session::SessionPolicy;
 use crate::db::adapters::session::SessionAdapter;
 use crate::db::adapters::user::UserAdapter;
 use crate::db::driver::SeaormDriver;
@@ -49,6 +51,8 @@ impl AuthenticationService {
                 .publisher("my-channel")
                 .await
                 .expect("Could not create publisher"),
+            session_policy: SessionPolicy::default(),
+            email_domains: EmailDomains::from_env(),
         }
     }
 }
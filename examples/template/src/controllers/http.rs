@@ -1,6 +1,8 @@
 pub mod auth;
+pub mod health;
 pub mod middleware;
 pub mod resources;
+pub mod validation;
 
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use hextacy::web::{cookie::time::Duration, cookie::CookieBuilder};
@@ -25,6 +27,19 @@ pub fn session_cookie<'a>(key: &'a str, value: &'a str, expire: bool) -> Cookie<
         .finish()
 }
 
+/// Cookie holding the session's CSRF token. Readable by scripts so they can echo it
+/// in the `x-csrf-token` header. Must be re-issued whenever the token is rotated.
+pub fn csrf_cookie<'a>(value: &'a str, expire: bool) -> Cookie<'a> {
+    CookieBuilder::new("X_CSRF", value)
+        .path(PATH)
+        .domain(DOMAIN)
+        .max_age(if expire { Duration::ZERO } else { MAX_AGE })
+        .same_site(SameSite::Strict)
+        .http_only(false)
+        .secure(SECURE)
+        .finish()
+}
+
 /// Holds a single message. Implements the Response trait as well as actix' Responder.
 #[derive(Debug, Serialize, RestResponse)]
 pub struct MessageResponse {
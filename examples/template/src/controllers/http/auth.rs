@@ -1,6 +1,6 @@
 use super::MessageResponse;
 use crate::config::state::AuthenticationService;
-use crate::controllers::http::session_cookie;
+use crate::controllers::http::{csrf_cookie, session_cookie};
 use crate::core::models::session::Session;
 use crate::error::Error;
 use axum::extract::State;
@@ -15,6 +15,8 @@ pub struct Register {
     #[modify(trim)]
     #[validate(length(min = 2))]
     pub username: String,
+    /// Checked against the accepted email domains by the service.
+    pub email: Option<String>,
     #[validate(length(min = 8))]
     pub password: String,
 }
@@ -37,14 +39,23 @@ pub async fn register(
     State(service): State<AuthenticationService>,
     Json(data): Json<RegisterPayload>,
 ) -> Result<Response<String>, Error> {
-    let Register { username, password } = Register::validify(data).map_err(Error::new)?;
-    let (_, session) = service.register(&username, &password).await?;
+    let Register {
+        username,
+        email,
+        password,
+    } = Register::validify(data).map_err(Error::new)?;
+    let (_, session) = service
+        .register(&username, email.as_deref(), &password)
+        .await?;
     let (session_id, csrf) = (session.id.to_string(), session.csrf.to_string());
-    let cookie = session_cookie("S_ID", &session_id, false);
+    let cookies = [
+        session_cookie("S_ID", &session_id, false),
+        csrf_cookie(&csrf, false),
+    ];
     MessageResponse::new("Successfully created account")
         .into_response(StatusCode::CREATED)
         .with_headers([("x-csrf-token", &csrf)])
-        .with_cookies(&[cookie])?
+        .with_cookies(&cookies)?
         .json()
         .map_err(Error::new)
 }
@@ -60,11 +71,14 @@ pub async fn login(
     } = Login::validify(data).map_err(Error::new)?;
     let session = service.login(&username, &password, remember).await?;
     let (session_id, csrf) = (session.id.to_string(), session.csrf.to_string());
-    let cookie = session_cookie("S_ID", &session_id, false);
+    let cookies = [
+        session_cookie("S_ID", &session_id, false),
+        csrf_cookie(&csrf, false),
+    ];
     MessageResponse::new("Successfully logged in")
         .into_response(StatusCode::OK)
         .with_headers([("x-csrf-token", &csrf)])
-        .with_cookies(&[cookie])?
+        .with_cookies(&cookies)?
         .json()
         .map_err(Error::new)
 }
@@ -0,0 +1,119 @@
+use crate::config::state::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use hextacy::Driver;
+use serde::Serialize;
+use std::fmt::Display;
+use std::time::Duration;
+use tracing::warn;
+
+/// Probes taking longer than this are logged.
+const SLOW_PROBE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Serialize)]
+pub struct Health {
+    healthy: bool,
+    drivers: Vec<DriverHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriverHealth {
+    name: &'static str,
+    healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Health check for load balancers. Responds with a 503 if any of the drivers can't reach its data source.
+pub async fn health(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let drivers = vec![
+        probe("postgres", &state.repository).await,
+        probe("redis", &state.cache).await,
+    ];
+    report(drivers)
+}
+
+async fn probe<D>(name: &'static str, driver: &D) -> DriverHealth
+where
+    D: Driver,
+    D::Error: Display,
+{
+    match driver.health_check().await {
+        Ok(latency) => {
+            if latency > SLOW_PROBE {
+                warn!("Health check of '{name}' took {latency:?}");
+            }
+            DriverHealth {
+                name,
+                healthy: true,
+                latency_ms: Some(latency.as_millis()),
+                error: None,
+            }
+        }
+        Err(e) => {
+            warn!("Health check of '{name}' failed: {e}");
+            DriverHealth {
+                name,
+                healthy: false,
+                latency_ms: None,
+                error: Some(e.to_string()),
+            }
+        }
+    }
+}
+
+fn report(drivers: Vec<DriverHealth>) -> (StatusCode, Json<Health>) {
+    let healthy = drivers.iter().all(|d| d.healthy);
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(Health { healthy, drivers }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeDriver(Result<Duration, &'static str>);
+
+    impl Driver for FakeDriver {
+        type Connection = ();
+        type Error = &'static str;
+
+        async fn connect(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<Duration, Self::Error> {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn unavailable_if_any_driver_fails() {
+        let postgres = FakeDriver(Ok(Duration::from_millis(3)));
+        let redis = FakeDriver(Err("Connection refused"));
+
+        let (status, Json(health)) = report(vec![probe("postgres", &postgres).await]);
+        assert_eq!(status, StatusCode::OK);
+        assert!(health.healthy);
+        assert_eq!(health.drivers[0].latency_ms, Some(3));
+
+        let (status, Json(health)) = report(vec![
+            probe("postgres", &postgres).await,
+            probe("redis", &redis).await,
+        ]);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!health.healthy);
+        assert!(health.drivers[0].healthy);
+        assert_eq!(
+            health.drivers[1].error.as_deref(),
+            Some("Connection refused")
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use crate::core::models::session::Session;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use hextacy::web::xhttp::access_log::AccessLog;
+
+/// Emits a structured access log record for every request, see [AccessLog].
+pub async fn access_log<B>(req: Request<B>, next: Next<B>) -> Response {
+    let mut log = AccessLog::begin(&req);
+    if let Some(path) = req.extensions().get::<MatchedPath>() {
+        log = log.path_template(path.as_str());
+    }
+    if let Some(session) = req.extensions().get::<Session>() {
+        log = log.user_id(session.user_id);
+    }
+
+    let res = next.run(req).await;
+    log.finish(&res);
+    res
+}
@@ -0,0 +1,15 @@
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use hextacy::web::xhttp::content_type::RequireContentType;
+
+const JSON: RequireContentType = RequireContentType::json();
+
+/// Rejects requests to JSON endpoints not sent as `application/json` with a 415 problem+json,
+/// before the extractors attempt to deserialize them.
+pub async fn require_json<B>(req: Request<B>, next: Next<B>) -> Response {
+    match JSON.check(&req) {
+        Some(res) => res.into_response(),
+        None => next.run(req).await,
+    }
+}
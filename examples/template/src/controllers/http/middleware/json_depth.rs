@@ -0,0 +1,34 @@
+use super::payload::PAYLOAD;
+use axum::body::{Body, HttpBody};
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use hextacy::web::xhttp::json_depth::JsonDepth;
+
+/// Maximum nesting depth of JSON bodies.
+pub const JSON_DEPTH: JsonDepth = JsonDepth::new(16);
+
+/// Rejects JSON bodies nested deeper than [JSON_DEPTH] with a 400 problem+json, before the extractors
+/// deserialize them. The body is buffered up to the [PAYLOAD] limit.
+pub async fn limit_json_depth(req: Request<Body>, next: Next<Body>) -> Response {
+    let (parts, mut body) = req.into_parts();
+
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) if bytes.len() + chunk.len() <= PAYLOAD.max() => {
+                bytes.extend_from_slice(&chunk)
+            }
+            Ok(_) => return PAYLOAD.too_large().into_response(),
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        }
+    }
+
+    match JSON_DEPTH.check(&bytes) {
+        Some(res) => res.into_response(),
+        None => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+    }
+}
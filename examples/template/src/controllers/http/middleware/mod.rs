@@ -1 +1,5 @@
+pub mod access_log;
 pub mod auth;
+pub mod content_type;
+pub mod json_depth;
+pub mod payload;
@@ -0,0 +1,15 @@
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use hextacy::web::xhttp::payload::PayloadConfig;
+
+/// Global cap on request body sizes, 1 MiB.
+pub const PAYLOAD: PayloadConfig = PayloadConfig::new(1024 * 1024);
+
+/// Rejects requests declaring bodies larger than [PAYLOAD] with a 413 problem+json.
+pub async fn limit_payload<B>(req: Request<B>, next: Next<B>) -> Response {
+    match PAYLOAD.check(&req) {
+        Some(res) => res.into_response(),
+        None => next.run(req).await,
+    }
+}
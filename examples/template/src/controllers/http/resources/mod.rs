@@ -1 +1,2 @@
 pub mod favicon;
+pub mod version;
@@ -0,0 +1,7 @@
+use axum::response::{IntoResponse, Response};
+use hextacy::version_info;
+
+/// The crate version, commit and build time of the deployed binary.
+pub async fn version() -> Response {
+    version_info!().response().into_response()
+}
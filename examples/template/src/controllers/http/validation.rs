@@ -0,0 +1,69 @@
+use validify::{ValidationErrors, Validify};
+
+/// Modifies and validates every item, for bulk endpoints which must reject the whole batch
+/// if any item is invalid.
+///
+/// Returns the items if all are valid, otherwise the errors of every invalid item along with its index.
+pub fn validate_all<T: Validify>(
+    items: impl IntoIterator<Item = T>,
+) -> Result<Vec<T>, Vec<(usize, ValidationErrors)>> {
+    let mut valid = vec![];
+    let mut failed = vec![];
+
+    for (i, mut item) in items.into_iter().enumerate() {
+        match item.validify() {
+            Ok(()) => valid.push(item),
+            Err(errors) => failed.push((i, errors)),
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(valid)
+    } else {
+        Err(failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Validify)]
+    struct Invite {
+        #[modify(trim)]
+        #[validate(length(min = 2))]
+        username: String,
+        #[validate(range(min = 1., max = 30.))]
+        expires_in_days: u32,
+    }
+
+    fn invite(username: &str, expires_in_days: u32) -> Invite {
+        Invite {
+            username: username.to_string(),
+            expires_in_days,
+        }
+    }
+
+    #[test]
+    fn all_valid() {
+        let invites = validate_all(vec![invite(" alice ", 7), invite("bob", 30)]).unwrap();
+        assert_eq!(invites.len(), 2);
+        assert_eq!(invites[0].username, "alice");
+    }
+
+    #[test]
+    fn reports_failing_indices() {
+        let failed = validate_all(vec![
+            invite("alice", 7),
+            invite(" a ", 7),
+            invite("carol", 14),
+            invite("d", 90),
+        ])
+        .unwrap_err();
+
+        let indices = failed.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+        assert_eq!(indices, [1, 3]);
+        assert_eq!(failed[0].1.errors().len(), 1);
+        assert_eq!(failed[1].1.errors().len(), 2);
+    }
+}
@@ -1,9 +1,13 @@
 use super::models::user::User;
 use crate::{
     core::{
-        models::session::Session,
+        models::{
+            email::{Email, EmailDomains},
+            session::{Session, SessionPolicy},
+        },
         repository::{session::SessionRepository, user::UserRepository},
     },
+    db::adapters::AdapterError,
     error::Error,
     AppResult,
 };
@@ -11,6 +15,7 @@ use hextacy::{queue::Producer, Driver};
 use serde::Serialize;
 use thiserror::Error;
 use uuid::Uuid;
+use validify::ValidationErrors;
 
 #[derive(Debug, Serialize)]
 pub struct UserRegisteredEvent {
@@ -23,6 +28,10 @@ pub struct Authentication<U, S, P> {
     pub user_repo: U,
     pub session_repo: S,
     pub producer: P,
+    /// Applied to sessions created on login.
+    pub session_policy: SessionPolicy,
+    /// Checked before accepting a registration.
+    pub email_domains: EmailDomains,
 }
 
 impl<U, S, P> Authentication<U, S, P>
@@ -31,10 +40,17 @@ where
     S: SessionRepository,
     P: Producer,
 {
-    pub async fn register(&self, username: &str, password: &str) -> AppResult<(User, Session)> {
-        match self.user_repo.get_by_username(username).await {
-            Ok(None) => {}
-            Ok(Some(_)) => return Err(AuthenticationError::UsernameTaken.into()),
+    pub async fn register(
+        &self,
+        username: &str,
+        email: Option<&str>,
+        password: &str,
+    ) -> AppResult<(User, Session)> {
+        let _email = email.map(|email| self.check_email(email)).transpose()?;
+
+        match self.user_repo.exists_by_username(username).await {
+            Ok(false) => {}
+            Ok(true) => return Err(AuthenticationError::UsernameTaken.into()),
             Err(e) => return Err(e.into()),
         };
 
@@ -59,6 +75,19 @@ where
         todo!()
     }
 
+    /// Parses the email and checks its domain is accepted.
+    pub fn check_email(&self, email: &str) -> AppResult<Email> {
+        let email = Email::parse(email).and_then(|email| {
+            self.email_domains.check(&email)?;
+            Ok(email)
+        });
+        email.map_err(|e| {
+            let mut errors = ValidationErrors::new();
+            errors.add(e);
+            Error::new(errors)
+        })
+    }
+
     pub async fn login(
         &self,
         username: &str,
@@ -76,11 +105,32 @@ where
             return Err(AuthenticationError::InvalidCredentials.into());
         }
 
-        let session = self.session_repo.create(&user, !remember).await?;
+        let session = match self
+            .session_repo
+            .create(&user, !remember, &self.session_policy)
+            .await
+        {
+            Ok(session) => session,
+            Err(AdapterError::SessionLimitReached(_)) => {
+                return Err(AuthenticationError::TooManySessions.into())
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         Ok(session)
     }
 
+    /// Re-issue the session's CSRF token. Must be called whenever the session's privileges change
+    /// so a token obtained before the change cannot be reused after it.
+    ///
+    /// Logging in always creates a new session and with it a fresh token.
+    pub async fn rotate_csrf(&self, session_id: Uuid) -> AppResult<Session> {
+        self.session_repo
+            .rotate_csrf(session_id)
+            .await
+            .map_err(Error::new)
+    }
+
     pub async fn logout(&self, session_id: Uuid, purge: bool) -> AppResult<u64> {
         let session = self.session_repo.expire(session_id).await?;
         if purge {
@@ -102,4 +152,119 @@ pub enum AuthenticationError {
     UsernameTaken,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Invalid or expired password reset token")]
+    InvalidResetToken,
+    #[error("Too many password reset requests")]
+    TooManyResetRequests,
+    #[error("Too many active sessions")]
+    TooManySessions,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        models::session::SessionPolicy,
+        repository::{session::MockSessionRepository, user::MockUserRepository},
+        tests::ready,
+    };
+    use chrono::Utc;
+    use hextacy::queue::QueueError;
+
+    #[derive(Debug, Clone)]
+    struct NoopProducer;
+
+    impl Producer for NoopProducer {
+        async fn publish<M>(&self, _: M) -> Result<(), QueueError>
+        where
+            M: Serialize + Send + Sync + 'static,
+        {
+            Ok(())
+        }
+    }
+
+    fn user() -> User {
+        let password = hextacy::crypto::bcrypt_hash("password", 4).unwrap();
+        User::new("user".to_string(), password)
+    }
+
+    fn service(
+        user_repo: MockUserRepository,
+        session_repo: MockSessionRepository,
+    ) -> Authentication<MockUserRepository, MockSessionRepository, NoopProducer> {
+        Authentication {
+            user_repo,
+            session_repo,
+            producer: NoopProducer,
+            session_policy: SessionPolicy::default(),
+            email_domains: EmailDomains::new(["mailinator.com"], None::<[&str; 0]>),
+        }
+    }
+
+    /// Logging in as `user` finds it and creates sessions for it.
+    fn login_mocks(user: User) -> (MockUserRepository, MockSessionRepository) {
+        let mut users = MockUserRepository::new();
+        users
+            .expect_get_by_username()
+            .withf(|username| username == "user")
+            .returning(move |_| ready(Ok(Some(user.clone()))));
+
+        let mut sessions = MockSessionRepository::new();
+        sessions
+            .expect_create()
+            .returning(|user, expires, _| Ok(Session::new(user.id, expires)));
+
+        (users, sessions)
+    }
+
+    #[tokio::test]
+    async fn login_issues_new_csrf() {
+        let (users, sessions) = login_mocks(user());
+        let service = service(users, sessions);
+
+        let first = service.login("user", "password", false).await.unwrap();
+        let second = service.login("user", "password", false).await.unwrap();
+
+        assert_ne!(first.csrf, second.csrf);
+    }
+
+    #[tokio::test]
+    async fn rotated_csrf_invalidates_previous() {
+        let (users, sessions) = login_mocks(user());
+        let mut service = service(users, sessions);
+        let session = service.login("user", "password", false).await.unwrap();
+
+        let id = session.id;
+        let rotated = session.clone().rotate_csrf(Utc::now().naive_utc());
+        service
+            .session_repo
+            .expect_rotate_csrf()
+            .withf(move |session_id| *session_id == id)
+            .times(1)
+            .returning({
+                let rotated = rotated.clone();
+                move |_| Ok(rotated.clone())
+            });
+
+        let returned = service.rotate_csrf(session.id).await.unwrap();
+        assert_eq!(returned.csrf, rotated.csrf);
+        assert_ne!(returned.csrf, session.csrf);
+    }
+
+    #[tokio::test]
+    async fn registration_rejects_denied_domains() {
+        // Rejected before reaching the repositories, the mocks panic if they are called
+        let service = service(MockUserRepository::new(), MockSessionRepository::new());
+
+        let error = service
+            .register("new_user", Some("new@mailinator.com"), "password")
+            .await
+            .unwrap_err();
+        let Error::Validation(problem) = error else {
+            panic!("expected a validation error, got {error:?}");
+        };
+        assert_eq!(problem.to_string(), "1 violation(s) in body");
+
+        assert!(service.check_email("new@example.com").is_ok());
+    }
 }
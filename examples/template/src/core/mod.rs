@@ -1,5 +1,6 @@
 pub mod auth;
 pub mod models;
+pub mod password_reset;
 pub mod repository;
 
 mod tests;
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Display;
+use validify::ValidationError;
+
+/// A validated email address in its normalized form, i.e. trimmed and lowercased,
+/// so the same address always gets stored and looked up the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Email(String);
+
+impl Email {
+    pub fn parse(email: &str) -> Result<Self, ValidationError> {
+        let mut email = email.to_string();
+        normalize_email(&mut email);
+        if !validify::validate_email(&email) {
+            return Err(ValidationError::new_field_named("email", "email")
+                .with_message("Invalid email address".to_string()));
+        }
+        Ok(Self(email))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The part after the `@`.
+    pub fn domain(&self) -> &str {
+        self.0
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or_default()
+    }
+}
+
+/// Comma separated domains rejected at registration.
+pub const DENYLIST_ENV: &str = "EMAIL_DOMAIN_DENYLIST";
+/// Comma separated domains, if set only these are accepted at registration.
+pub const ALLOWLIST_ENV: &str = "EMAIL_DOMAIN_ALLOWLIST";
+
+/// Which email domains can be used to register, e.g. to block disposable email providers.
+///
+/// A listed domain also covers its subdomains. The denylist takes precedence over the allowlist
+/// and without an allowlist every domain not denied is accepted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailDomains {
+    deny: HashSet<String>,
+    allow: Option<HashSet<String>>,
+}
+
+impl EmailDomains {
+    pub fn new<'a>(
+        deny: impl IntoIterator<Item = &'a str>,
+        allow: Option<impl IntoIterator<Item = &'a str>>,
+    ) -> Self {
+        Self {
+            deny: normalize_domains(deny),
+            allow: allow.map(normalize_domains),
+        }
+    }
+
+    /// Loads the lists from [DENYLIST_ENV] and [ALLOWLIST_ENV].
+    pub fn from_env() -> Self {
+        let deny = hextacy::env::get(DENYLIST_ENV).unwrap_or_default();
+        let allow = hextacy::env::get(ALLOWLIST_ENV).ok();
+        Self::new(deny.split(','), allow.as_deref().map(|a| a.split(',')))
+    }
+
+    pub fn check(&self, email: &Email) -> Result<(), ValidationError> {
+        let domain = email.domain();
+        let listed = |list: &HashSet<String>| {
+            std::iter::successors(Some(domain), |d| {
+                d.split_once('.').map(|(_, parent)| parent)
+            })
+            .any(|d| list.contains(d))
+        };
+
+        let allowed = !listed(&self.deny) && self.allow.as_ref().map_or(true, listed);
+        if !allowed {
+            return Err(ValidationError::new_field_named("email", "email_domain")
+                .with_message(format!("Email addresses from {domain} are not accepted"))
+                .with_param("domain", &domain));
+        }
+        Ok(())
+    }
+}
+
+fn normalize_domains<'a>(domains: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    domains
+        .into_iter()
+        .map(|d| d.trim().trim_start_matches('@').to_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// Modifier for payloads, i.e. `#[modify(custom(normalize_email))]` followed by `#[validate(email)]`.
+pub fn normalize_email(email: &mut String) {
+    *email = email.trim().to_lowercase();
+}
+
+impl TryFrom<String> for Email {
+    type Error = ValidationError;
+
+    fn try_from(email: String) -> Result<Self, Self::Error> {
+        Self::parse(&email)
+    }
+}
+
+impl From<Email> for String {
+    fn from(email: Email) -> Self {
+        email.0
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Email {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization() {
+        let email = Email::parse("  user@example.com\n").unwrap();
+        assert_eq!(email.as_str(), "user@example.com");
+    }
+
+    #[test]
+    fn mixed_case_normalizes_consistently() {
+        let a = Email::parse("John.Doe@Example.COM").unwrap();
+        let b = Email::parse("john.doe@example.com ").unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.as_str(), "john.doe@example.com");
+
+        let c: Email = serde_json::from_str("\"JOHN.DOE@EXAMPLE.COM\"").unwrap();
+        assert_eq!(a, c);
+    }
+
+    #[test]
+    fn invalid_address() {
+        for email in ["", "user", "user@", "@example.com", "us er@example.com"] {
+            let err = Email::parse(email).unwrap_err();
+            assert_eq!(err.field_name(), Some("email"), "{email}");
+            assert_eq!(
+                err.message().as_deref(),
+                Some("Invalid email address"),
+                "{email}"
+            );
+        }
+        let err = serde_json::from_str::<Email>("\"not an email\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid email address"), "{err}");
+    }
+
+    fn email(address: &str) -> Email {
+        Email::parse(address).unwrap()
+    }
+
+    #[test]
+    fn allows_unlisted_domains() {
+        let domains = EmailDomains::new(["mailinator.com"], None::<[&str; 0]>);
+        assert!(domains.check(&email("user@example.com")).is_ok());
+        assert!(EmailDomains::default()
+            .check(&email("user@mailinator.com"))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_denied_domains() {
+        let domains = EmailDomains::new(
+            [" Mailinator.com", "@10minutemail.com", ""],
+            None::<[&str; 0]>,
+        );
+
+        for address in [
+            "user@mailinator.com",
+            "user@MAILINATOR.COM",
+            "user@eu.mailinator.com",
+            "user@10minutemail.com",
+        ] {
+            let error = domains.check(&email(address)).unwrap_err();
+            assert_eq!(error.code(), "email_domain", "{address}");
+        }
+
+        // Only the domain and its subdomains are covered
+        assert!(domains.check(&email("user@notmailinator.com")).is_ok());
+    }
+
+    #[test]
+    fn allowlist_rejects_everything_else() {
+        let domains = EmailDomains::new(["contractor.acme.com"], Some(["acme.com"]));
+
+        assert!(domains.check(&email("user@acme.com")).is_ok());
+        assert!(domains.check(&email("user@eu.acme.com")).is_ok());
+        assert!(domains.check(&email("user@contractor.acme.com")).is_err());
+        assert!(domains.check(&email("user@example.com")).is_err());
+        assert!(domains.check(&email("user@acme.com.evil.com")).is_err());
+    }
+}
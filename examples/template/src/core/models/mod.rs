@@ -1,2 +1,3 @@
+pub mod email;
 pub mod session;
 pub mod user;
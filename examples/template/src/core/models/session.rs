@@ -1,4 +1,4 @@
-use chrono::{NaiveDateTime, Utc};
+use chrono::{Duration, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -37,6 +37,142 @@ impl Session {
     }
 }
 
+/// Position of the last session on a page, for keyset pagination over sessions ordered by creation time.
+/// Sessions created in the same instant are ordered by their ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionCursor {
+    #[serde(with = "ts_datetime")]
+    pub created_at: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl From<&Session> for SessionCursor {
+    fn from(session: &Session) -> Self {
+        Self {
+            created_at: session.created_at,
+            id: session.id,
+        }
+    }
+}
+
+/// A page of sessions, along with the cursor for the next one if the page is full.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionPage {
+    pub sessions: Vec<Session>,
+    pub next: Option<SessionCursor>,
+}
+
+impl SessionPage {
+    pub fn new(sessions: Vec<Session>, limit: u64) -> Self {
+        let next = (sessions.len() as u64 == limit)
+            .then(|| sessions.last().map(SessionCursor::from))
+            .flatten();
+        Self { sessions, next }
+    }
+}
+
+impl Session {
+    /// Issue a new CSRF token for the session, invalidating the previous one.
+    pub fn rotate_csrf(mut self, now: NaiveDateTime) -> Self {
+        self.csrf = Uuid::new_v4();
+        self.updated_at = now;
+        self
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Extend the session according to the policy. Sessions that already expired or never expire are returned as is.
+    ///
+    /// If the session has lived past the policy's absolute timeout it gets expired at `now` instead,
+    /// otherwise when the policy is sliding, its expiration is pushed to `now + idle_timeout`, capped at the absolute timeout.
+    pub fn refresh(mut self, policy: &SessionPolicy, now: NaiveDateTime) -> Self {
+        if self.is_expired(now) || self.expires_at == NaiveDateTime::MAX {
+            return self;
+        }
+
+        let cap = self.created_at + policy.absolute_timeout;
+
+        if now >= cap {
+            self.expires_at = now;
+            self.updated_at = now;
+            return self;
+        }
+
+        if policy.sliding {
+            self.expires_at = (now + policy.idle_timeout).min(cap);
+            self.updated_at = now;
+        }
+
+        self
+    }
+}
+
+/// Determines how long sessions are kept alive when refreshed and how many a user can have.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionPolicy {
+    /// Whether activity extends the session by `idle_timeout`
+    pub sliding: bool,
+    /// How long a session lives without activity
+    pub idle_timeout: Duration,
+    /// How long a session can live in total, counting from its creation, regardless of activity
+    pub absolute_timeout: Duration,
+    /// How many active sessions a user can have, unlimited if `None`
+    pub max_active: Option<usize>,
+    /// What happens when a session is created for a user who reached `max_active`
+    pub on_limit: SessionLimit,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            sliding: true,
+            idle_timeout: Duration::minutes(30),
+            absolute_timeout: Duration::seconds(SESSION_DURATION),
+            max_active: None,
+            on_limit: SessionLimit::EvictOldest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLimit {
+    /// Expire the user's oldest sessions to make room for the new one
+    EvictOldest,
+    /// Refuse to create the new session
+    Reject,
+}
+
+impl SessionPolicy {
+    /// Decide which of the user's `active` sessions must be expired before creating a new one.
+    /// Errors with the limit if the policy rejects new sessions and the limit is reached.
+    pub fn evictions(&self, active: &[Session]) -> Result<Vec<Uuid>, usize> {
+        let Some(max) = self.max_active else {
+            return Ok(vec![]);
+        };
+
+        // Room for the new session
+        let excess = (active.len() + 1).saturating_sub(max.max(1));
+        if excess == 0 {
+            return Ok(vec![]);
+        }
+
+        match self.on_limit {
+            SessionLimit::Reject => Err(max),
+            SessionLimit::EvictOldest => {
+                let mut oldest = active.iter().collect::<Vec<_>>();
+                oldest.sort_by_key(|session| session.created_at);
+                Ok(oldest
+                    .into_iter()
+                    .take(excess)
+                    .map(|session| session.id)
+                    .collect())
+            }
+        }
+    }
+}
+
 /// Serde utility for serializing `NaiveDateTime`s to timestamps and vice versa.
 mod ts_datetime {
     use chrono::NaiveDateTime;
@@ -113,3 +249,122 @@ impl From<Session> for crate::db::entities::sessions::ActiveModel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(created_at: NaiveDateTime) -> Session {
+        Session {
+            created_at,
+            updated_at: created_at,
+            expires_at: created_at + Duration::minutes(30),
+            ..Session::new(Uuid::new_v4(), true)
+        }
+    }
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::from_timestamp_opt(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn sliding_extension() {
+        let policy = SessionPolicy::default();
+        let session = session(at(0));
+
+        let refreshed = session.clone().refresh(&policy, at(20 * 60));
+        assert_eq!(refreshed.expires_at, at(50 * 60));
+        assert_eq!(refreshed.updated_at, at(20 * 60));
+
+        let fixed = SessionPolicy {
+            sliding: false,
+            ..policy
+        };
+        let refreshed = session.refresh(&fixed, at(20 * 60));
+        assert_eq!(refreshed.expires_at, at(30 * 60));
+    }
+
+    #[test]
+    fn csrf_rotation() {
+        let session = session(at(0));
+        let previous = session.csrf;
+
+        let rotated = session.rotate_csrf(at(60));
+        assert_ne!(rotated.csrf, previous);
+        assert_eq!(rotated.updated_at, at(60));
+    }
+
+    #[test]
+    fn absolute_cap() {
+        let policy = SessionPolicy {
+            sliding: true,
+            idle_timeout: Duration::minutes(30),
+            absolute_timeout: Duration::minutes(60),
+            ..Default::default()
+        };
+
+        let mut session = session(at(0));
+        session.expires_at = at(55 * 60);
+
+        // Extended only up to the cap
+        let session = session.refresh(&policy, at(50 * 60));
+        assert_eq!(session.expires_at, at(60 * 60));
+        assert!(!session.is_expired(at(59 * 60)));
+
+        // Past the cap the session gets expired instead of extended
+        let mut session = session;
+        session.expires_at = at(90 * 60);
+        let session = session.refresh(&policy, at(61 * 60));
+        assert_eq!(session.expires_at, at(61 * 60));
+        assert!(session.is_expired(at(61 * 60)));
+    }
+
+    #[test]
+    fn evicts_oldest_sessions() {
+        let policy = SessionPolicy {
+            max_active: Some(3),
+            on_limit: SessionLimit::EvictOldest,
+            ..Default::default()
+        };
+        let active = [session(at(30)), session(at(10)), session(at(20))];
+
+        assert_eq!(policy.evictions(&active[..2]).unwrap(), Vec::<Uuid>::new());
+        assert_eq!(policy.evictions(&active).unwrap(), [active[1].id]);
+
+        // Lowering the limit evicts as many as needed
+        let policy = SessionPolicy {
+            max_active: Some(1),
+            ..policy
+        };
+        assert_eq!(
+            policy.evictions(&active).unwrap(),
+            [active[1].id, active[2].id, active[0].id]
+        );
+    }
+
+    #[test]
+    fn rejects_at_limit() {
+        let policy = SessionPolicy {
+            max_active: Some(2),
+            on_limit: SessionLimit::Reject,
+            ..Default::default()
+        };
+        let active = [session(at(10)), session(at(20))];
+
+        assert_eq!(policy.evictions(&active[..1]).unwrap(), Vec::<Uuid>::new());
+        assert_eq!(policy.evictions(&active), Err(2));
+
+        let unlimited = SessionPolicy::default();
+        assert_eq!(unlimited.evictions(&active).unwrap(), Vec::<Uuid>::new());
+    }
+
+    #[test]
+    fn next_cursor_only_for_full_pages() {
+        let sessions = vec![session(at(10)), session(at(20))];
+        let last = SessionCursor::from(&sessions[1]);
+
+        assert_eq!(SessionPage::new(sessions.clone(), 2).next, Some(last));
+        assert_eq!(SessionPage::new(sessions, 3).next, None);
+        assert_eq!(SessionPage::new(vec![], 3).next, None);
+    }
+}
@@ -8,6 +8,10 @@ pub struct User {
     pub username: String,
     #[serde(skip_serializing)]
     pub password: String,
+    /// Normalized, see [Email][super::email::Email]. Optional for accounts created without one.
+    pub email: Option<String>,
+    #[serde(skip_serializing)]
+    pub failed_logins: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -18,18 +22,52 @@ impl User {
             id: Uuid::new_v4(),
             username,
             password,
+            email: None,
+            failed_logins: 0,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 }
 
+/// Number of rows removed from each table when hard-deleting a user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DeletedUser {
+    pub sessions: u64,
+    pub users: u64,
+}
+
+/// Counter columns on the user row which can be incremented atomically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserCounter {
+    FailedLogins,
+}
+
+/// Columns users can be listed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserSortBy {
+    #[default]
+    CreatedAt,
+    Username,
+    FailedLogins,
+}
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
 impl From<crate::db::entities::users::Model> for User {
     fn from(
         crate::db::entities::users::Model {
             id,
             username,
             password,
+            email,
+            failed_logins,
             created_at,
             updated_at,
         }: crate::db::entities::users::Model,
@@ -38,6 +76,8 @@ impl From<crate::db::entities::users::Model> for User {
             id,
             username,
             password,
+            email,
+            failed_logins,
             created_at: created_at.into(),
             updated_at: updated_at.into(),
         }
@@ -50,6 +90,8 @@ impl From<User> for crate::db::entities::users::ActiveModel {
             id,
             username,
             password,
+            email,
+            failed_logins,
             created_at,
             updated_at,
         }: User,
@@ -58,6 +100,8 @@ impl From<User> for crate::db::entities::users::ActiveModel {
             id: sea_orm::Set(id),
             username: sea_orm::Set(username),
             password: sea_orm::Set(password),
+            email: sea_orm::Set(email),
+            failed_logins: sea_orm::Set(failed_logins),
             created_at: sea_orm::Set(created_at.into()),
             updated_at: sea_orm::Set(updated_at.into()),
         }
@@ -0,0 +1,336 @@
+use super::{
+    auth::AuthenticationError,
+    models::{
+        email::Email,
+        session::{Session, SessionPolicy},
+    },
+    repository::{session::SessionRepository, user::UserRepository},
+};
+use crate::{error::Error, AppResult};
+use hextacy::adapters::cache::SimpleCacheAccess;
+use hextacy::adapters::email::{RecipientInfo, SimpleTemplateMailer, TemplateMailerError};
+use hextacy::retry::jittered;
+use hextacy::Driver;
+use rand::{distributions::Alphanumeric, Rng};
+use std::time::Duration;
+use uuid::Uuid;
+
+const TOKEN_KEY: &str = "password_reset:token";
+const THROTTLE_KEY: &str = "password_reset:throttle";
+
+/// Delivers password reset tokens.
+pub trait ResetMailer {
+    fn send_reset(&self, to: &Email, token: &str) -> Result<(), TemplateMailerError>;
+}
+
+/// Sends the `password_reset` template, which receives the token in its `token` placeholder.
+impl ResetMailer for SimpleTemplateMailer {
+    fn send_reset(&self, to: &Email, token: &str) -> Result<(), TemplateMailerError> {
+        self.send(
+            "password_reset",
+            RecipientInfo::new(to.to_string(), to.to_string()),
+            Some(&[("token", token)]),
+            "Password reset",
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ResetPolicy {
+    /// How long a token stays valid.
+    pub token_ttl: Duration,
+    /// How many resets can be requested for an email within `window`.
+    pub max_requests: i64,
+    /// Restarts on every request, so an email stays throttled while requests keep coming.
+    pub window: Duration,
+    /// Every restart of the window is randomly moved by up to this much in either direction,
+    /// so its expiry can't be timed.
+    pub jitter: Duration,
+}
+
+impl ResetPolicy {
+    /// The window with [jitter][ResetPolicy::jitter] applied.
+    pub fn throttle_ttl(&self) -> Duration {
+        jittered(self.window, self.jitter)
+    }
+}
+
+impl Default for ResetPolicy {
+    fn default() -> Self {
+        Self {
+            token_ttl: Duration::from_secs(30 * 60),
+            max_requests: 3,
+            window: Duration::from_secs(60 * 60),
+            jitter: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Password resets through single use tokens sent by email.
+#[derive(Debug, Clone)]
+pub struct PasswordReset<U, S, C, M> {
+    pub user_repo: U,
+    pub session_repo: S,
+    pub cache: C,
+    pub mailer: M,
+    pub policy: ResetPolicy,
+}
+
+impl<U, S, C, M> PasswordReset<U, S, C, M>
+where
+    U: UserRepository,
+    S: SessionRepository,
+    C: Driver,
+    C::Connection: SimpleCacheAccess,
+    C::Error: Into<Error>,
+    M: ResetMailer,
+{
+    /// Send a reset token to the email if it belongs to a user.
+    ///
+    /// Unknown emails succeed without sending anything so the endpoint cannot be used to probe
+    /// for registered addresses. Requests above the policy's limit are rejected either way.
+    pub async fn request(&self, email: &Email) -> AppResult<()> {
+        let mut cache = self.cache.connect().await.map_err(Into::into)?;
+
+        let requests = cache
+            .set_or_increment(
+                &format!("{THROTTLE_KEY}:{email}"),
+                Some(self.policy.throttle_ttl().as_secs() as usize),
+            )
+            .await?;
+        if requests > self.policy.max_requests {
+            return Err(AuthenticationError::TooManyResetRequests.into());
+        }
+
+        let Some(user) = self.user_repo.get_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let token = rand::thread_rng()
+            .sample_iter(Alphanumeric)
+            .take(48)
+            .map(char::from)
+            .collect::<String>();
+
+        cache
+            .set_str(
+                &format!("{TOKEN_KEY}:{token}"),
+                &user.id.to_string(),
+                Some(self.policy.token_ttl.as_secs() as usize),
+            )
+            .await?;
+
+        self.mailer.send_reset(email, &token)?;
+
+        Ok(())
+    }
+
+    /// Consume the token and set the user's new password. Every existing session of the user is
+    /// expired and a fresh one is returned in their place.
+    pub async fn complete(&self, token: &str, new_password: &str) -> AppResult<Session> {
+        let mut cache = self.cache.connect().await.map_err(Into::into)?;
+        let key = format!("{TOKEN_KEY}:{token}");
+
+        let Some(user_id) = cache.get_string(&key).await? else {
+            return Err(AuthenticationError::InvalidResetToken.into());
+        };
+
+        // Of concurrent completions with the same token, only the one deleting it gets through
+        if !cache.delete_if_eq(&key, &user_id).await? {
+            return Err(AuthenticationError::InvalidResetToken.into());
+        }
+
+        let Some(user) = self.user_repo.get_by_id(Uuid::parse_str(&user_id)?).await? else {
+            return Err(AuthenticationError::InvalidResetToken.into());
+        };
+
+        let hashed = hextacy::crypto::bcrypt_hash(new_password, 10)?;
+        self.user_repo.update_password(user.id, &hashed).await?;
+
+        self.session_repo.purge(user.id).await?;
+        let session = self
+            .session_repo
+            .create(&user, true, &SessionPolicy::default())
+            .await?;
+
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        models::user::User,
+        repository::{session::MockSessionRepository, user::MockUserRepository},
+        tests::ready,
+    };
+    use hextacy::adapters::cache::in_mem::MemoryCache;
+    use mockall::Sequence;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, Default)]
+    struct Outbox(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl ResetMailer for Outbox {
+        fn send_reset(&self, to: &Email, token: &str) -> Result<(), TemplateMailerError> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((to.to_string(), token.to_string()));
+            Ok(())
+        }
+    }
+
+    type Service = PasswordReset<MockUserRepository, MockSessionRepository, MemoryCache, Outbox>;
+
+    fn user() -> User {
+        let password = hextacy::crypto::bcrypt_hash("password", 4).unwrap();
+        let mut user = User::new("user".to_string(), password);
+        user.email = Some("user@example.com".to_string());
+        user
+    }
+
+    /// Finds `user` by its email and ID, any other email is unknown.
+    fn users(user: &User) -> MockUserRepository {
+        let mut users = MockUserRepository::new();
+        users.expect_get_by_email().returning({
+            let user = user.clone();
+            move |email| {
+                let found = user.email.as_deref() == Some(email.as_str());
+                ready(Ok(found.then(|| user.clone())))
+            }
+        });
+        users.expect_get_by_id().returning({
+            let user = user.clone();
+            move |id| ready(Ok((user.id == id).then(|| user.clone())))
+        });
+        users
+    }
+
+    /// Expects a single completed reset setting `password` for `user`, which expires its sessions
+    /// before creating the new one.
+    fn complete_mocks(
+        user: &User,
+        password: &'static str,
+    ) -> (MockUserRepository, MockSessionRepository) {
+        let mut users = users(user);
+        let id = user.id;
+        users
+            .expect_update_password()
+            .withf(move |user_id, hash| {
+                *user_id == id && hextacy::crypto::bcrypt_verify(password, hash).unwrap()
+            })
+            .times(1)
+            .returning(|_, _| ready(Ok(())));
+
+        let mut seq = Sequence::new();
+        let mut sessions = MockSessionRepository::new();
+        sessions
+            .expect_purge()
+            .withf(move |user_id| *user_id == id)
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(1));
+        sessions
+            .expect_create()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|user, expires, _| Ok(Session::new(user.id, expires)));
+
+        (users, sessions)
+    }
+
+    fn service(user_repo: MockUserRepository, session_repo: MockSessionRepository) -> Service {
+        PasswordReset {
+            user_repo,
+            session_repo,
+            cache: MemoryCache::new(),
+            mailer: Outbox::default(),
+            policy: ResetPolicy::default(),
+        }
+    }
+
+    fn last_token(service: &Service) -> String {
+        service.mailer.0.lock().unwrap().last().unwrap().1.clone()
+    }
+
+    fn email() -> Email {
+        Email::parse("user@example.com").unwrap()
+    }
+
+    #[tokio::test]
+    async fn reset_sets_password_and_rotates_sessions() {
+        let user = user();
+        let (users, sessions) = complete_mocks(&user, "new password");
+        let service = service(users, sessions);
+
+        service.request(&email()).await.unwrap();
+        let token = last_token(&service);
+
+        let session = service.complete(&token, "new password").await.unwrap();
+        assert_eq!(session.user_id, user.id);
+    }
+
+    #[tokio::test]
+    async fn token_is_single_use() {
+        // Only the first completion reaches the repositories
+        let (users, sessions) = complete_mocks(&user(), "new password");
+        let service = service(users, sessions);
+
+        service.request(&email()).await.unwrap();
+        let token = last_token(&service);
+
+        service.complete(&token, "new password").await.unwrap();
+        let reused = service.complete(&token, "other password").await;
+        assert!(matches!(
+            reused,
+            Err(Error::Auth(AuthenticationError::InvalidResetToken))
+        ));
+
+        let unknown = service.complete("nope", "other password").await;
+        assert!(matches!(
+            unknown,
+            Err(Error::Auth(AuthenticationError::InvalidResetToken))
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_are_throttled_per_email() {
+        let service = service(users(&user()), MockSessionRepository::new());
+        let email = email();
+
+        for _ in 0..service.policy.max_requests {
+            service.request(&email).await.unwrap();
+        }
+        let throttled = service.request(&email).await;
+        assert!(matches!(
+            throttled,
+            Err(Error::Auth(AuthenticationError::TooManyResetRequests))
+        ));
+        assert_eq!(
+            service.mailer.0.lock().unwrap().len() as i64,
+            service.policy.max_requests
+        );
+
+        // Unknown emails are throttled separately and silently send nothing
+        let unknown = Email::parse("nobody@example.com").unwrap();
+        service.request(&unknown).await.unwrap();
+        assert_eq!(
+            service.mailer.0.lock().unwrap().len() as i64,
+            service.policy.max_requests
+        );
+    }
+
+    #[test]
+    fn throttle_ttls_vary() {
+        let policy = ResetPolicy::default();
+        let ttls = (0..20)
+            .map(|_| policy.throttle_ttl())
+            .collect::<std::collections::HashSet<_>>();
+        assert!(ttls.len() > 1);
+        assert!(ttls
+            .iter()
+            .all(|ttl| ttl.abs_diff(policy.window) <= policy.jitter));
+    }
+}
@@ -1,14 +1,41 @@
 use crate::{
-    core::models::{session::Session, user::User},
+    core::models::{
+        session::{Session, SessionCursor, SessionPage, SessionPolicy},
+        user::User,
+    },
     db::adapters::AdapterError,
 };
 use async_trait::async_trait;
 use hextacy::Driver;
 use uuid::Uuid;
 
+#[cfg_attr(test, mockall::automock)]
 pub trait SessionRepository {
     async fn get_valid_by_id(&self, id: Uuid, csrf: Uuid) -> Result<Option<Session>, AdapterError>;
-    async fn create(&self, user: &User, expires: bool) -> Result<Session, AdapterError>;
+    /// Creates a session for the user, first enforcing the policy's limit on active sessions.
+    /// Errors with [SessionLimitReached][AdapterError::SessionLimitReached] if the policy rejects it.
+    async fn create(
+        &self,
+        user: &User,
+        expires: bool,
+        policy: &SessionPolicy,
+    ) -> Result<Session, AdapterError>;
+    /// Extends the session according to the policy, expiring it if it cannot be extended.
+    async fn refresh(
+        &self,
+        session: Session,
+        policy: &SessionPolicy,
+    ) -> Result<Session, AdapterError>;
+    /// Issues a new CSRF token for the session. The previous token no longer validates.
+    async fn rotate_csrf(&self, id: Uuid) -> Result<Session, AdapterError>;
     async fn expire(&self, id: Uuid) -> Result<Session, AdapterError>;
     async fn purge(&self, user_id: Uuid) -> Result<u64, AdapterError>;
+    /// A page of the user's sessions, including expired ones, ordered by creation time and starting after `after`.
+    /// Seeks past the cursor instead of using an offset, so pages deep into the history are as fast as the first.
+    async fn list_after(
+        &self,
+        user_id: Uuid,
+        after: Option<SessionCursor>,
+        limit: u64,
+    ) -> Result<SessionPage, AdapterError>;
 }
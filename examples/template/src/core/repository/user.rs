@@ -1,10 +1,15 @@
 use crate::{
-    core::models::{session::Session, user::User},
+    core::models::{
+        email::Email,
+        session::Session,
+        user::{DeletedUser, SortOrder, User, UserCounter, UserSortBy},
+    },
     db::adapters::AdapterError,
 };
 use std::future::Future;
 use uuid::Uuid;
 
+#[cfg_attr(test, mockall::automock)]
 pub trait UserRepository {
     fn get_by_id(
         &self,
@@ -16,6 +21,50 @@ pub trait UserRepository {
         username: &str,
     ) -> impl Future<Output = Result<Option<User>, AdapterError>> + Send;
 
+    fn get_by_email(
+        &self,
+        email: &Email,
+    ) -> impl Future<Output = Result<Option<User>, AdapterError>> + Send;
+
+    /// Replace the user's password hash.
+    fn update_password(
+        &self,
+        id: Uuid,
+        password: &str,
+    ) -> impl Future<Output = Result<(), AdapterError>> + Send;
+
+    /// Fetch the user along with its sessions which have not expired, as of a single snapshot.
+    /// Errors with [RecordNotFound][sea_orm::DbErr::RecordNotFound] when the user does not exist.
+    async fn get_user_with_sessions(&self, id: Uuid) -> Result<(User, Vec<Session>), AdapterError>;
+
+    /// Check whether the user exists without fetching it.
+    fn exists_by_id(&self, id: Uuid) -> impl Future<Output = Result<bool, AdapterError>> + Send;
+
+    /// Check whether a user with the username exists without fetching it.
+    fn exists_by_username(
+        &self,
+        username: &str,
+    ) -> impl Future<Output = Result<bool, AdapterError>> + Send;
+
+    /// Add `by` to the counter in a single statement and return its new value, so concurrent
+    /// increments cannot overwrite each other.
+    fn increment_counter(
+        &self,
+        id: Uuid,
+        counter: UserCounter,
+        by: i64,
+    ) -> impl Future<Output = Result<i64, AdapterError>> + Send;
+
+    /// A page of users sorted by `sort_by`. Users sharing a value are always returned in the same
+    /// order, so consecutive pages never repeat or skip a user.
+    fn list(
+        &self,
+        sort_by: UserSortBy,
+        order: SortOrder,
+        offset: u64,
+        limit: u64,
+    ) -> impl Future<Output = Result<Vec<User>, AdapterError>> + Send;
+
     fn create(
         &self,
         username: &str,
@@ -28,4 +77,8 @@ pub trait UserRepository {
         password: &str,
         expires: bool,
     ) -> Result<(User, Session), AdapterError>;
+
+    /// Permanently delete the user and every row that references it in a single transaction,
+    /// so either everything or nothing is deleted.
+    async fn delete_user_cascade(&self, user_id: Uuid) -> Result<DeletedUser, AdapterError>;
 }
@@ -3,18 +3,30 @@ pub mod integration_tests {
 
     use suitest::*;
 
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
-    use hextacy::Driver;
+    use hextacy::{transaction, Driver};
+    use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+    use tokio::sync::oneshot;
 
     use crate::{
         config::state::{AppState, AuthenticationService},
-        core::models::user::User,
+        core::{
+            models::{
+                email::EmailDomains,
+                session::{SessionCursor, SessionLimit, SessionPolicy},
+                user::{DeletedUser, SortOrder, User, UserCounter, UserSortBy},
+            },
+            repository::{session::SessionRepository, user::UserRepository},
+        },
         db::{
-            adapters::{session::SessionAdapter, user::UserAdapter},
+            adapters::{session::SessionAdapter, user::UserAdapter, AdapterError},
             driver::SeaormDriver,
             entities::sessions::Model as SessionModel,
-            entities::users::{ActiveModel as ActiveUserModel, Model as UserModel},
+            entities::users::{
+                ActiveModel as ActiveUserModel, Column as UserColumn, Entity as UserEntity,
+                Model as UserModel,
+            },
         },
     };
 
@@ -36,6 +48,8 @@ pub mod integration_tests {
                     .publisher("my-channel")
                     .await
                     .expect("Could not create publisher"),
+                session_policy: SessionPolicy::default(),
+                email_domains: EmailDomains::default(),
             },
         )
     }
@@ -84,7 +98,7 @@ pub mod integration_tests {
 
     #[test]
     async fn registration(driver: SeaormDriver, service: AuthenticationService) {
-        let (user, session) = service.register("fooser", "passbar").await.unwrap();
+        let (user, session) = service.register("fooser", None, "passbar").await.unwrap();
 
         let conn = driver.connect().await.unwrap();
         let user = driver
@@ -107,4 +121,348 @@ pub mod integration_tests {
             .unwrap();
         assert_eq!(count, 0);
     }
+
+    #[test]
+    async fn delete_user_cascade(driver: SeaormDriver) {
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+        let sessions = SessionAdapter {
+            driver: driver.clone(),
+        };
+
+        let (user, session) = users
+            .insert_with_session("gdpr", "passbar", true)
+            .await
+            .unwrap();
+        let other = sessions
+            .create(&user, false, &SessionPolicy::default())
+            .await
+            .unwrap();
+
+        let deleted = users.delete_user_cascade(user.id).await.unwrap();
+        assert_eq!(
+            deleted,
+            DeletedUser {
+                sessions: 2,
+                users: 1
+            }
+        );
+
+        let conn = driver.connect().await.unwrap();
+        assert!(driver
+            .get_by_id::<User, UserModel, _, _, _>(&conn, user.id)
+            .await
+            .unwrap()
+            .is_none());
+        for id in [session.id, other.id] {
+            assert!(driver
+                .get_by_id::<crate::core::models::session::Session, SessionModel, _, _, _>(
+                    &conn, id
+                )
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        // Nothing left to delete
+        let deleted = users.delete_user_cascade(user.id).await.unwrap();
+        assert_eq!(deleted, DeletedUser::default());
+    }
+
+    #[test]
+    async fn user_with_active_sessions(driver: SeaormDriver, user: User) {
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+        let sessions = SessionAdapter {
+            driver: driver.clone(),
+        };
+
+        let active = sessions
+            .create(&user, true, &SessionPolicy::default())
+            .await
+            .unwrap();
+        let permanent = sessions
+            .create(&user, false, &SessionPolicy::default())
+            .await
+            .unwrap();
+        let expired = sessions
+            .create(&user, true, &SessionPolicy::default())
+            .await
+            .unwrap();
+        sessions.expire(expired.id).await.unwrap();
+
+        let (fetched, fetched_sessions) = users.get_user_with_sessions(user.id).await.unwrap();
+        assert_eq!(fetched.id, user.id);
+
+        let mut ids = fetched_sessions.iter().map(|s| s.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        let mut expected = vec![active.id, permanent.id];
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+
+        assert!(matches!(
+            users.get_user_with_sessions(uuid::Uuid::new_v4()).await,
+            Err(AdapterError::SeaORM(sea_orm::DbErr::RecordNotFound(_)))
+        ));
+
+        let conn = driver.connect().await.unwrap();
+        for id in [active.id, permanent.id, expired.id] {
+            driver
+                .delete::<SessionModel, _, _, _>(&conn, id)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test]
+    async fn session_limit(driver: SeaormDriver, user: User) {
+        let sessions = SessionAdapter {
+            driver: driver.clone(),
+        };
+        let evict = SessionPolicy {
+            max_active: Some(2),
+            on_limit: SessionLimit::EvictOldest,
+            ..Default::default()
+        };
+        let reject = SessionPolicy {
+            on_limit: SessionLimit::Reject,
+            ..evict
+        };
+
+        let oldest = sessions.create(&user, true, &evict).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let newer = sessions.create(&user, true, &evict).await.unwrap();
+
+        assert!(matches!(
+            sessions.create(&user, true, &reject).await,
+            Err(AdapterError::SessionLimitReached(2))
+        ));
+
+        let newest = sessions.create(&user, true, &evict).await.unwrap();
+        let (_, active) = UserAdapter {
+            driver: driver.clone(),
+        }
+        .get_user_with_sessions(user.id)
+        .await
+        .unwrap();
+        let mut ids = active.iter().map(|s| s.id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        let mut expected = vec![newer.id, newest.id];
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+
+        let conn = driver.connect().await.unwrap();
+        for id in [oldest.id, newer.id, newest.id] {
+            driver
+                .delete::<SessionModel, _, _, _>(&conn, id)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test]
+    async fn session_keyset_pagination(driver: SeaormDriver, user: User) {
+        let sessions = SessionAdapter {
+            driver: driver.clone(),
+        };
+
+        // Sessions are created with second precision, so most of them share a timestamp
+        let mut created = std::collections::HashSet::new();
+        for i in 0..11 {
+            if i % 4 == 0 {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            let session = sessions
+                .create(&user, true, &SessionPolicy::default())
+                .await
+                .unwrap();
+            created.insert(session.id);
+        }
+
+        let mut seen = vec![];
+        let mut cursor: Option<SessionCursor> = None;
+        loop {
+            let page = sessions.list_after(user.id, cursor, 3).await.unwrap();
+            assert!(page.sessions.len() <= 3);
+            seen.extend(page.sessions.iter().map(|s| (s.created_at, s.id)));
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let mut sorted = seen.clone();
+        sorted.sort();
+        assert_eq!(seen, sorted, "pages are out of order");
+
+        let unique = seen
+            .iter()
+            .map(|(_, id)| *id)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(
+            unique.len(),
+            seen.len(),
+            "a session was repeated across pages"
+        );
+        assert_eq!(unique, created, "a session was skipped");
+
+        let conn = driver.connect().await.unwrap();
+        for id in created {
+            driver
+                .delete::<SessionModel, _, _, _>(&conn, id)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test]
+    async fn existence(driver: SeaormDriver, user: User) {
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+
+        assert!(users.exists_by_username(&user.username).await.unwrap());
+        assert!(users.exists_by_id(user.id).await.unwrap());
+
+        assert!(!users.exists_by_username("nobody").await.unwrap());
+        assert!(!users.exists_by_id(uuid::Uuid::new_v4()).await.unwrap());
+    }
+
+    #[test]
+    async fn concurrent_counter_increments(driver: SeaormDriver, user: User) {
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+
+        let increments = (0..20).map(|_| {
+            let users = users.clone();
+            let id = user.id;
+            tokio::spawn(async move {
+                users
+                    .increment_counter(id, UserCounter::FailedLogins, 2)
+                    .await
+            })
+        });
+
+        let mut values = vec![];
+        for increment in increments {
+            values.push(increment.await.unwrap().unwrap());
+        }
+
+        // Every increment observed a distinct value and none were lost
+        values.sort_unstable();
+        assert_eq!(values, (1..=20).map(|i| i * 2).collect::<Vec<_>>());
+
+        let user = users.get_by_id(user.id).await.unwrap().unwrap();
+        assert_eq!(user.failed_logins, 40);
+
+        assert!(users
+            .increment_counter(uuid::Uuid::new_v4(), UserCounter::FailedLogins, 1)
+            .await
+            .is_err());
+    }
+
+    /// Locks the user's row, signals once it holds the lock and increments the failed logins
+    /// after `hold`, committing on return.
+    async fn increment_locked(
+        driver: SeaormDriver,
+        id: uuid::Uuid,
+        hold: Duration,
+        locked: Option<oneshot::Sender<()>>,
+    ) -> Result<User, AdapterError> {
+        let conn = driver.connect().await?;
+        let user = transaction!(
+            conn: DatabaseConnection => {
+                let user = UserAdapter::get_by_id_for_update(&conn, id)
+                    .await?
+                    .expect("user exists");
+                if let Some(locked) = locked {
+                    locked.send(()).unwrap();
+                }
+                tokio::time::sleep(hold).await;
+                UserEntity::update_many()
+                    .col_expr(UserColumn::FailedLogins, Expr::value(user.failed_logins + 1))
+                    .filter(UserColumn::Id.eq(id))
+                    .exec(&conn)
+                    .await?;
+                Ok(user)
+            }
+        )?;
+        Ok(user)
+    }
+
+    #[test]
+    async fn row_lock_blocks_until_commit(driver: SeaormDriver, user: User) {
+        let (locked_tx, locked_rx) = oneshot::channel();
+        let first = tokio::spawn(increment_locked(
+            driver.clone(),
+            user.id,
+            Duration::from_millis(500),
+            Some(locked_tx),
+        ));
+        locked_rx.await.unwrap();
+
+        let started = Instant::now();
+        let seen = increment_locked(driver.clone(), user.id, Duration::ZERO, None)
+            .await
+            .unwrap();
+
+        // The second transaction waited for the first to commit and read its write
+        assert!(started.elapsed() >= Duration::from_millis(400));
+        assert_eq!(first.await.unwrap().unwrap().failed_logins, 0);
+        assert_eq!(seen.failed_logins, 1);
+
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+        let user = users.get_by_id(user.id).await.unwrap().unwrap();
+        assert_eq!(user.failed_logins, 2);
+    }
+
+    #[test]
+    async fn pagination_is_deterministic(driver: SeaormDriver) {
+        let users = UserAdapter {
+            driver: driver.clone(),
+        };
+
+        // Every user has the same amount of failed logins
+        let mut created = std::collections::HashSet::new();
+        for i in 0..23 {
+            let user = users.create(&format!("paged{i}"), "passbar").await.unwrap();
+            created.insert(user.id);
+        }
+
+        for order in [SortOrder::Asc, SortOrder::Desc] {
+            let mut seen = vec![];
+            let mut offset = 0;
+            loop {
+                let page = users
+                    .list(UserSortBy::FailedLogins, order, offset, 5)
+                    .await
+                    .unwrap();
+                if page.is_empty() {
+                    break;
+                }
+                offset += page.len() as u64;
+                seen.extend(page.into_iter().map(|user| user.id));
+            }
+
+            let unique = seen.iter().collect::<std::collections::HashSet<_>>();
+            assert_eq!(unique.len(), seen.len(), "a user was repeated across pages");
+            assert!(
+                created.iter().all(|id| unique.contains(id)),
+                "a user was skipped"
+            );
+        }
+
+        let conn = driver.connect().await.unwrap();
+        for id in created {
+            driver
+                .delete::<UserModel, _, _, _>(&conn, id)
+                .await
+                .unwrap();
+        }
+    }
 }
@@ -1,2 +1,11 @@
 #[cfg(test)]
 pub mod auth;
+
+#[cfg(test)]
+use std::{future::Future, pin::Pin};
+
+/// The boxed future the repository mocks return from methods declared as returning `impl Future`.
+#[cfg(test)]
+pub fn ready<T: Send + 'static>(value: T) -> Pin<Box<dyn Future<Output = T> + Send>> {
+    Box::pin(std::future::ready(value))
+}
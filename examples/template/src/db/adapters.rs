@@ -1,10 +1,26 @@
 pub mod session;
 pub mod user;
 
+use sea_orm::{EntityTrait, Iterable, Order, PrimaryKeyToColumn, QueryOrder, Select};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum AdapterError {
     #[error("SeaORM: {0}")]
     SeaORM(#[from] sea_orm::DbErr),
+    #[error("User reached the limit of {0} active sessions")]
+    SessionLimitReached(usize),
+}
+
+/// Orders the query by `column` followed by the entity's primary key, giving a total order.
+///
+/// Without the tiebreaker, rows sharing a value in `column` can come back in any order and
+/// offset pagination may repeat or skip them across pages.
+pub fn order_deterministic<E>(select: Select<E>, column: E::Column, order: Order) -> Select<E>
+where
+    E: EntityTrait,
+{
+    E::PrimaryKey::iter().fold(select.order_by(column, order.clone()), |select, key| {
+        select.order_by(key.into_column(), order.clone())
+    })
 }
@@ -1,16 +1,17 @@
 use super::super::entities::sessions::{
     ActiveModel as SessionModel, Column, Entity as SessionEntity,
 };
-use crate::core::models::session::Session;
+use crate::core::models::session::{Session, SessionCursor, SessionPage, SessionPolicy};
 use crate::core::models::user::User;
 use crate::core::repository::session::SessionRepository;
 use crate::db::adapters::AdapterError;
 use crate::db::driver::SeaormDriver;
 use chrono::Utc;
+use hextacy::transaction;
 use hextacy::Atomic;
 use hextacy::Driver;
 use sea_orm::prelude::*;
-use sea_orm::Set;
+use sea_orm::{Condition, QueryOrder, QuerySelect, Set};
 
 #[derive(Debug, Clone)]
 pub struct SessionAdapter {
@@ -30,14 +31,98 @@ impl SessionRepository for SessionAdapter {
             .map_err(AdapterError::SeaORM)
     }
 
-    async fn create(&self, user: &User, expires: bool) -> Result<Session, AdapterError> {
+    async fn create(
+        &self,
+        user: &User,
+        expires: bool,
+        policy: &SessionPolicy,
+    ) -> Result<Session, AdapterError> {
         let conn = self.driver.connect().await?;
         let session: SessionModel = Session::new(user.id, expires).into();
-        SessionEntity::insert(session)
-            .exec_with_returning(&conn)
-            .await
-            .map(Session::from)
-            .map_err(AdapterError::SeaORM)
+
+        if policy.max_active.is_none() {
+            return SessionEntity::insert(session)
+                .exec_with_returning(&conn)
+                .await
+                .map(Session::from)
+                .map_err(AdapterError::SeaORM);
+        }
+
+        let session = transaction!(
+            conn: DatabaseConnection => {
+                // Locking the user's sessions keeps concurrent logins from both taking the last free slot
+                let active = SessionEntity::find()
+                    .filter(Column::UserId.eq(user.id))
+                    .filter(Column::ExpiresAt.gt(Utc::now()))
+                    .lock_exclusive()
+                    .all(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?
+                    .into_iter()
+                    .map(Session::from)
+                    .collect::<Vec<_>>();
+
+                let evicted = policy
+                    .evictions(&active)
+                    .map_err(AdapterError::SessionLimitReached)?;
+
+                if !evicted.is_empty() {
+                    SessionEntity::update_many()
+                        .col_expr(Column::ExpiresAt, Expr::value(Utc::now()))
+                        .filter(Column::Id.is_in(evicted))
+                        .exec(&conn)
+                        .await
+                        .map_err(AdapterError::SeaORM)?;
+                }
+
+                let session = SessionEntity::insert(session)
+                    .exec_with_returning(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?;
+
+                Ok(Session::from(session))
+            }
+        )?;
+
+        Ok(session)
+    }
+
+    async fn refresh(
+        &self,
+        session: Session,
+        policy: &SessionPolicy,
+    ) -> Result<Session, AdapterError> {
+        let conn = self.driver.connect().await?;
+        let Session {
+            id,
+            updated_at,
+            expires_at,
+            ..
+        } = session.refresh(policy, Utc::now().naive_utc());
+        SessionModel {
+            id: Set(id),
+            updated_at: Set(updated_at.and_utc().fixed_offset()),
+            expires_at: Set(expires_at.and_utc().fixed_offset()),
+            ..Default::default()
+        }
+        .update(&conn)
+        .await
+        .map(Session::from)
+        .map_err(AdapterError::SeaORM)
+    }
+
+    async fn rotate_csrf(&self, id: Uuid) -> Result<Session, AdapterError> {
+        let conn = self.driver.connect().await?;
+        SessionModel {
+            id: Set(id),
+            csrf: Set(Uuid::new_v4()),
+            updated_at: Set(Utc::now().into()),
+            ..Default::default()
+        }
+        .update(&conn)
+        .await
+        .map(Session::from)
+        .map_err(AdapterError::SeaORM)
     }
 
     async fn expire(&self, id: Uuid) -> Result<Session, AdapterError> {
@@ -63,4 +148,38 @@ impl SessionRepository for SessionAdapter {
             .map(|res| res.rows_affected)
             .map_err(AdapterError::SeaORM)
     }
+
+    async fn list_after(
+        &self,
+        user_id: Uuid,
+        after: Option<SessionCursor>,
+        limit: u64,
+    ) -> Result<SessionPage, AdapterError> {
+        let conn = self.driver.connect().await?;
+        let mut query = SessionEntity::find().filter(Column::UserId.eq(user_id));
+
+        if let Some(SessionCursor { created_at, id }) = after {
+            let created_at = created_at.and_utc().fixed_offset();
+            query = query.filter(
+                Condition::any().add(Column::CreatedAt.gt(created_at)).add(
+                    Condition::all()
+                        .add(Column::CreatedAt.eq(created_at))
+                        .add(Column::Id.gt(id)),
+                ),
+            );
+        }
+
+        let sessions = query
+            .order_by_asc(Column::CreatedAt)
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(&conn)
+            .await
+            .map_err(AdapterError::SeaORM)?
+            .into_iter()
+            .map(Session::from)
+            .collect();
+
+        Ok(SessionPage::new(sessions, limit))
+    }
 }
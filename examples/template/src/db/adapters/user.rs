@@ -1,18 +1,22 @@
 use super::super::entities::{users::ActiveModel as UserModel, users::Entity as UserEntity};
+use crate::core::models::email::Email;
 use crate::core::models::session::Session;
-use crate::core::models::user::User;
+use crate::core::models::user::{DeletedUser, SortOrder, User, UserCounter, UserSortBy};
 use crate::core::repository::user::UserRepository;
-use crate::db::adapters::AdapterError;
+use crate::db::adapters::{order_deterministic, AdapterError};
 use crate::db::driver::SeaormDriver;
 use crate::db::entities::sessions::ActiveModel as SessionModel;
+use crate::db::entities::sessions::Column as SessionColumn;
 use crate::db::entities::sessions::Entity as SessionEntity;
 use crate::db::entities::users::Column;
 use async_trait::async_trait;
+use chrono::Utc;
 use hextacy::transaction;
 use hextacy::Atomic;
 use hextacy::Driver;
 use sea_orm::prelude::*;
-use sea_orm::ConnectionTrait;
+use sea_orm::sea_query::{Expr, Query, SelectStatement};
+use sea_orm::{ConnectionTrait, DatabaseTransaction, Order, QuerySelect};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -41,6 +45,140 @@ impl UserRepository for UserAdapter {
             .map(|user| user.map(User::from))
     }
 
+    async fn get_by_email(&self, email: &Email) -> Result<Option<User>, AdapterError> {
+        let conn = self.driver.connect().await?;
+        UserEntity::find()
+            .filter(Column::Email.eq(email.as_str()))
+            .one(&conn)
+            .await
+            .map_err(AdapterError::SeaORM)
+            .map(|user| user.map(User::from))
+    }
+
+    async fn update_password(&self, id: Uuid, password: &str) -> Result<(), AdapterError> {
+        let conn = self.driver.connect().await?;
+        let updated = UserEntity::update_many()
+            .col_expr(Column::Password, Expr::value(password))
+            .col_expr(Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(Column::Id.eq(id))
+            .exec(&conn)
+            .await?
+            .rows_affected;
+        if updated == 0 {
+            return Err(AdapterError::SeaORM(DbErr::RecordNotFound(id.to_string())));
+        }
+        Ok(())
+    }
+
+    async fn get_user_with_sessions(&self, id: Uuid) -> Result<(User, Vec<Session>), AdapterError> {
+        let conn = self.driver.connect().await?;
+
+        let (user, sessions) = transaction!(
+            conn: DatabaseConnection => {
+                let user = UserEntity::find_by_id(id)
+                    .one(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?
+                    .ok_or_else(|| AdapterError::SeaORM(DbErr::RecordNotFound(id.to_string())))?;
+
+                let sessions = SessionEntity::find()
+                    .filter(SessionColumn::UserId.eq(id))
+                    .filter(SessionColumn::ExpiresAt.gt(Utc::now()))
+                    .all(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?;
+
+                Ok((user, sessions))
+            }
+        )?;
+
+        Ok((
+            User::from(user),
+            sessions.into_iter().map(Session::from).collect(),
+        ))
+    }
+
+    async fn exists_by_id(&self, id: Uuid) -> Result<bool, AdapterError> {
+        let conn = self.driver.connect().await?;
+        exists(
+            &conn,
+            Query::select()
+                .expr(Expr::val(1))
+                .from(UserEntity)
+                .and_where(Column::Id.eq(id))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn exists_by_username(&self, username: &str) -> Result<bool, AdapterError> {
+        let conn = self.driver.connect().await?;
+        exists(
+            &conn,
+            Query::select()
+                .expr(Expr::val(1))
+                .from(UserEntity)
+                .and_where(Column::Username.eq(username))
+                .to_owned(),
+        )
+        .await
+    }
+
+    async fn increment_counter(
+        &self,
+        id: Uuid,
+        counter: UserCounter,
+        by: i64,
+    ) -> Result<i64, AdapterError> {
+        let conn = self.driver.connect().await?;
+
+        // Only the columns listed in UserCounter can end up in the statement
+        let column = match counter {
+            UserCounter::FailedLogins => Column::FailedLogins,
+        };
+
+        let stmt = Query::update()
+            .table(UserEntity)
+            .value(column, Expr::col(column).add(by))
+            .and_where(Column::Id.eq(id))
+            .returning_col(column)
+            .to_owned();
+
+        let row = conn
+            .query_one(conn.get_database_backend().build(&stmt))
+            .await?
+            .ok_or_else(|| AdapterError::SeaORM(DbErr::RecordNotFound(id.to_string())))?;
+        row.try_get_by_index::<i64>(0).map_err(AdapterError::SeaORM)
+    }
+
+    async fn list(
+        &self,
+        sort_by: UserSortBy,
+        order: SortOrder,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<User>, AdapterError> {
+        let conn = self.driver.connect().await?;
+
+        let column = match sort_by {
+            UserSortBy::CreatedAt => Column::CreatedAt,
+            UserSortBy::Username => Column::Username,
+            UserSortBy::FailedLogins => Column::FailedLogins,
+        };
+        let order = match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+
+        order_deterministic(UserEntity::find(), column, order)
+            .offset(offset)
+            .limit(limit)
+            .all(&conn)
+            .await
+            .map_err(AdapterError::SeaORM)
+            .map(|users| users.into_iter().map(User::from).collect())
+    }
+
     async fn create(&self, username: &str, password: &str) -> Result<User, AdapterError> {
         let conn = self.driver.connect().await?;
         let user: UserModel = User::new(username.to_string(), password.to_string()).into();
@@ -84,4 +222,65 @@ impl UserRepository for UserAdapter {
 
         Ok((user, session))
     }
+
+    async fn delete_user_cascade(&self, user_id: Uuid) -> Result<DeletedUser, AdapterError> {
+        let conn = self.driver.connect().await?;
+
+        let deleted = transaction!(
+            conn: DatabaseConnection => {
+                // Dependents first so nothing is left dangling if the schema does not cascade
+                let sessions = SessionEntity::delete_many()
+                    .filter(SessionColumn::UserId.eq(user_id))
+                    .exec(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?
+                    .rows_affected;
+
+                let users = UserEntity::delete_by_id(user_id)
+                    .exec(&conn)
+                    .await
+                    .map_err(AdapterError::SeaORM)?
+                    .rows_affected;
+
+                Ok(DeletedUser { sessions, users })
+            }
+        )?;
+
+        Ok(deleted)
+    }
+}
+
+impl UserAdapter {
+    /// Fetch the user with `SELECT ... FOR UPDATE`, locking its row until the transaction commits or
+    /// aborts. Concurrent locks on the same row wait for it, so the user can be read and written back
+    /// without losing updates.
+    ///
+    /// Takes the transaction instead of a connection, so it can only be called within [transaction].
+    pub async fn get_by_id_for_update(
+        tx: &DatabaseTransaction,
+        id: Uuid,
+    ) -> Result<Option<User>, AdapterError> {
+        UserEntity::find_by_id(id)
+            .lock_exclusive()
+            .one(tx)
+            .await
+            .map_err(AdapterError::SeaORM)
+            .map(|u| u.map(User::from))
+    }
+}
+
+/// Runs `SELECT EXISTS(<select>)`.
+async fn exists<C>(conn: &C, select: SelectStatement) -> Result<bool, AdapterError>
+where
+    C: ConnectionTrait,
+{
+    let stmt = Query::select().expr(Expr::exists(select)).to_owned();
+    let row = conn
+        .query_one(conn.get_database_backend().build(&stmt))
+        .await?
+        .ok_or(AdapterError::SeaORM(DbErr::RecordNotFound(
+            "EXISTS returned no rows".to_string(),
+        )))?;
+    row.try_get_by_index::<bool>(0)
+        .map_err(AdapterError::SeaORM)
 }
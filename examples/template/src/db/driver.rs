@@ -1,6 +1,7 @@
 use hextacy::Driver;
 use sea_orm::DatabaseConnection;
 use sea_orm::{ConnectOptions, Database};
+use std::time::Duration;
 
 #[cfg(test)]
 use sea_orm::{
@@ -30,6 +31,10 @@ impl Driver for SeaormDriver {
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
         self.pool.connect().await
     }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.pool.health_check().await
+    }
 }
 
 // Helper implementations
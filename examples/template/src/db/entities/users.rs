@@ -9,6 +9,9 @@ pub struct Model {
     pub id: Uuid,
     pub username: String,
     pub password: String,
+    #[sea_orm(unique)]
+    pub email: Option<String>,
+    pub failed_logins: i64,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -6,7 +6,9 @@ use axum::Json;
 use deadpool_redis::redis;
 use hextacy::queue::QueueError;
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use thiserror::Error;
 use validify::ValidationErrors;
 
@@ -27,8 +29,17 @@ pub enum Error {
     #[error("Redis: {0}")]
     Redis(#[from] redis::RedisError),
 
+    #[error("Cache: {0}")]
+    Cache(#[from] hextacy::adapters::cache::CacheError),
+
+    #[error("Cache pool: {0}")]
+    CachePool(#[from] deadpool_redis::PoolError),
+
+    #[error("Email: {0}")]
+    Email(#[from] hextacy::adapters::email::TemplateMailerError),
+
     #[error("Validation: {0}")]
-    Validation(#[from] ValidationErrors),
+    Validation(ValidationProblem),
 
     #[error("Serde: {0}")]
     Serde(#[from] serde_json::Error),
@@ -49,19 +60,63 @@ impl From<QueueError> for Error {
     }
 }
 
+/// For drivers which cannot fail to connect, such as the in-memory cache.
+impl From<Infallible> for Error {
+    fn from(value: Infallible) -> Self {
+        match value {}
+    }
+}
+
+/// Validation errors from request bodies. Use [Error::validation] for other sources.
+impl From<ValidationErrors> for Error {
+    fn from(value: ValidationErrors) -> Self {
+        Self::validation(ValidationSource::Body, value)
+    }
+}
+
 impl Error {
     pub fn new<E: Into<Self>>(e: E) -> Self {
         e.into()
     }
 
+    /// All extractors should report validation failures through this so the response has the same shape regardless
+    /// of where the invalid input came from.
+    pub fn validation(source: ValidationSource, errors: ValidationErrors) -> Self {
+        Self::Validation(ValidationProblem::new(source, errors))
+    }
+
     /// Returns error message and description
     pub fn message_and_description(&self) -> (&'static str, String) {
         match self {
             Self::Validation(_) => ("Validation", "Invalid request parameters".to_string()),
+            Self::Auth(e) => ("Authentication", e.to_string()),
             _ => ("Internal Server Error", "Internal server error".to_string()),
         }
     }
 
+    /// The key clients use to look up the translated message, along with the values to interpolate into it.
+    /// The description is the default English message for the key. Internal errors have no key.
+    pub fn message_key(&self) -> Option<MessageKey> {
+        let key = match self {
+            Self::Validation(problem) => MessageKey::new("error.validation")
+                .param("source", problem.source.to_string())
+                .param("violations", problem.violations.len()),
+            Self::Auth(e) => MessageKey::new(match e {
+                AuthenticationError::Unauthenticated => "error.auth.unauthenticated",
+                AuthenticationError::UsernameTaken => "error.auth.username_taken",
+                AuthenticationError::InvalidCredentials => "error.auth.invalid_credentials",
+                AuthenticationError::InvalidResetToken => "error.auth.invalid_reset_token",
+                AuthenticationError::TooManyResetRequests => "error.auth.too_many_reset_requests",
+                AuthenticationError::TooManySessions => "error.auth.too_many_sessions",
+            }),
+            Self::Adapter(AdapterError::SessionLimitReached(max)) => {
+                MessageKey::new("error.auth.too_many_sessions").param("max", max)
+            }
+            _ => return None,
+        };
+        Some(key)
+    }
+
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
@@ -75,17 +130,118 @@ impl Error {
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
+        let status = self.status_code();
+        let body = Json(self.body());
+        (status, body).into_response()
+    }
+}
+
+impl Error {
+    fn body(&self) -> Value {
         let status = self.status_code();
         let (message, description) = self.message_and_description();
-        let error_response = match self {
-            Self::Validation(errs) => {
-                ErrorResponse::new(status.as_u16(), message, &description, Some(errs))
+        let key = self.message_key();
+        match self {
+            Self::Validation(problem) => {
+                json! {ErrorResponse::new(status.as_u16(), message, &description, Some(problem)).with_key(key)}
             }
-            _ => ErrorResponse::new(status.as_u16(), message, &description, None),
-        };
+            _ => {
+                json! {ErrorResponse::<()>::new(status.as_u16(), message, &description, None).with_key(key)}
+            }
+        }
+    }
+}
 
-        let body = Json(json! {error_response});
-        (status, body).into_response()
+/// Where the invalid input of a request came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationSource {
+    Query,
+    Body,
+    Path,
+}
+
+impl std::fmt::Display for ValidationSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Query => write!(f, "query"),
+            Self::Body => write!(f, "body"),
+            Self::Path => write!(f, "path"),
+        }
+    }
+}
+
+/// The details of a 422 response. Flattens the validation errors so every violation has the same fields.
+#[derive(Debug, Serialize)]
+pub struct ValidationProblem {
+    source: ValidationSource,
+    violations: Vec<Violation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    field: Option<String>,
+    location: String,
+    code: String,
+    message: Option<String>,
+    params: HashMap<&'static str, Value>,
+}
+
+impl ValidationProblem {
+    pub fn new(source: ValidationSource, errors: ValidationErrors) -> Self {
+        let violations = errors
+            .errors()
+            .iter()
+            .map(|error| Violation {
+                field: error.field_name().map(String::from),
+                location: error.location().to_string(),
+                code: error.code(),
+                message: error.message(),
+                params: error.params(),
+            })
+            .collect();
+        Self { source, violations }
+    }
+}
+
+impl std::fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} violation(s) in {}",
+            self.violations.len(),
+            self.source
+        )
+    }
+}
+
+/// An i18n message key and its interpolation parameters, e.g. `error.auth.too_many_sessions` with `{ "max": 5 }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MessageKey {
+    key: &'static str,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    params: HashMap<&'static str, Value>,
+}
+
+impl MessageKey {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            params: HashMap::new(),
+        }
+    }
+
+    pub fn param(mut self, name: &'static str, value: impl Serialize) -> Self {
+        self.params.insert(name, json!(value));
+        self
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    pub fn params(&self) -> &HashMap<&'static str, Value> {
+        &self.params
     }
 }
 
@@ -96,6 +252,8 @@ pub struct ErrorResponse<'a, T> {
     description: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
     details: Option<T>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    key: Option<MessageKey>,
 }
 
 impl<'a, T> ErrorResponse<'a, T>
@@ -108,8 +266,15 @@ where
             message,
             description,
             details,
+            key: None,
         }
     }
+
+    /// Serializes the key and its params alongside the default message.
+    pub fn with_key(mut self, key: Option<MessageKey>) -> Self {
+        self.key = key;
+        self
+    }
 }
 
 impl<T> std::fmt::Display for ErrorResponse<'_, T> {
@@ -121,3 +286,81 @@ impl<T> std::fmt::Display for ErrorResponse<'_, T> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validify::ValidationError;
+
+    fn errors() -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        errors.add(ValidationError::new_field_named("username", "length").with_param("min", &2));
+        errors.add(ValidationError::new_schema("passwords_match"));
+        errors
+    }
+
+    /// Replaces all leaf values so only the structure is compared.
+    fn shape(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                Value::Object(map.iter().map(|(k, v)| (k.clone(), shape(v))).collect())
+            }
+            Value::Array(values) => Value::Array(values.iter().map(shape).collect()),
+            _ => Value::Null,
+        }
+    }
+
+    #[test]
+    fn validation_shape_is_independent_of_source() {
+        let query = Error::validation(ValidationSource::Query, errors());
+        let body = Error::new(errors());
+
+        assert_eq!(query.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (query, body) = (query.body(), body.body());
+        assert_eq!(shape(&query), shape(&body));
+
+        assert_eq!(query["details"]["source"], "query");
+        assert_eq!(body["details"]["source"], "body");
+        assert_eq!(
+            query["details"]["violations"],
+            body["details"]["violations"]
+        );
+        assert_eq!(body["details"]["violations"][0]["field"], "username");
+        assert_eq!(body["details"]["violations"][0]["code"], "length");
+        assert_eq!(body["details"]["violations"][1]["field"], Value::Null);
+    }
+
+    #[test]
+    fn serializes_message_keys() {
+        let body = Error::new(AuthenticationError::InvalidCredentials).body();
+        assert_eq!(body["key"], "error.auth.invalid_credentials");
+        assert_eq!(body["description"], "Invalid credentials");
+        assert!(body.get("params").is_none());
+
+        let body = Error::new(AdapterError::SessionLimitReached(5)).body();
+        assert_eq!(body["key"], "error.auth.too_many_sessions");
+        assert_eq!(body["params"], json!({ "max": 5 }));
+
+        let body = Error::validation(ValidationSource::Query, errors()).body();
+        assert_eq!(body["key"], "error.validation");
+        assert_eq!(
+            body["params"],
+            json!({ "source": "query", "violations": 2 })
+        );
+        assert_eq!(body["message"], "Validation");
+        assert_eq!(body["details"]["violations"][0]["code"], "length");
+    }
+
+    #[test]
+    fn internal_errors_have_no_key() {
+        let error = Error::new(uuid::Uuid::parse_str("not a uuid").unwrap_err());
+        assert!(error.message_key().is_none());
+
+        let body = error.body();
+        assert_eq!(body["description"], "Internal server error");
+        assert!(body.get("key").is_none());
+        assert!(body.get("params").is_none());
+    }
+}
@@ -16,3 +16,8 @@ pub mod db;
 pub mod email;
 
 pub mod queue;
+
+/// Utilities for configuring connection pools.
+pub mod pool;
+
+pub use pool::{PoolConfig, PoolConfigError};
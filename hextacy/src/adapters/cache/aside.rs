@@ -0,0 +1,162 @@
+use super::{CacheError, SimpleCacheAccess};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+/// Cache-aside lookup. Returns the value cached under `key`, or computes it, caches it for `ex` seconds and returns it.
+///
+/// ### Example
+///
+/// ```ignore
+/// let user = cache_aside(&mut cache, &key, Some(60), || repo.get_by_id(id)).await?;
+/// ```
+pub async fn cache_aside<C, T, E, F, Fut>(
+    cache: &mut C,
+    key: &str,
+    ex: Option<usize>,
+    compute: F,
+) -> Result<T, E>
+where
+    C: SimpleCacheAccess,
+    T: Serialize + DeserializeOwned + Sync,
+    E: From<CacheError>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    if let Some(value) = cache.get_json(key).await? {
+        return Ok(value);
+    }
+    let value = compute().await?;
+    cache.set_json(key, &value, ex).await?;
+    Ok(value)
+}
+
+/// Coalesces concurrent [cache_aside] misses for the same key so only one computation per key is in flight.
+///
+/// The first caller to miss computes the value while the rest wait for it to finish and then read the result
+/// from the cache. If the computation fails, the next waiter computes it instead. Clones share in-flight keys,
+/// so a single instance should be shared by everything populating the same keys.
+///
+/// Coalescing only happens within the process.
+///
+/// ### Example
+///
+/// ```ignore
+/// let flights = SingleFlight::new();
+/// let user = flights.cache_aside(&mut cache, &key, Some(60), || repo.get_by_id(id)).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SingleFlight {
+    in_flight: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl SingleFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn cache_aside<C, T, E, F, Fut>(
+        &self,
+        cache: &mut C,
+        key: &str,
+        ex: Option<usize>,
+        compute: F,
+    ) -> Result<T, E>
+    where
+        C: SimpleCacheAccess,
+        T: Serialize + DeserializeOwned + Sync,
+        E: From<CacheError>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = cache.get_json(key).await? {
+            return Ok(value);
+        }
+
+        let flight = self
+            .in_flight
+            .lock()
+            .expect("single flight lock poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        let result = {
+            let _guard = flight.lock().await;
+            // Populated if we waited on someone else's computation
+            cache_aside(cache, key, ex, compute).await
+        };
+
+        let mut in_flight = self.in_flight.lock().expect("single flight lock poisoned");
+        // Only the map and this call hold it, i.e. no one else is waiting
+        if Arc::strong_count(&flight) == 2 {
+            in_flight.remove(key);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::cache::tests::MapCache;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn computes_once_under_contention() {
+        let cache = MapCache::default();
+        let flights = SingleFlight::new();
+        let computed = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..32)
+            .map(|_| {
+                let mut cache = cache.clone();
+                let flights = flights.clone();
+                let computed = computed.clone();
+                tokio::spawn(async move {
+                    flights
+                        .cache_aside(&mut cache, "key", None, || async {
+                            computed.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok::<_, CacheError>(42)
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), 42);
+        }
+
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+        assert!(flights.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_computation_is_retried() {
+        let mut cache = MapCache::default();
+        let flights = SingleFlight::new();
+
+        let result = flights
+            .cache_aside(&mut cache, "key", None, || async {
+                Err::<usize, _>(CacheError::Serde(serde::de::Error::custom("failed")))
+            })
+            .await;
+        assert!(result.is_err());
+
+        let value = flights
+            .cache_aside(&mut cache, "key", None, || async { Ok::<_, CacheError>(1) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::driver::{Driver, DriverError};
-use async_trait::async_trait;
+use super::{CacheError, SimpleCacheAccess};
+use crate::driver::Driver;
+use serde::{de::DeserializeOwned, Serialize};
 use std::{
     any::Any,
-    collections::{hash_map::DefaultHasher, HashMap},
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    convert::Infallible,
     hash::{Hash, Hasher},
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
 };
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tracing::debug;
 
 type AnyHMap = HashMap<u64, Box<dyn Any + Send + Sync + 'static>>;
 
@@ -104,21 +109,414 @@ impl InMemConnection {
                 .expect("Invalid type provided for `value`")
         })
     }
+
+    /// Set the value only if the key does not exist and return whether it was set.
+    /// The check and the insert happen under the cache lock.
+    pub fn set_nx<K, V>(&mut self, key: K, value: V) -> bool
+    where
+        K: Hash,
+        V: Clone + Any + Send + Sync + 'static,
+    {
+        let mut map = self.cache.lock().unwrap();
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hashed = hasher.finish();
+
+        if map.contains_key(&hashed) {
+            return false;
+        }
+
+        map.insert(hashed, Box::new(value));
+        true
+    }
+
+    /// Increment the `i64` stored at `key` by one, setting it to 1 if it does not exist, and return
+    /// the new value. The whole operation happens under the cache lock so concurrent increments are never lost.
+    pub fn set_or_increment<K>(&mut self, key: K) -> i64
+    where
+        K: Hash,
+    {
+        let mut map = self.cache.lock().unwrap();
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hashed = hasher.finish();
+
+        let count = map
+            .entry(hashed)
+            .or_insert_with(|| Box::new(0_i64))
+            .downcast_mut::<i64>()
+            .expect("Invalid type provided for `value`");
+
+        *count += 1;
+        *count
+    }
 }
 
-#[async_trait]
 impl Driver for InMemCache {
     type Connection = InMemConnection;
+    type Error = Infallible;
 
-    async fn connect(&self) -> Result<Self::Connection, DriverError> {
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
         Ok(InMemConnection::new(self))
     }
 }
 
+/// An in-process [Driver] whose connections implement [SimpleCacheAccess], so adapters written against it
+/// run unchanged without Redis, i.e. in tests and single node deployments.
+///
+/// Values are stored as strings, the same way Redis stores them. Expired entries are never returned and are
+/// removed when read, and periodically by the [sweeper][MemoryCache::spawn_sweeper] if one is running.
+///
+/// With a capacity, the least recently used entry is evicted when inserting a new key into a full cache.
+///
+/// Clones share entries.
+///
+/// ### Example
+///
+/// ```ignore
+/// let cache = MemoryCache::lru(10_000);
+/// cache.spawn_sweeper(Duration::from_secs(60));
+/// let auth_cache = AuthenticationCache { driver: cache };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCache {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryCache {
+    /// An unbounded cache, entries are only removed when deleted or expired.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A cache holding at most `capacity` entries.
+    pub fn lru(capacity: usize) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(MemoryStore {
+                capacity: Some(capacity),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// The amount of entries, including expired ones which were not yet removed.
+    pub fn len(&self) -> usize {
+        self.store
+            .lock()
+            .expect("cache lock poisoned")
+            .entries
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove expired entries every `interval`. The task stops once every handle to the cache is dropped.
+    pub fn spawn_sweeper(&self, interval: Duration) -> JoinHandle<()> {
+        let store: Weak<Mutex<MemoryStore>> = Arc::downgrade(&self.store);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(store) = store.upgrade() else {
+                    return;
+                };
+                let swept = store.lock().expect("cache lock poisoned").sweep();
+                if swept > 0 {
+                    debug!("Swept {swept} expired cache entries");
+                }
+            }
+        })
+    }
+}
+
+impl Driver for MemoryCache {
+    type Connection = MemoryConnection;
+    type Error = Infallible;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        Ok(MemoryConnection {
+            store: self.store.clone(),
+        })
+    }
+}
+
+/// A handle on the entries of the [MemoryCache] it was obtained from.
+#[derive(Debug, Clone)]
+pub struct MemoryConnection {
+    store: Arc<Mutex<MemoryStore>>,
+}
+
+impl MemoryConnection {
+    fn store(&self) -> std::sync::MutexGuard<'_, MemoryStore> {
+        self.store.lock().expect("cache lock poisoned")
+    }
+}
+
+/// Keeps track of recency with a monotonically increasing tick, like the [LRU][super::lru::LruCache].
+#[derive(Debug, Default)]
+struct MemoryStore {
+    capacity: Option<usize>,
+    tick: u64,
+    entries: HashMap<String, MemoryEntry>,
+    order: BTreeMap<u64, String>,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    value: String,
+    expires_at: Option<Instant>,
+    tick: u64,
+}
+
+impl MemoryEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+impl MemoryStore {
+    /// The live entry under the key, marking it as recently used.
+    fn get(&mut self, key: &str) -> Option<&mut MemoryEntry> {
+        if self.entries.get(key)?.is_expired(Instant::now()) {
+            self.remove(key);
+            return None;
+        }
+
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.tick);
+        self.order.insert(self.tick, key.to_string());
+        entry.tick = self.tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: &str, value: String, expires_at: Option<Instant>) {
+        self.remove(key);
+
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            while self.entries.len() >= capacity {
+                let Some((_, lru)) = self.order.pop_first() else {
+                    break;
+                };
+                self.entries.remove(&lru);
+            }
+        }
+
+        self.tick += 1;
+        self.order.insert(self.tick, key.to_string());
+        self.entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                value,
+                expires_at,
+                tick: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &str) -> Option<MemoryEntry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        Some(entry)
+    }
+
+    /// Remove the key, returning its value only if it was still live.
+    fn take(&mut self, key: &str) -> Option<MemoryEntry> {
+        self.remove(key)
+            .filter(|entry| !entry.is_expired(Instant::now()))
+    }
+
+    fn sweep(&mut self) -> usize {
+        let now = Instant::now();
+        let expired = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in expired.iter() {
+            self.remove(key);
+        }
+        expired.len()
+    }
+}
+
+fn expiration(ex: Option<usize>) -> Option<Instant> {
+    ex.map(|ex| Instant::now() + Duration::from_secs(ex as u64))
+}
+
+impl SimpleCacheAccess for MemoryConnection {
+    async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+        Ok(self.store().get(key).map(|entry| entry.value.clone()))
+    }
+
+    async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        let Some(value) = self.get_string(key).await? else {
+            return Ok(None);
+        };
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| CacheError::NotAnInteger(key.to_string()))
+    }
+
+    async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(value) = self.get_string(key).await? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_str(&value)?))
+    }
+
+    async fn set_str(
+        &mut self,
+        key: &str,
+        value: &str,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        self.store().insert(key, value.to_string(), expiration(ex));
+        Ok(())
+    }
+
+    async fn set_i64(
+        &mut self,
+        key: &str,
+        value: i64,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        self.set_str(key, &value.to_string(), ex).await
+    }
+
+    async fn set_json<T>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let value = serde_json::to_string(value)?;
+        self.set_str(key, &value, ex).await
+    }
+
+    /// Like Redis' `INCR`, the remaining expiration is kept when `ex` is not provided.
+    async fn set_or_increment(&mut self, key: &str, ex: Option<usize>) -> Result<i64, CacheError> {
+        let mut store = self.store();
+        let (count, expires_at) = match store.get(key) {
+            Some(entry) => {
+                let count = entry
+                    .value
+                    .parse::<i64>()
+                    .map_err(|_| CacheError::NotAnInteger(key.to_string()))?;
+                (count + 1, entry.expires_at)
+            }
+            None => (1, None),
+        };
+        let expires_at = expiration(ex).or(expires_at);
+        store.insert(key, count.to_string(), expires_at);
+        Ok(count)
+    }
+
+    async fn set_nx(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<bool, CacheError> {
+        let mut store = self.store();
+        if store.get(key).is_some() {
+            return Ok(false);
+        }
+        store.insert(key, value.to_string(), ttl.map(|ttl| Instant::now() + ttl));
+        Ok(true)
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+        self.store().remove(key);
+        Ok(())
+    }
+
+    async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+        let mut store = self.store();
+        Ok(keys.iter().filter(|key| store.take(key).is_some()).count() as u64)
+    }
+
+    async fn migrate_keys(
+        &mut self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<u64, CacheError> {
+        let mut store = self.store();
+        let keys = store
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(old_prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut moved = 0;
+        for key in keys {
+            let Some(entry) = store.take(&key) else {
+                continue;
+            };
+            let new_key = format!("{new_prefix}{}", &key[old_prefix.len()..]);
+            store.insert(&new_key, entry.value, entry.expires_at);
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let now = Instant::now();
+        Ok(self
+            .store()
+            .entries
+            .iter()
+            .filter(|(key, entry)| key.starts_with(prefix) && !entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect())
+    }
+
+    async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+        let mut store = self.store();
+        if store.get(key).is_some_and(|entry| entry.value == value) {
+            store.remove(key);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn expire_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> Result<bool, CacheError> {
+        let mut store = self.store();
+        let Some(entry) = store.get(key).filter(|entry| entry.value == value) else {
+            return Ok(false);
+        };
+        entry.expires_at = Some(Instant::now() + ttl);
+        Ok(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::InMemCache;
     use crate::adapters::cache::in_mem::InMemConnection;
+    use crate::adapters::cache::CacheKey;
 
     #[derive(Debug, Clone)]
     struct SomeItem {
@@ -156,4 +554,174 @@ mod tests {
 
         assert_eq!(conn.cache.lock().unwrap().len(), 0);
     }
+
+    #[test]
+    fn concurrent_increments() {
+        let cache = InMemCache::new();
+
+        let handles = (0..8)
+            .map(|_| {
+                let mut conn = InMemConnection::new(&cache);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        conn.set_or_increment("attempts");
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut conn = InMemConnection::new(&cache);
+        assert_eq!(conn.get::<_, i64>("attempts"), Some(800));
+        assert_eq!(conn.set_or_increment("attempts"), 801);
+    }
+
+    #[test]
+    fn set_nx_is_exclusive() {
+        let cache = InMemCache::new();
+        let mut conn = InMemConnection::new(&cache);
+
+        assert!(conn.set_nx("lock", "a"));
+        assert!(!conn.set_nx("lock", "b"));
+        assert_eq!(conn.get::<_, &str>("lock"), Some("a"));
+    }
+
+    #[test]
+    fn cache_keys_do_not_collide() {
+        let cache = InMemCache::new().pool;
+        let mut conn = InMemConnection { cache };
+
+        conn.set(CacheKey::new("user").segment("a:b"), 1_u8);
+        conn.set(CacheKey::new("user").segment("a").segment("b"), 2_u8);
+
+        assert_eq!(conn.cache.lock().unwrap().len(), 2);
+        assert_eq!(
+            conn.get::<_, u8>(CacheKey::new("user").segment("a:b")),
+            Some(1)
+        );
+        assert_eq!(
+            conn.get::<_, u8>(CacheKey::new("user").segment("a").segment("b")),
+            Some(2)
+        );
+    }
+
+    mod memory {
+        use crate::adapters::cache::{in_mem::MemoryCache, CacheError, SimpleCacheAccess};
+        use crate::driver::Driver;
+        use std::time::Duration;
+
+        #[tokio::test]
+        async fn simple_cache_access() {
+            let cache = MemoryCache::new();
+            let mut conn = cache.connect().await.unwrap();
+
+            conn.set_str("name", "alice", None).await.unwrap();
+            conn.set_json("ids", &[1, 2], None).await.unwrap();
+            assert_eq!(
+                conn.get_string("name").await.unwrap().as_deref(),
+                Some("alice")
+            );
+            assert_eq!(
+                conn.get_json::<Vec<u8>>("ids").await.unwrap(),
+                Some(vec![1, 2])
+            );
+            assert!(matches!(
+                conn.get_i64("name").await,
+                Err(CacheError::NotAnInteger(_))
+            ));
+
+            assert_eq!(conn.set_or_increment("attempts", None).await.unwrap(), 1);
+            assert_eq!(conn.set_or_increment("attempts", None).await.unwrap(), 2);
+            assert_eq!(conn.get_i64("attempts").await.unwrap(), Some(2));
+
+            assert!(conn.set_nx("lock", "a", None).await.unwrap());
+            assert!(!conn.set_nx("lock", "b", None).await.unwrap());
+            assert!(!conn.delete_if_eq("lock", "b").await.unwrap());
+            assert!(conn.delete_if_eq("lock", "a").await.unwrap());
+
+            conn.set_str("session:1", "a", None).await.unwrap();
+            conn.set_str("session:2", "b", None).await.unwrap();
+            assert_eq!(
+                conn.migrate_keys("session:", "session:v2:").await.unwrap(),
+                2
+            );
+            assert_eq!(conn.get_string("session:1").await.unwrap(), None);
+            assert_eq!(
+                conn.get_string("session:v2:2").await.unwrap().as_deref(),
+                Some("b")
+            );
+
+            conn.delete("name").await.unwrap();
+            assert_eq!(
+                conn.delete_many(&["ids", "name", "session:v2:1"])
+                    .await
+                    .unwrap(),
+                2
+            );
+
+            // Clones share entries
+            let mut other = cache.clone().connect().await.unwrap();
+            assert_eq!(other.get_i64("attempts").await.unwrap(), Some(2));
+        }
+
+        #[tokio::test]
+        async fn entries_expire() {
+            let cache = MemoryCache::new();
+            let mut conn = cache.connect().await.unwrap();
+
+            conn.set_str("short", "a", Some(1)).await.unwrap();
+            conn.set_nx("shorter", "b", Some(Duration::from_millis(20)))
+                .await
+                .unwrap();
+            conn.set_str("forever", "c", None).await.unwrap();
+            assert_eq!(conn.set_or_increment("counter", Some(1)).await.unwrap(), 1);
+            // Keeps the expiration
+            assert_eq!(conn.set_or_increment("counter", None).await.unwrap(), 2);
+
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            assert_eq!(conn.get_string("shorter").await.unwrap(), None);
+            assert!(conn.set_nx("shorter", "d", None).await.unwrap());
+
+            let sweeper = cache.spawn_sweeper(Duration::from_millis(50));
+            tokio::time::sleep(Duration::from_millis(1100)).await;
+
+            // Swept without being read
+            assert_eq!(cache.len(), 2);
+            assert_eq!(conn.get_string("short").await.unwrap(), None);
+            assert_eq!(conn.get_i64("counter").await.unwrap(), None);
+            assert_eq!(
+                conn.get_string("forever").await.unwrap().as_deref(),
+                Some("c")
+            );
+
+            drop((cache, conn));
+            tokio::time::timeout(Duration::from_secs(1), sweeper)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn evicts_least_recently_used() {
+            let cache = MemoryCache::lru(2);
+            let mut conn = cache.connect().await.unwrap();
+
+            conn.set_str("a", "1", None).await.unwrap();
+            conn.set_str("b", "2", None).await.unwrap();
+            conn.get_string("a").await.unwrap();
+            conn.set_str("c", "3", None).await.unwrap();
+
+            assert_eq!(cache.len(), 2);
+            assert_eq!(conn.get_string("b").await.unwrap(), None);
+            assert_eq!(conn.get_string("a").await.unwrap().as_deref(), Some("1"));
+            assert_eq!(conn.get_string("c").await.unwrap().as_deref(), Some("3"));
+
+            // Overwriting doesn't evict
+            conn.set_str("c", "4", None).await.unwrap();
+            assert_eq!(conn.get_string("a").await.unwrap().as_deref(), Some("1"));
+        }
+    }
 }
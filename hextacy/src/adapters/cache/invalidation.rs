@@ -0,0 +1,229 @@
+use super::SimpleCacheAccess;
+use crate::Atomic;
+use std::ops::{Deref, DerefMut};
+use tracing::warn;
+
+/// Wraps a connection and defers cache invalidations queued during a transaction until it commits.
+///
+/// Invalidations [queued][Invalidating::invalidate] in a transaction are executed after it successfully
+/// commits and discarded if it is aborted, so a rolled back write never evicts valid entries. Outside of a
+/// transaction there is nothing to wait for, so queued invalidations must be [flushed][Invalidating::flush] manually.
+///
+/// Since the transaction is already committed when the keys get deleted, failing to delete them does not
+/// fail the commit and is only logged.
+///
+/// ### Example
+///
+/// ```ignore
+/// let conn = Invalidating::new(driver.connect().await?, cache.connect().await?);
+///
+/// transaction!(
+///     conn: Invalidating<DatabaseConnection, RedisConnection> => {
+///         update_user(&*conn, &user).await?;
+///         conn.invalidate(user.cache_key().to_string());
+///         Ok(())
+///     }
+/// )
+/// ```
+#[derive(Debug)]
+pub struct Invalidating<C, K> {
+    inner: C,
+    cache: K,
+    pending: Vec<String>,
+}
+
+impl<C, K> Invalidating<C, K>
+where
+    K: SimpleCacheAccess,
+{
+    pub fn new(inner: C, cache: K) -> Self {
+        Self {
+            inner,
+            cache,
+            pending: vec![],
+        }
+    }
+
+    /// Queue the key for deletion once the transaction commits.
+    pub fn invalidate(&mut self, key: impl Into<String>) {
+        self.pending.push(key.into());
+    }
+
+    /// The keys that will be deleted on commit.
+    pub fn pending(&self) -> &[String] {
+        &self.pending
+    }
+
+    /// Delete all queued keys.
+    pub async fn flush(&mut self) {
+        for key in std::mem::take(&mut self.pending) {
+            if let Err(e) = self.cache.delete(&key).await {
+                warn!("Error occurred while invalidating cache key {key}: {e}");
+            }
+        }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C, K> Deref for Invalidating<C, K> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<C, K> DerefMut for Invalidating<C, K> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<C, K> Atomic for Invalidating<C, K>
+where
+    C: Atomic + Send,
+    C::TransactionResult: Send,
+    K: SimpleCacheAccess + Send,
+{
+    type TransactionResult = Invalidating<C::TransactionResult, K>;
+    type Error = C::Error;
+
+    async fn start_transaction(self) -> Result<Self::TransactionResult, Self::Error> {
+        let Self {
+            inner,
+            cache,
+            pending,
+        } = self;
+        let tx = inner.start_transaction().await?;
+        Ok(Invalidating {
+            inner: tx,
+            cache,
+            pending,
+        })
+    }
+
+    async fn commit_transaction(mut tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        let pending = std::mem::take(&mut tx.pending);
+        let Invalidating {
+            inner, mut cache, ..
+        } = tx;
+
+        C::commit_transaction(inner).await?;
+
+        for key in pending {
+            if let Err(e) = cache.delete(&key).await {
+                warn!("Error occurred while invalidating cache key {key}: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn abort_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        C::abort_transaction(tx.inner).await
+    }
+
+    async fn start_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::start_savepoint(&mut tx.inner).await
+    }
+
+    async fn release_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::release_savepoint(&mut tx.inner).await
+    }
+
+    /// Keys invalidated since the savepoint stay pending, invalidating them on commit is harmless.
+    async fn rollback_to_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::rollback_to_savepoint(&mut tx.inner).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::cache::tests::MapCache;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, Default)]
+    struct FakeConnection(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Atomic for FakeConnection {
+        type TransactionResult = FakeConnection;
+        type Error = ();
+
+        async fn start_transaction(self) -> Result<FakeConnection, ()> {
+            self.0.lock().unwrap().push("BEGIN");
+            Ok(self)
+        }
+
+        async fn commit_transaction(tx: FakeConnection) -> Result<(), ()> {
+            tx.0.lock().unwrap().push("COMMIT");
+            Ok(())
+        }
+
+        async fn abort_transaction(tx: FakeConnection) -> Result<(), ()> {
+            tx.0.lock().unwrap().push("ROLLBACK");
+            Ok(())
+        }
+
+        async fn start_savepoint(tx: &mut FakeConnection) -> Result<(), ()> {
+            tx.0.lock().unwrap().push("SAVEPOINT");
+            Ok(())
+        }
+
+        async fn release_savepoint(tx: &mut FakeConnection) -> Result<(), ()> {
+            tx.0.lock().unwrap().push("RELEASE");
+            Ok(())
+        }
+
+        async fn rollback_to_savepoint(tx: &mut FakeConnection) -> Result<(), ()> {
+            tx.0.lock().unwrap().push("ROLLBACK TO");
+            Ok(())
+        }
+    }
+
+    async fn cache() -> MapCache {
+        let mut cache = MapCache::default();
+        cache.set_str("user:1", "cached", None).await.unwrap();
+        cache
+    }
+
+    #[tokio::test]
+    async fn aborted_transaction_discards_invalidations() {
+        let cache = cache().await;
+        let conn = Invalidating::new(FakeConnection::default(), cache.clone());
+
+        let mut tx = conn.start_transaction().await.unwrap();
+        tx.invalidate("user:1");
+        assert_eq!(tx.pending(), ["user:1"]);
+        Invalidating::<FakeConnection, MapCache>::abort_transaction(tx)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache.0.lock().unwrap().get("user:1").map(String::as_str),
+            Some("cached")
+        );
+    }
+
+    #[tokio::test]
+    async fn committed_transaction_runs_invalidations() {
+        let cache = cache().await;
+        let fake = FakeConnection::default();
+        let conn = Invalidating::new(fake.clone(), cache.clone());
+
+        let mut tx = conn.start_transaction().await.unwrap();
+        tx.invalidate("user:1");
+
+        // Nothing is invalidated before the commit
+        assert!(cache.0.lock().unwrap().contains_key("user:1"));
+
+        Invalidating::<FakeConnection, MapCache>::commit_transaction(tx)
+            .await
+            .unwrap();
+
+        assert!(!cache.0.lock().unwrap().contains_key("user:1"));
+        assert_eq!(*fake.0.lock().unwrap(), ["BEGIN", "COMMIT"]);
+    }
+}
@@ -0,0 +1,450 @@
+use super::{CacheError, SimpleCacheAccess};
+use crate::driver::Driver;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tracing::warn;
+
+/// Try to acquire the lock on `key` for at most `ttl`. Returns the token that owns the lock if it was acquired
+/// and `None` if someone else holds it.
+///
+/// The token must be passed to [release_lock] to release the lock. The `ttl` ensures the lock is eventually released
+/// if its owner never does.
+///
+/// ### Example
+///
+/// ```ignore
+/// let Some(token) = acquire_lock(&mut conn, "jobs:cleanup", Duration::from_secs(30)).await? else {
+///     return Ok(()); // Another instance is doing the work
+/// };
+/// cleanup().await?;
+/// release_lock(&mut conn, "jobs:cleanup", &token).await?;
+/// ```
+pub async fn acquire_lock<C>(
+    cache: &mut C,
+    key: &str,
+    ttl: Duration,
+) -> Result<Option<String>, CacheError>
+where
+    C: SimpleCacheAccess,
+{
+    let token = lock_token();
+    let acquired = cache.set_nx(key, &token, Some(ttl)).await?;
+    Ok(acquired.then_some(token))
+}
+
+/// Release the lock on `key` only if it is still owned by `token`, i.e. a lock that expired and
+/// got acquired by someone else is never released. Returns whether the lock was released.
+pub async fn release_lock<C>(cache: &mut C, key: &str, token: &str) -> Result<bool, CacheError>
+where
+    C: SimpleCacheAccess,
+{
+    cache.delete_if_eq(key, token).await
+}
+
+/// A distributed lock over a Redis pool, e.g. to run a scheduled job on a single instance.
+#[cfg(feature = "cache-redis")]
+pub type RedisLock = CacheLock<deadpool_redis::Pool>;
+
+/// A distributed lock on keys of the cache behind the driver, built on [acquire_lock] and [release_lock].
+///
+/// Acquired locks are held by a [LockGuard], which releases the lock when dropped. The guard holds on to
+/// the connection the lock was acquired with, so it can release the lock without connecting again.
+///
+/// Locks expire after their `ttl` in case their owner dies. Jobs that may run longer should enable
+/// the [watchdog][CacheLock::watchdog], which keeps extending the lock for as long as the guard is alive.
+///
+/// ### Example
+///
+/// ```ignore
+/// let lock = RedisLock::new(pool).watchdog(true);
+///
+/// let Some(guard) = lock.try_acquire("jobs:cleanup", Duration::from_secs(30)).await? else {
+///     return Ok(()); // Another instance is doing the work
+/// };
+/// cleanup().await?;
+/// guard.release().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct CacheLock<D> {
+    driver: D,
+    watchdog: bool,
+    retry_interval: Duration,
+}
+
+impl<D> CacheLock<D>
+where
+    D: Driver,
+    D::Connection: SimpleCacheAccess + Send + 'static,
+{
+    pub fn new(driver: D) -> Self {
+        Self {
+            driver,
+            watchdog: false,
+            retry_interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Whether to extend acquired locks by their `ttl` every third of it until their guard is dropped.
+    /// The watchdog uses a connection of its own.
+    pub fn watchdog(mut self, enabled: bool) -> Self {
+        self.watchdog = enabled;
+        self
+    }
+
+    /// How long [acquire][CacheLock::acquire] waits between attempts. Defaults to 100ms.
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Try to acquire the lock on `key` once, returning `None` if someone else holds it.
+    pub async fn try_acquire(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> Result<Option<LockGuard<D::Connection>>, LockError<D::Error>> {
+        let mut conn = self.driver.connect().await.map_err(LockError::Driver)?;
+        let Some(token) = acquire_lock(&mut conn, key, ttl).await? else {
+            return Ok(None);
+        };
+
+        let mut guard = LockGuard {
+            conn: Some(conn),
+            key: key.to_string(),
+            token,
+            lost: Arc::new(AtomicBool::new(false)),
+            watchdog: None,
+        };
+
+        if self.watchdog {
+            match self.driver.connect().await {
+                Ok(conn) => guard.watchdog = Some(guard.spawn_watchdog(conn, ttl)),
+                Err(e) => {
+                    // Not holding on to a lock that will expire under the job
+                    guard.release().await?;
+                    return Err(LockError::Driver(e));
+                }
+            }
+        }
+
+        Ok(Some(guard))
+    }
+
+    /// Try to acquire the lock on `key` until `timeout` elapses, returning `None` if it could not be acquired in time.
+    pub async fn acquire(
+        &self,
+        key: &str,
+        ttl: Duration,
+        timeout: Duration,
+    ) -> Result<Option<LockGuard<D::Connection>>, LockError<D::Error>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(guard) = self.try_acquire(key, ttl).await? {
+                return Ok(Some(guard));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(self.retry_interval.min(deadline - now)).await;
+        }
+    }
+}
+
+/// Holds an acquired [CacheLock]. The lock is released when the guard is dropped, or explicitly with
+/// [release][LockGuard::release] to find out whether it was still held.
+#[derive(Debug)]
+pub struct LockGuard<C>
+where
+    C: SimpleCacheAccess + Send + 'static,
+{
+    conn: Option<C>,
+    key: String,
+    token: String,
+    lost: Arc<AtomicBool>,
+    watchdog: Option<JoinHandle<()>>,
+}
+
+impl<C> LockGuard<C>
+where
+    C: SimpleCacheAccess + Send + 'static,
+{
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Whether the lock is still held as far as the watchdog knows. Without a watchdog this is always true,
+    /// even after the lock expires.
+    pub fn is_held(&self) -> bool {
+        !self.lost.load(Ordering::SeqCst)
+    }
+
+    /// Release the lock, returning whether it was still held.
+    pub async fn release(mut self) -> Result<bool, CacheError> {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.abort();
+        }
+        let Some(mut conn) = self.conn.take() else {
+            return Ok(false);
+        };
+        release_lock(&mut conn, &self.key, &self.token).await
+    }
+
+    fn spawn_watchdog(&self, mut conn: C, ttl: Duration) -> JoinHandle<()> {
+        let (key, token, lost) = (self.key.clone(), self.token.clone(), self.lost.clone());
+        tokio::spawn(async move {
+            // Intervals panic on a zero period
+            let period = (ttl / 3).max(Duration::from_millis(1));
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match conn.expire_if_eq(&key, &token, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!("Lost lock {key}");
+                        lost.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                    // The lock is still held until it expires, the next tick may get through
+                    Err(e) => warn!("Failed to extend lock {key}: {e}"),
+                }
+            }
+        })
+    }
+}
+
+impl<C> Drop for LockGuard<C>
+where
+    C: SimpleCacheAccess + Send + 'static,
+{
+    fn drop(&mut self) {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.abort();
+        }
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        // Outside of a runtime the lock is left to expire
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (key, token) = (
+            std::mem::take(&mut self.key),
+            std::mem::take(&mut self.token),
+        );
+        runtime.spawn(async move {
+            if let Err(e) = release_lock(&mut conn, &key, &token).await {
+                warn!("Failed to release lock {key}: {e}");
+            }
+        });
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LockError<E> {
+    #[error("Driver: {0}")]
+    Driver(E),
+    #[error("Cache: {0}")]
+    Cache(#[from] CacheError),
+}
+
+/// Generates a token unique to this process and call.
+fn lock_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+
+    format!(
+        "{:016x}{:x}",
+        hasher.finish(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::cache::tests::MapCache;
+
+    #[tokio::test]
+    async fn contended_acquisition() {
+        let cache = MapCache::default();
+
+        let handles = (0..16)
+            .map(|_| {
+                let mut cache = cache.clone();
+                tokio::spawn(async move {
+                    acquire_lock(&mut cache, "lock", Duration::from_secs(10))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut tokens = vec![];
+        for handle in handles {
+            tokens.extend(handle.await.unwrap());
+        }
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(cache.0.lock().unwrap().get("lock"), Some(&tokens[0]));
+    }
+
+    #[tokio::test]
+    async fn only_owner_releases() {
+        let mut cache = MapCache::default();
+
+        let token = acquire_lock(&mut cache, "lock", Duration::from_secs(10))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(!release_lock(&mut cache, "lock", "not-the-owner")
+            .await
+            .unwrap());
+        assert!(acquire_lock(&mut cache, "lock", Duration::from_secs(10))
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(release_lock(&mut cache, "lock", &token).await.unwrap());
+        assert!(!release_lock(&mut cache, "lock", &token).await.unwrap());
+
+        let next = acquire_lock(&mut cache, "lock", Duration::from_secs(10))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(next, token);
+    }
+
+    #[tokio::test]
+    async fn contended_guards() {
+        let cache = MapCache::default();
+        let first = CacheLock::new(cache.clone()).retry_interval(Duration::from_millis(5));
+        let second = CacheLock::new(cache.clone()).retry_interval(Duration::from_millis(5));
+        let ttl = Duration::from_secs(10);
+
+        let guard = first.try_acquire("job", ttl).await.unwrap().unwrap();
+        assert!(second.try_acquire("job", ttl).await.unwrap().is_none());
+        assert!(second
+            .acquire("job", ttl, Duration::from_millis(20))
+            .await
+            .unwrap()
+            .is_none());
+
+        let waiting = tokio::spawn(async move {
+            second
+                .acquire("job", ttl, Duration::from_secs(5))
+                .await
+                .unwrap()
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(guard);
+
+        let guard = waiting.await.unwrap().unwrap();
+        assert_eq!(
+            cache.0.lock().unwrap().get("job").map(String::as_str),
+            Some(guard.token())
+        );
+
+        assert!(guard.release().await.unwrap());
+        assert!(cache.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn watchdog_detects_lost_locks() {
+        let cache = MapCache::default();
+        let lock = CacheLock::new(cache.clone()).watchdog(true);
+
+        let guard = lock
+            .try_acquire("job", Duration::from_millis(30))
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(guard.is_held());
+
+        // Expired and taken over by someone else
+        cache
+            .0
+            .lock()
+            .unwrap()
+            .insert("job".to_string(), "other".to_string());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!guard.is_held());
+
+        // Never releases someone else's lock
+        assert!(!guard.release().await.unwrap());
+        assert_eq!(
+            cache.0.lock().unwrap().get("job").map(String::as_str),
+            Some("other")
+        );
+    }
+
+    #[tokio::test]
+    async fn watchdog_survives_tiny_ttls() {
+        let lock = CacheLock::new(MapCache::default()).watchdog(true);
+
+        let guard = lock
+            .try_acquire("job", Duration::ZERO)
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert!(!guard.watchdog.as_ref().unwrap().is_finished());
+        assert!(guard.is_held());
+    }
+
+    /// The tests above run against [MapCache], this one covers the Redis commands and scripts.
+    /// Run it with `cargo test -- --ignored` and a server at `REDIS_URL`.
+    #[cfg(feature = "cache-redis")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn redis_locks_across_connections() {
+        let url =
+            std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let pool = || {
+            deadpool_redis::Config::from_url(&url)
+                .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+                .unwrap()
+        };
+        let first = RedisLock::new(pool()).watchdog(true);
+        let second = RedisLock::new(pool());
+        let key = format!("hextacy:test:lock:{}", lock_token());
+        let ttl = Duration::from_millis(300);
+
+        let guard = first.try_acquire(&key, ttl).await.unwrap().unwrap();
+        assert!(second.try_acquire(&key, ttl).await.unwrap().is_none());
+
+        // Extended by the watchdog past its ttl
+        tokio::time::sleep(ttl * 2).await;
+        assert!(guard.is_held());
+        assert!(second.try_acquire(&key, ttl).await.unwrap().is_none());
+
+        assert!(guard.release().await.unwrap());
+        let guard = second.try_acquire(&key, ttl).await.unwrap().unwrap();
+        assert!(guard.release().await.unwrap());
+    }
+}
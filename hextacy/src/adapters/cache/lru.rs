@@ -0,0 +1,619 @@
+use super::{serialize_batch, CacheError, SimpleCacheAccess};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A [SimpleCacheAccess] decorator that keeps hot keys in a bounded, in-process LRU in order
+/// to avoid round-trips to the inner cache.
+///
+/// Reads check the LRU first and fall through to the inner cache on a miss, populating the LRU with the result.
+/// Writes go through to the inner cache and update the LRU entry on success. Deletes always evict the entry.
+///
+/// Entries live for at most the configured `ttl` (or the expiration given on write, whichever is shorter),
+/// so the `ttl` is also the upper bound on how stale a value can be when other processes write to the inner cache.
+///
+/// The LRU store is shared between clones and can be layered over other inner caches (i.e. connections
+/// obtained per request) with [share][LruCache::share].
+#[derive(Debug, Clone)]
+pub struct LruCache<C> {
+    inner: C,
+    store: Arc<Mutex<LruStore>>,
+}
+
+impl<C> LruCache<C> {
+    /// Wraps `inner` with an LRU holding at most `capacity` entries, each living for at most `ttl`.
+    pub fn new(inner: C, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            inner,
+            store: Arc::new(Mutex::new(LruStore::new(capacity, ttl))),
+        }
+    }
+
+    /// Wrap another inner cache with the same LRU store.
+    pub fn share<N>(&self, inner: N) -> LruCache<N> {
+        LruCache {
+            inner,
+            store: self.store.clone(),
+        }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn cached(&self, key: &str) -> Option<String> {
+        self.store.lock().unwrap().get(key)
+    }
+
+    fn populate(&self, key: &str, value: String, ex: Option<usize>) {
+        self.store.lock().unwrap().insert(key, value, ex)
+    }
+
+    fn evict(&self, key: &str) {
+        self.store.lock().unwrap().remove(key)
+    }
+}
+
+impl<C> SimpleCacheAccess for LruCache<C>
+where
+    C: SimpleCacheAccess + Send,
+{
+    async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+        if let Some(value) = self.cached(key) {
+            return Ok(Some(value));
+        }
+        let value = self.inner.get_string(key).await?;
+        if let Some(ref value) = value {
+            self.populate(key, value.clone(), None);
+        }
+        Ok(value)
+    }
+
+    async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        if let Some(value) = self.cached(key).and_then(|v| v.parse().ok()) {
+            return Ok(Some(value));
+        }
+        let value = self.inner.get_i64(key).await?;
+        if let Some(value) = value {
+            self.populate(key, value.to_string(), None);
+        }
+        Ok(value)
+    }
+
+    async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(value) = self.cached(key) {
+            return serde_json::from_str(&value)
+                .map(Some)
+                .map_err(CacheError::from);
+        }
+        let Some(value) = self.inner.get_string(key).await? else {
+            return Ok(None);
+        };
+        let result = serde_json::from_str(&value)?;
+        self.populate(key, value, None);
+        Ok(Some(result))
+    }
+
+    async fn set_str(
+        &mut self,
+        key: &str,
+        value: &str,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        if let Err(e) = self.inner.set_str(key, value, ex).await {
+            self.evict(key);
+            return Err(e);
+        }
+        self.populate(key, value.to_string(), ex);
+        Ok(())
+    }
+
+    async fn set_i64(
+        &mut self,
+        key: &str,
+        value: i64,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        if let Err(e) = self.inner.set_i64(key, value, ex).await {
+            self.evict(key);
+            return Err(e);
+        }
+        self.populate(key, value.to_string(), ex);
+        Ok(())
+    }
+
+    async fn set_json<T>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let value = serde_json::to_string(value)?;
+        self.set_str(key, &value, ex).await
+    }
+
+    /// Counters are always read from the inner cache since they are meant to be shared.
+    async fn set_or_increment(&mut self, key: &str, ex: Option<usize>) -> Result<i64, CacheError> {
+        self.evict(key);
+        self.inner.set_or_increment(key, ex).await
+    }
+
+    async fn set_nx(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<bool, CacheError> {
+        // The key may exist in the inner cache even if it is not in the LRU
+        self.evict(key);
+        self.inner.set_nx(key, value, ttl).await
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+        self.evict(key);
+        self.inner.delete(key).await
+    }
+
+    async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+        {
+            let mut store = self.store.lock().unwrap();
+            for key in keys {
+                store.remove(key);
+            }
+        }
+        self.inner.delete_many(keys).await
+    }
+
+    async fn migrate_keys(
+        &mut self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<u64, CacheError> {
+        let migrated = self.inner.migrate_keys(old_prefix, new_prefix).await;
+        self.store
+            .lock()
+            .unwrap()
+            .evict_migrated(old_prefix, new_prefix);
+        migrated
+    }
+
+    async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        self.inner.scan_keys(prefix).await
+    }
+
+    async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+        self.evict(key);
+        self.inner.delete_if_eq(key, value).await
+    }
+
+    async fn expire_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> Result<bool, CacheError> {
+        self.inner.expire_if_eq(key, value, ttl).await
+    }
+
+    async fn mset_json<T>(
+        &mut self,
+        entries: &[(&str, &T)],
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let serialized = serialize_batch(entries)?;
+        if let Err(e) = self.inner.mset_json(entries, ex).await {
+            for (key, _) in serialized {
+                self.evict(key);
+            }
+            return Err(e);
+        }
+        for (key, value) in serialized {
+            self.populate(key, value, ex);
+        }
+        Ok(())
+    }
+}
+
+/// Keeps track of recency with a monotonically increasing tick. The entry with the lowest
+/// tick is the least recently used one.
+#[derive(Debug)]
+struct LruStore {
+    capacity: usize,
+    ttl: Duration,
+    tick: u64,
+    entries: HashMap<String, LruEntry>,
+    order: BTreeMap<u64, String>,
+}
+
+#[derive(Debug)]
+struct LruEntry {
+    value: String,
+    expires_at: Instant,
+    tick: u64,
+}
+
+impl LruStore {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<String> {
+        let entry = self.entries.get_mut(key)?;
+
+        if entry.expires_at <= Instant::now() {
+            self.remove(key);
+            return None;
+        }
+
+        self.tick += 1;
+        self.order.remove(&entry.tick);
+        self.order.insert(self.tick, key.to_string());
+        entry.tick = self.tick;
+
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: &str, value: String, ex: Option<usize>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.remove(key);
+
+        while self.entries.len() >= self.capacity {
+            let Some((_, lru)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&lru);
+        }
+
+        let ttl = ex.map_or(self.ttl, |ex| self.ttl.min(Duration::from_secs(ex as u64)));
+
+        self.tick += 1;
+        self.order.insert(self.tick, key.to_string());
+        self.entries.insert(
+            key.to_string(),
+            LruEntry {
+                value,
+                expires_at: Instant::now() + ttl,
+                tick: self.tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+        }
+    }
+
+    /// Evicts the entries under the old prefix along with the ones they would be moved to. They are
+    /// never re-keyed since the inner cache can skip keys, i.e. ones that expired mid migration.
+    fn evict_migrated(&mut self, old_prefix: &str, new_prefix: &str) {
+        let keys = self
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(old_prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            self.remove(&format!("{new_prefix}{}", &key[old_prefix.len()..]));
+            self.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    /// Counts the calls that reach the inner cache.
+    #[derive(Debug, Default)]
+    struct CountingCache {
+        map: HashMap<String, String>,
+        gets: usize,
+    }
+
+    impl SimpleCacheAccess for CountingCache {
+        async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+            self.gets += 1;
+            Ok(self.map.get(key).cloned())
+        }
+
+        async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+            self.gets += 1;
+            Ok(self.map.get(key).and_then(|v| v.parse().ok()))
+        }
+
+        async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+        where
+            T: DeserializeOwned,
+        {
+            self.gets += 1;
+            let Some(value) = self.map.get(key) else {
+                return Ok(None);
+            };
+            Ok(Some(serde_json::from_str(value)?))
+        }
+
+        async fn set_str(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Option<usize>,
+        ) -> Result<(), CacheError> {
+            self.map.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        async fn set_i64(
+            &mut self,
+            key: &str,
+            value: i64,
+            _: Option<usize>,
+        ) -> Result<(), CacheError> {
+            self.map.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        async fn set_json<T>(
+            &mut self,
+            key: &str,
+            value: &T,
+            _: Option<usize>,
+        ) -> Result<(), CacheError>
+        where
+            T: Serialize + Sync,
+        {
+            self.map
+                .insert(key.to_string(), serde_json::to_string(value)?);
+            Ok(())
+        }
+
+        async fn set_or_increment(
+            &mut self,
+            key: &str,
+            _: Option<usize>,
+        ) -> Result<i64, CacheError> {
+            let value = self
+                .map
+                .entry(key.to_string())
+                .or_insert_with(|| "0".to_string());
+            let count = value.parse::<i64>().unwrap() + 1;
+            *value = count.to_string();
+            Ok(count)
+        }
+
+        async fn set_nx(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Option<Duration>,
+        ) -> Result<bool, CacheError> {
+            if self.map.contains_key(key) {
+                return Ok(false);
+            }
+            self.map.insert(key.to_string(), value.to_string());
+            Ok(true)
+        }
+
+        async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+            self.map.remove(key);
+            Ok(())
+        }
+
+        async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+            Ok(keys
+                .iter()
+                .filter(|key| self.map.remove(**key).is_some())
+                .count() as u64)
+        }
+
+        async fn migrate_keys(
+            &mut self,
+            old_prefix: &str,
+            new_prefix: &str,
+        ) -> Result<u64, CacheError> {
+            let keys = self
+                .map
+                .keys()
+                .filter(|key| key.starts_with(old_prefix))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in keys.iter() {
+                let value = self.map.remove(key).unwrap();
+                self.map
+                    .insert(format!("{new_prefix}{}", &key[old_prefix.len()..]), value);
+            }
+            Ok(keys.len() as u64)
+        }
+
+        async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+            Ok(self
+                .map
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+
+        async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+            if self.map.get(key).is_some_and(|v| v == value) {
+                self.map.remove(key);
+                return Ok(true);
+            }
+            Ok(false)
+        }
+
+        async fn expire_if_eq(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Duration,
+        ) -> Result<bool, CacheError> {
+            Ok(self.map.get(key).is_some_and(|v| v == value))
+        }
+    }
+
+    #[test]
+    fn second_get_hits_lru() {
+        let mut inner = CountingCache::default();
+        inner.map.insert("foo".to_string(), "bar".to_string());
+
+        let mut cache = LruCache::new(inner, 8, Duration::from_secs(60));
+
+        let first = block_on(cache.get_string("foo")).unwrap();
+        let second = block_on(cache.get_string("foo")).unwrap();
+
+        assert_eq!(first.as_deref(), Some("bar"));
+        assert_eq!(second.as_deref(), Some("bar"));
+        assert_eq!(cache.inner.gets, 1);
+
+        let missing = block_on(cache.get_string("baz")).unwrap();
+        assert!(missing.is_none());
+        assert_eq!(cache.inner.gets, 2);
+    }
+
+    #[test]
+    fn writes_update_and_delete_invalidates() {
+        let mut cache = LruCache::new(CountingCache::default(), 8, Duration::from_secs(60));
+
+        block_on(cache.set_i64("count", 1, None)).unwrap();
+        assert_eq!(block_on(cache.get_i64("count")).unwrap(), Some(1));
+
+        block_on(cache.set_i64("count", 2, None)).unwrap();
+        assert_eq!(block_on(cache.get_i64("count")).unwrap(), Some(2));
+        assert_eq!(cache.inner.gets, 0);
+
+        block_on(cache.delete("count")).unwrap();
+        assert_eq!(block_on(cache.get_i64("count")).unwrap(), None);
+        assert_eq!(cache.inner.gets, 1);
+    }
+
+    #[test]
+    fn counters_bypass_lru() {
+        let mut cache = LruCache::new(CountingCache::default(), 8, Duration::from_secs(60));
+
+        block_on(cache.set_i64("attempts", 5, None)).unwrap();
+        assert_eq!(
+            block_on(cache.set_or_increment("attempts", None)).unwrap(),
+            6
+        );
+        assert_eq!(block_on(cache.get_i64("attempts")).unwrap(), Some(6));
+        assert_eq!(cache.inner.gets, 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_and_expired() {
+        let mut cache = LruCache::new(CountingCache::default(), 2, Duration::from_secs(60));
+
+        block_on(cache.set_json("a", &1, None)).unwrap();
+        block_on(cache.set_json("b", &2, None)).unwrap();
+
+        // Touch `a` so `b` becomes the least recently used
+        assert_eq!(block_on(cache.get_json::<u8>("a")).unwrap(), Some(1));
+
+        block_on(cache.set_json("c", &3, None)).unwrap();
+
+        assert_eq!(block_on(cache.get_json::<u8>("b")).unwrap(), Some(2));
+        assert_eq!(cache.inner.gets, 1);
+
+        let mut cache = cache.share(CountingCache::default());
+        cache.store.lock().unwrap().ttl = Duration::ZERO;
+        block_on(cache.set_str("d", "expired", None)).unwrap();
+        assert_eq!(
+            block_on(cache.get_string("d")).unwrap().as_deref(),
+            Some("expired")
+        );
+        assert_eq!(cache.inner.gets, 1);
+    }
+
+    #[test]
+    fn migrate_keys_evicts_moved_entries() {
+        let mut cache = LruCache::new(CountingCache::default(), 8, Duration::from_secs(60));
+
+        block_on(async {
+            cache.set_str("user:1", "alice", Some(10)).await.unwrap();
+            cache.set_str("user:2", "bob", None).await.unwrap();
+            cache
+                .set_str("tenant:a:user:2", "real", None)
+                .await
+                .unwrap();
+            cache.set_str("session:1", "s", None).await.unwrap();
+        });
+
+        // Expired in the inner cache before it got to it, so only user:1 is moved
+        cache.inner.map.remove("user:2");
+
+        let migrated = block_on(cache.migrate_keys("user:", "tenant:a:user:")).unwrap();
+        assert_eq!(migrated, 1);
+
+        let gets = cache.inner.gets;
+        for (key, value) in [
+            ("tenant:a:user:1", Some("alice")),
+            ("tenant:a:user:2", Some("real")),
+            ("user:1", None),
+            ("user:2", None),
+        ] {
+            assert_eq!(block_on(cache.get_string(key)).unwrap().as_deref(), value);
+        }
+        assert_eq!(cache.inner.gets, gets + 4);
+
+        // Cached again on the miss
+        assert_eq!(
+            block_on(cache.get_string("tenant:a:user:1"))
+                .unwrap()
+                .as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            block_on(cache.get_string("session:1")).unwrap().as_deref(),
+            Some("s")
+        );
+        assert_eq!(cache.inner.gets, gets + 4);
+    }
+
+    #[test]
+    fn delete_many_counts_existing_keys() {
+        let mut cache = LruCache::new(CountingCache::default(), 8, Duration::from_secs(60));
+
+        for key in ["session:1", "session:2", "session:3"] {
+            block_on(cache.set_str(key, "user", None)).unwrap();
+        }
+
+        let deleted =
+            block_on(cache.delete_many(&["session:1", "session:3", "session:404"])).unwrap();
+        assert_eq!(deleted, 2);
+
+        // Evicted from the LRU as well
+        assert_eq!(block_on(cache.get_string("session:1")).unwrap(), None);
+        assert_eq!(block_on(cache.get_string("session:3")).unwrap(), None);
+        assert_eq!(cache.inner.gets, 2);
+        assert_eq!(
+            block_on(cache.get_string("session:2")).unwrap().as_deref(),
+            Some("user")
+        );
+
+        assert_eq!(block_on(cache.delete_many(&[])).unwrap(), 0);
+    }
+}
@@ -3,3 +3,390 @@ pub mod redis;
 
 #[cfg(any(feature = "cache-full", feature = "cache-inmem"))]
 pub mod in_mem;
+
+pub mod aside;
+pub mod invalidation;
+pub mod key;
+pub mod lock;
+pub mod lru;
+pub mod prefix;
+
+pub use key::CacheKey;
+pub use prefix::Prefixed;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::Future, time::Duration};
+use thiserror::Error;
+
+/// A basic set of operations for key-value caches. Implemented on cache connections so adapters
+/// can be written once and run against any cache, as well as any layers in front of one (e.g. [lru::LruCache]).
+///
+/// `ex` is always an optional expiration time in seconds.
+pub trait SimpleCacheAccess {
+    fn get_string(
+        &mut self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<String>, CacheError>> + Send;
+
+    fn get_i64(
+        &mut self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<i64>, CacheError>> + Send;
+
+    fn get_json<T>(
+        &mut self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<T>, CacheError>> + Send
+    where
+        T: DeserializeOwned;
+
+    fn set_str(
+        &mut self,
+        key: &str,
+        value: &str,
+        ex: Option<usize>,
+    ) -> impl Future<Output = Result<(), CacheError>> + Send;
+
+    fn set_i64(
+        &mut self,
+        key: &str,
+        value: i64,
+        ex: Option<usize>,
+    ) -> impl Future<Output = Result<(), CacheError>> + Send;
+
+    fn set_json<T>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ex: Option<usize>,
+    ) -> impl Future<Output = Result<(), CacheError>> + Send
+    where
+        T: Serialize + Sync;
+
+    /// Atomically increment the integer stored at `key` by one, setting it to 1 if it does not exist,
+    /// and return the new value. Concurrent calls never lose updates, making this suitable for counters
+    /// such as throttling failed attempts.
+    ///
+    /// If `ex` is provided, the key's expiration is (re)set on every increment.
+    fn set_or_increment(
+        &mut self,
+        key: &str,
+        ex: Option<usize>,
+    ) -> impl Future<Output = Result<i64, CacheError>> + Send;
+
+    /// Set the key only if it does not already exist and return whether it was set.
+    /// `ttl` has millisecond precision, unlike `ex`.
+    fn set_nx(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> impl Future<Output = Result<bool, CacheError>> + Send;
+
+    fn delete(&mut self, key: &str) -> impl Future<Output = Result<(), CacheError>> + Send;
+
+    /// Delete all the keys in a single round trip and return how many of them existed.
+    fn delete_many(
+        &mut self,
+        keys: &[&str],
+    ) -> impl Future<Output = Result<u64, CacheError>> + Send;
+
+    /// Move every key starting with `old_prefix` under `new_prefix`, i.e. `old_prefix:a` becomes `new_prefix:a`,
+    /// keeping values and remaining expirations. Returns how many keys were moved.
+    /// Keys already existing under the new prefix are overwritten.
+    fn migrate_keys(
+        &mut self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> impl Future<Output = Result<u64, CacheError>> + Send;
+
+    /// Return every key starting with `prefix`, e.g. to [delete_many][SimpleCacheAccess::delete_many]
+    /// everything cached under a namespace. The keys are gathered incrementally and not as a snapshot,
+    /// keys written or deleted during the scan may or may not be returned.
+    fn scan_keys(
+        &mut self,
+        prefix: &str,
+    ) -> impl Future<Output = Result<Vec<String>, CacheError>> + Send;
+
+    /// Atomically delete the key only if it holds `value` and return whether it was deleted.
+    fn delete_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+    ) -> impl Future<Output = Result<bool, CacheError>> + Send;
+
+    /// Atomically reset the key's expiration to `ttl` only if it holds `value` and return whether it was reset.
+    fn expire_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> impl Future<Output = Result<bool, CacheError>> + Send;
+
+    /// Get the JSON values of all the keys, e.g. to warm a cache. Adapters fetch them in a single round trip
+    /// where the backend supports it, by default they are fetched one by one.
+    ///
+    /// Returns one entry per key at the same index as in `keys`, with `None` for missing ones. Values that fail
+    /// to deserialize do not abort the batch, their entries hold the error instead.
+    fn mget_json<T>(
+        &mut self,
+        keys: &[&str],
+    ) -> impl Future<Output = Result<Vec<Result<Option<T>, serde_json::Error>>, CacheError>> + Send
+    where
+        Self: Send,
+        T: DeserializeOwned + Send,
+    {
+        async move {
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                values.push(self.get_string(key).await?);
+            }
+            Ok(deserialize_batch(values))
+        }
+    }
+
+    /// Set all the entries as JSON, each expiring after `ex` if provided. Adapters set them in a single
+    /// round trip where the backend supports it, by default they are set one by one.
+    ///
+    /// Nothing is written if any of the values fails to serialize.
+    fn mset_json<T>(
+        &mut self,
+        entries: &[(&str, &T)],
+        ex: Option<usize>,
+    ) -> impl Future<Output = Result<(), CacheError>> + Send
+    where
+        Self: Send,
+        T: Serialize + Sync,
+    {
+        async move {
+            let entries = serialize_batch(entries)?;
+            for (key, value) in entries {
+                self.set_str(key, &value, ex).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Deserialize the values of a batch get, keeping misses as `None`.
+fn deserialize_batch<T>(values: Vec<Option<String>>) -> Vec<Result<Option<T>, serde_json::Error>>
+where
+    T: DeserializeOwned,
+{
+    values
+        .into_iter()
+        .map(|value| value.map(|v| serde_json::from_str(&v)).transpose())
+        .collect()
+}
+
+/// Serialize the values of a batch set, failing on the first value that does not serialize.
+fn serialize_batch<'a, T>(entries: &[(&'a str, &T)]) -> Result<Vec<(&'a str, String)>, CacheError>
+where
+    T: Serialize,
+{
+    entries
+        .iter()
+        .map(|(key, value)| Ok((*key, serde_json::to_string(value)?)))
+        .collect()
+}
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[cfg(feature = "cache-redis")]
+    #[error("Redis: {0}")]
+    Redis(#[from] deadpool_redis::redis::RedisError),
+
+    #[error("Serde: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Value of {0} is not an integer")]
+    NotAnInteger(String),
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    /// A cache shared between clones, ignoring expiration.
+    #[derive(Debug, Clone, Default)]
+    pub struct MapCache(pub Arc<Mutex<HashMap<String, String>>>);
+
+    impl crate::driver::Driver for MapCache {
+        type Connection = MapCache;
+        type Error = CacheError;
+
+        async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+            Ok(self.clone())
+        }
+    }
+
+    impl SimpleCacheAccess for MapCache {
+        async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+            Ok(self.0.lock().unwrap().get(key).and_then(|v| v.parse().ok()))
+        }
+
+        async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+        where
+            T: DeserializeOwned,
+        {
+            let Some(value) = self.get_string(key).await? else {
+                return Ok(None);
+            };
+            Ok(Some(serde_json::from_str(&value)?))
+        }
+
+        async fn set_str(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Option<usize>,
+        ) -> Result<(), CacheError> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        async fn set_i64(
+            &mut self,
+            key: &str,
+            value: i64,
+            ex: Option<usize>,
+        ) -> Result<(), CacheError> {
+            self.set_str(key, &value.to_string(), ex).await
+        }
+
+        async fn set_json<T>(
+            &mut self,
+            key: &str,
+            value: &T,
+            ex: Option<usize>,
+        ) -> Result<(), CacheError>
+        where
+            T: Serialize + Sync,
+        {
+            let value = serde_json::to_string(value)?;
+            self.set_str(key, &value, ex).await
+        }
+
+        async fn set_or_increment(
+            &mut self,
+            key: &str,
+            _: Option<usize>,
+        ) -> Result<i64, CacheError> {
+            let mut map = self.0.lock().unwrap();
+            let value = map
+                .entry(key.to_string())
+                .or_insert_with(|| "0".to_string());
+            let count = value.parse::<i64>().unwrap() + 1;
+            *value = count.to_string();
+            Ok(count)
+        }
+
+        async fn set_nx(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Option<Duration>,
+        ) -> Result<bool, CacheError> {
+            let mut map = self.0.lock().unwrap();
+            if map.contains_key(key) {
+                return Ok(false);
+            }
+            map.insert(key.to_string(), value.to_string());
+            Ok(true)
+        }
+
+        async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+            let mut map = self.0.lock().unwrap();
+            Ok(keys
+                .iter()
+                .filter(|key| map.remove(**key).is_some())
+                .count() as u64)
+        }
+
+        async fn migrate_keys(
+            &mut self,
+            old_prefix: &str,
+            new_prefix: &str,
+        ) -> Result<u64, CacheError> {
+            let mut map = self.0.lock().unwrap();
+            let keys = map
+                .keys()
+                .filter(|key| key.starts_with(old_prefix))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in keys.iter() {
+                let value = map.remove(key).unwrap();
+                map.insert(format!("{new_prefix}{}", &key[old_prefix.len()..]), value);
+            }
+            Ok(keys.len() as u64)
+        }
+
+        async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+            let map = self.0.lock().unwrap();
+            Ok(map
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+
+        async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+            let mut map = self.0.lock().unwrap();
+            if map.get(key).is_some_and(|v| v == value) {
+                map.remove(key);
+                return Ok(true);
+            }
+            Ok(false)
+        }
+
+        async fn expire_if_eq(
+            &mut self,
+            key: &str,
+            value: &str,
+            _: Duration,
+        ) -> Result<bool, CacheError> {
+            Ok(self.0.lock().unwrap().get(key).is_some_and(|v| v == value))
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_get_and_set() {
+        let mut cache = MapCache::default();
+
+        cache
+            .mset_json(&[("user:1", &"alice"), ("user:2", &"bob")], Some(60))
+            .await
+            .unwrap();
+        cache.set_str("user:3", "not json", None).await.unwrap();
+
+        let result = cache
+            .mget_json::<String>(&["user:2", "user:4", "user:3", "user:1"])
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[0].as_ref().unwrap().as_deref(), Some("bob"));
+        assert_eq!(result[1].as_ref().unwrap(), &None);
+        assert!(result[2].is_err());
+        assert_eq!(result[3].as_ref().unwrap().as_deref(), Some("alice"));
+
+        let empty = cache.mget_json::<String>(&[]).await.unwrap();
+        assert!(empty.is_empty());
+    }
+}
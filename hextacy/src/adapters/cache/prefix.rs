@@ -0,0 +1,279 @@
+use super::{key::SEPARATOR, CacheError, SimpleCacheAccess};
+use crate::driver::Driver;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{sync::Arc, time::Duration};
+
+/// A [Driver] layer namespacing every key of the connections it hands out, so multiple services
+/// or environments can share a cache without reading each other's keys.
+///
+/// The prefix is prepended to keys followed by the [SEPARATOR], i.e. with the `myapp:staging` prefix
+/// the key `auth:42` is stored as `myapp:staging:auth:42`. Adapters keep using unprefixed keys, keys returned
+/// from [scan_keys][SimpleCacheAccess::scan_keys] have the prefix stripped.
+///
+/// ### Example
+///
+/// ```ignore
+/// let cache = Prefixed::new(pool, env::get_or_default("CACHE_PREFIX", "myapp:staging"));
+/// let auth_cache = AuthenticationCache { driver: cache };
+/// ```
+#[derive(Debug, Clone)]
+pub struct Prefixed<D> {
+    driver: D,
+    prefix: Arc<str>,
+}
+
+impl<D> Prefixed<D> {
+    pub fn new(driver: D, prefix: impl Into<String>) -> Self {
+        let mut prefix = prefix.into();
+        prefix.push(SEPARATOR);
+        Self {
+            driver,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// The prefix, including the trailing separator.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+}
+
+impl<D> Driver for Prefixed<D>
+where
+    D: Driver,
+{
+    type Connection = PrefixedConnection<D::Connection>;
+    type Error = D::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        Ok(PrefixedConnection {
+            inner: self.driver.connect().await?,
+            prefix: self.prefix.clone(),
+        })
+    }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.driver.health_check().await
+    }
+}
+
+/// A connection prepending the prefix of the [Prefixed] driver it was obtained from to every key.
+#[derive(Debug, Clone)]
+pub struct PrefixedConnection<C> {
+    inner: C,
+    prefix: Arc<str>,
+}
+
+impl<C> PrefixedConnection<C> {
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+impl<C> SimpleCacheAccess for PrefixedConnection<C>
+where
+    C: SimpleCacheAccess + Send,
+{
+    async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+        let key = self.key(key);
+        self.inner.get_string(&key).await
+    }
+
+    async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        let key = self.key(key);
+        self.inner.get_i64(&key).await
+    }
+
+    async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+    where
+        T: DeserializeOwned,
+    {
+        let key = self.key(key);
+        self.inner.get_json(&key).await
+    }
+
+    async fn set_str(
+        &mut self,
+        key: &str,
+        value: &str,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        let key = self.key(key);
+        self.inner.set_str(&key, value, ex).await
+    }
+
+    async fn set_i64(
+        &mut self,
+        key: &str,
+        value: i64,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        let key = self.key(key);
+        self.inner.set_i64(&key, value, ex).await
+    }
+
+    async fn set_json<T>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let key = self.key(key);
+        self.inner.set_json(&key, value, ex).await
+    }
+
+    async fn set_or_increment(&mut self, key: &str, ex: Option<usize>) -> Result<i64, CacheError> {
+        let key = self.key(key);
+        self.inner.set_or_increment(&key, ex).await
+    }
+
+    async fn set_nx(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<bool, CacheError> {
+        let key = self.key(key);
+        self.inner.set_nx(&key, value, ttl).await
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+        let key = self.key(key);
+        self.inner.delete(&key).await
+    }
+
+    async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+        let keys = keys.iter().map(|key| self.key(key)).collect::<Vec<_>>();
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+        self.inner.delete_many(&keys).await
+    }
+
+    async fn migrate_keys(
+        &mut self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<u64, CacheError> {
+        let (old_prefix, new_prefix) = (self.key(old_prefix), self.key(new_prefix));
+        self.inner.migrate_keys(&old_prefix, &new_prefix).await
+    }
+
+    async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let prefix = self.key(prefix);
+        let keys = self.inner.scan_keys(&prefix).await?;
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&*self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+        let key = self.key(key);
+        self.inner.delete_if_eq(&key, value).await
+    }
+
+    async fn expire_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> Result<bool, CacheError> {
+        let key = self.key(key);
+        self.inner.expire_if_eq(&key, value, ttl).await
+    }
+
+    async fn mget_json<T>(
+        &mut self,
+        keys: &[&str],
+    ) -> Result<Vec<Result<Option<T>, serde_json::Error>>, CacheError>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let keys = keys.iter().map(|key| self.key(key)).collect::<Vec<_>>();
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+        self.inner.mget_json(&keys).await
+    }
+
+    async fn mset_json<T>(
+        &mut self,
+        entries: &[(&str, &T)],
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let keys = entries
+            .iter()
+            .map(|(key, _)| self.key(key))
+            .collect::<Vec<_>>();
+        let entries = keys
+            .iter()
+            .zip(entries)
+            .map(|(key, (_, value))| (key.as_str(), *value))
+            .collect::<Vec<_>>();
+        self.inner.mset_json(&entries, ex).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::cache::{tests::MapCache, CacheKey};
+
+    #[tokio::test]
+    async fn prefixes_isolate_drivers() {
+        let shared = MapCache::default();
+        let staging = Prefixed::new(shared.clone(), "myapp:staging");
+        let production = Prefixed::new(shared.clone(), "myapp:production");
+
+        let mut staging = staging.connect().await.unwrap();
+        let mut production = production.connect().await.unwrap();
+
+        let key = CacheKey::new("auth").segment(42);
+        staging.set_str(&key, "staging", None).await.unwrap();
+        assert_eq!(production.get_string(&key).await.unwrap(), None);
+        assert!(production.set_nx(&key, "production", None).await.unwrap());
+
+        assert_eq!(
+            staging.get_string(&key).await.unwrap().as_deref(),
+            Some("staging")
+        );
+        assert_eq!(
+            shared
+                .0
+                .lock()
+                .unwrap()
+                .get("myapp:production:auth:42")
+                .map(String::as_str),
+            Some("production")
+        );
+
+        staging.set_str("auth:43", "staging", None).await.unwrap();
+        staging.set_str("session:1", "staging", None).await.unwrap();
+
+        let mut keys = staging.scan_keys("auth:").await.unwrap();
+        keys.sort();
+        assert_eq!(keys, ["auth:42", "auth:43"]);
+        assert_eq!(production.scan_keys("auth:").await.unwrap(), ["auth:42"]);
+
+        // Busting a namespace leaves other prefixes alone
+        let keys = staging.scan_keys("auth:").await.unwrap();
+        let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(staging.delete_many(&keys).await.unwrap(), 2);
+        assert_eq!(staging.scan_keys("").await.unwrap(), ["session:1"]);
+        assert_eq!(
+            production.get_string(&key).await.unwrap().as_deref(),
+            Some("production")
+        );
+    }
+}
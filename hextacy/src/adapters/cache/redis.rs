@@ -1,8 +1,12 @@
+use super::{deserialize_batch, serialize_batch, CacheError, SimpleCacheAccess};
 use crate::driver::Driver;
-use deadpool_redis::redis::{AsyncCommands, FromRedisValue, ToRedisArgs};
+use deadpool_redis::redis::{cmd, pipe, AsyncCommands, FromRedisValue, ToRedisArgs};
 use deadpool_redis::{Connection, Pool};
 use serde::{de::DeserializeOwned, Serialize};
-use std::future::Future;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
 
 pub type RedisConnection = Connection;
 
@@ -13,6 +17,17 @@ impl Driver for Pool {
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
         self.get().await
     }
+
+    /// Sends a `PING` on a pooled connection.
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        let start = Instant::now();
+        let mut conn = self.get().await?;
+        cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await
+            .map_err(deadpool_redis::PoolError::Backend)?;
+        Ok(start.elapsed())
+    }
 }
 
 /// Utility trait for adapters that use Redis. Provides a basic set of functionality out of the box.
@@ -110,3 +125,264 @@ pub trait RedisExt {
         }
     }
 }
+
+impl SimpleCacheAccess for RedisConnection {
+    async fn get_string(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+        self.get(key).await.map_err(CacheError::from)
+    }
+
+    async fn get_i64(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        self.get(key).await.map_err(CacheError::from)
+    }
+
+    async fn get_json<T>(&mut self, key: &str) -> Result<Option<T>, CacheError>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(result) = self.get::<_, Option<String>>(key).await? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&result)
+            .map(Some)
+            .map_err(CacheError::from)
+    }
+
+    async fn set_str(
+        &mut self,
+        key: &str,
+        value: &str,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        if let Some(ex) = ex {
+            self.set_ex(key, value, ex).await.map_err(CacheError::from)
+        } else {
+            self.set(key, value).await.map_err(CacheError::from)
+        }
+    }
+
+    async fn set_i64(
+        &mut self,
+        key: &str,
+        value: i64,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError> {
+        if let Some(ex) = ex {
+            self.set_ex(key, value, ex).await.map_err(CacheError::from)
+        } else {
+            self.set(key, value).await.map_err(CacheError::from)
+        }
+    }
+
+    async fn set_json<T>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let value = serde_json::to_string(value)?;
+        self.set_str(key, &value, ex).await
+    }
+
+    async fn set_or_increment(&mut self, key: &str, ex: Option<usize>) -> Result<i64, CacheError> {
+        let Some(ex) = ex else {
+            return self.incr(key, 1).await.map_err(CacheError::from);
+        };
+        let (count,): (i64,) = pipe()
+            .atomic()
+            .incr(key, 1)
+            .expire(key, ex)
+            .ignore()
+            .query_async(self)
+            .await?;
+        Ok(count)
+    }
+
+    async fn set_nx(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+    ) -> Result<bool, CacheError> {
+        let mut cmd = cmd("SET");
+        cmd.arg(key).arg(value).arg("NX");
+        if let Some(ttl) = ttl {
+            // Redis rejects a PX of 0, sub-millisecond TTLs expire as soon as they can
+            cmd.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        let result: Option<String> = cmd.query_async(self).await?;
+        Ok(result.is_some())
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), CacheError> {
+        self.del(key).await.map_err(CacheError::from)
+    }
+
+    /// Sends a `DEL` per key in a pipeline rather than a single multi-key `DEL`, so keys hashing
+    /// to different slots do not fail in cluster mode.
+    async fn delete_many(&mut self, keys: &[&str]) -> Result<u64, CacheError> {
+        if keys.is_empty() {
+            return Ok(0);
+        }
+        let mut pipe = pipe();
+        for key in keys {
+            pipe.del(*key);
+        }
+        let deleted: Vec<u64> = pipe.query_async(self).await?;
+        Ok(deleted.into_iter().sum())
+    }
+
+    /// Collects the keys with `SCAN` before renaming any, since renamed keys could otherwise be
+    /// returned by the scan again. `RENAME` keeps the key's TTL. Keys expiring in between are skipped.
+    async fn migrate_keys(
+        &mut self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<u64, CacheError> {
+        let keys = self.scan_keys(old_prefix).await?;
+
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let mut pipe = pipe();
+        for key in keys.iter() {
+            pipe.cmd("EVAL")
+                .arg(RENAME_IF_EXISTS_SCRIPT)
+                .arg(2)
+                .arg(key)
+                .arg(format!("{new_prefix}{}", &key[old_prefix.len()..]));
+        }
+        let renamed: Vec<u64> = pipe.query_async(self).await?;
+        Ok(renamed.into_iter().sum())
+    }
+
+    async fn scan_keys(&mut self, prefix: &str) -> Result<Vec<String>, CacheError> {
+        let pattern = format!("{}*", escape_glob(prefix));
+        let mut iter = self.scan_match::<_, String>(pattern).await?;
+        let mut keys = vec![];
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    async fn delete_if_eq(&mut self, key: &str, value: &str) -> Result<bool, CacheError> {
+        let deleted: i64 = cmd("EVAL")
+            .arg(DELETE_IF_EQ_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(value)
+            .query_async(self)
+            .await?;
+        Ok(deleted == 1)
+    }
+
+    async fn expire_if_eq(
+        &mut self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> Result<bool, CacheError> {
+        let expired: i64 = cmd("EVAL")
+            .arg(EXPIRE_IF_EQ_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(value)
+            .arg(ttl.as_millis().max(1) as u64)
+            .query_async(self)
+            .await?;
+        Ok(expired == 1)
+    }
+
+    /// Uses `MGET`.
+    async fn mget_json<T>(
+        &mut self,
+        keys: &[&str],
+    ) -> Result<Vec<Result<Option<T>, serde_json::Error>>, CacheError>
+    where
+        T: DeserializeOwned + Send,
+    {
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let values: Vec<Option<String>> = cmd("MGET").arg(keys).query_async(self).await?;
+        Ok(deserialize_batch(values))
+    }
+
+    /// Uses `MSET`, or a transaction of `SET`s if the entries expire since `MSET` cannot set expirations.
+    async fn mset_json<T>(
+        &mut self,
+        entries: &[(&str, &T)],
+        ex: Option<usize>,
+    ) -> Result<(), CacheError>
+    where
+        T: Serialize + Sync,
+    {
+        let entries = serialize_batch(entries)?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let Some(ex) = ex else {
+            return self.mset(&entries).await.map_err(CacheError::from);
+        };
+
+        let mut pipe = pipe();
+        pipe.atomic();
+        for (key, value) in entries.iter() {
+            pipe.set_ex(key, value, ex).ignore();
+        }
+        pipe.query_async(self).await.map_err(CacheError::from)
+    }
+}
+
+const RENAME_IF_EXISTS_SCRIPT: &str = r#"
+if redis.call("EXISTS", KEYS[1]) == 1 then
+    redis.call("RENAME", KEYS[1], KEYS[2])
+    return 1
+else
+    return 0
+end
+"#;
+
+/// Escapes the characters `SCAN MATCH` treats as glob patterns.
+fn escape_glob(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+const DELETE_IF_EQ_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+const EXPIRE_IF_EQ_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::escape_glob;
+
+    #[test]
+    fn escapes_glob_patterns() {
+        assert_eq!(escape_glob("user:"), "user:");
+        assert_eq!(escape_glob("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+}
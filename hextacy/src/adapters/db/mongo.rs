@@ -1,5 +1,6 @@
 use crate::driver::{Atomic, Driver};
-use mongodb::{Client, ClientSession};
+use mongodb::{bson::doc, Client, ClientSession};
+use std::time::{Duration, Instant};
 
 impl Driver for Client {
     type Connection = ClientSession;
@@ -8,6 +9,15 @@ impl Driver for Client {
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
         self.start_session(None).await
     }
+
+    /// Runs the `ping` admin command.
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        let start = Instant::now();
+        self.database("admin")
+            .run_command(doc! { "ping": 1 }, None)
+            .await?;
+        Ok(start.elapsed())
+    }
 }
 
 impl Atomic for ClientSession {
@@ -28,4 +38,28 @@ impl Atomic for ClientSession {
         ClientSession::abort_transaction(&mut tx).await?;
         Ok(())
     }
+
+    /// MongoDB has no savepoints, always fails.
+    async fn start_savepoint(_: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        Err(savepoints_unsupported())
+    }
+
+    /// MongoDB has no savepoints, always fails.
+    async fn release_savepoint(_: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        Err(savepoints_unsupported())
+    }
+
+    /// MongoDB has no savepoints, always fails.
+    async fn rollback_to_savepoint(_: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        Err(savepoints_unsupported())
+    }
+}
+
+// The driver's transaction error kind can't be constructed outside of it
+fn savepoints_unsupported() -> mongodb::error::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "MongoDB transactions do not support savepoints",
+    )
+    .into()
 }
@@ -5,9 +5,27 @@
 ))]
 pub mod diesel;
 
+#[cfg(all(
+    feature = "db-sqlite-diesel",
+    not(feature = "db-postgres-diesel"),
+    not(feature = "db-mysql-diesel")
+))]
+pub mod sqlite;
+
 #[cfg(any(
     feature = "db-postgres-seaorm",
     feature = "db-mysql-seaorm",
     feature = "db-sqlite-seaorm"
 ))]
 pub mod seaorm;
+
+/// Checking the live database against the schema generated by diesel.
+#[cfg(any(
+    feature = "db-postgres-diesel",
+    feature = "db-mysql-diesel",
+    feature = "db-sqlite-diesel"
+))]
+pub mod schema;
+
+#[cfg(any(feature = "db-postgres-diesel", feature = "db-postgres-seaorm"))]
+pub mod tls;
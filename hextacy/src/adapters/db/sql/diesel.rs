@@ -1,9 +1,20 @@
 use crate::driver::{Atomic, Driver};
 use cfg_if::cfg_if;
 use diesel::{
-    connection::TransactionManager,
-    r2d2::{ConnectionManager, Pool, PooledConnection},
+    connection::{Instrumentation, InstrumentationEvent, TransactionManager},
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection},
+    result::{DatabaseErrorKind, Error as DieselError},
 };
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::warn;
 
 cfg_if!(
     if #[cfg(feature = "db-postgres-diesel")] {
@@ -20,6 +31,8 @@ cfg_if!(
 pub type DieselConnection = PooledConnection<ConnectionManager<Connection>>;
 pub type DieselPool = Pool<ConnectionManager<Connection>>;
 
+/// Uses the default [health_check][Driver::health_check]. r2d2 validates connections on checkout with
+/// a `SELECT 1` unless `test_on_check_out` is disabled, so acquiring one is already a round trip.
 impl Driver for DieselPool {
     type Connection = DieselConnection;
     type Error = diesel::r2d2::PoolError;
@@ -34,6 +47,23 @@ impl Atomic for DieselConnection {
     type Error = diesel::result::Error;
 
     async fn start_transaction(mut self) -> Result<Self, Self::Error> {
+        #[cfg(all(
+            feature = "db-sqlite-diesel",
+            not(feature = "db-postgres-diesel"),
+            not(feature = "db-mysql-diesel")
+        ))]
+        {
+            use diesel::connection::AnsiTransactionManager;
+            // SQLite's default deferred transactions only take the write lock on their first write,
+            // failing with SQLITE_BUSY if another connection took it in the meantime
+            if AnsiTransactionManager::transaction_manager_status_mut(&mut *self)
+                .transaction_depth()?
+                .is_none()
+            {
+                AnsiTransactionManager::begin_transaction_sql(&mut *self, "BEGIN IMMEDIATE")?;
+                return Ok(self);
+            }
+        }
         diesel::connection::AnsiTransactionManager::begin_transaction(&mut *self)?;
         Ok(self)
     }
@@ -47,4 +77,793 @@ impl Atomic for DieselConnection {
         diesel::connection::AnsiTransactionManager::rollback_transaction(&mut *tx)?;
         Ok(())
     }
+
+    /// Diesel nests transactions with savepoints, so this begins a nested transaction, failing if none is open.
+    async fn start_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        use diesel::connection::AnsiTransactionManager;
+        if AnsiTransactionManager::transaction_manager_status_mut(&mut **tx)
+            .transaction_depth()?
+            .is_none()
+        {
+            return Err(DieselError::NotInTransaction);
+        }
+        AnsiTransactionManager::begin_transaction(&mut **tx)
+    }
+
+    async fn release_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        diesel::connection::AnsiTransactionManager::commit_transaction(&mut **tx)
+    }
+
+    async fn rollback_to_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        diesel::connection::AnsiTransactionManager::rollback_transaction(&mut **tx)
+    }
+}
+
+/// How long a replica has to provide a connection before reads fall back to the primary.
+pub const REPLICA_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A primary pool along with read replicas. As a [Driver] it only ever connects to the primary,
+/// so writes and transactions never reach a replica.
+///
+/// Read only queries opt into the replicas with [connect_read][ReplicatedPool::connect_read], or by giving the
+/// repository the [reads][ReplicatedPool::reads] driver. Replicas are used round-robin and if one can't provide
+/// a connection, the primary is used instead.
+///
+/// Connections are checked out on the blocking thread pool. Replicas are given [REPLICA_TIMEOUT] to provide one,
+/// instead of the pool's connection timeout, so a replica that is down delays reads only briefly.
+/// It can be changed with [with_replica_timeout][ReplicatedPool::with_replica_timeout].
+///
+/// Replica connections are [ReadConnection]s, which do not implement [Atomic].
+///
+/// ### Example
+///
+/// ```ignore
+/// let pool = ReplicatedPool::from_urls(&primary_url, &[&replica_a_url, &replica_b_url])?;
+/// let users = UserAdapter { driver: pool.clone() };
+/// let audit = AuditAdapter { driver: pool.reads() };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplicatedPool {
+    primary: DieselPool,
+    replicas: Arc<[DieselPool]>,
+    next: Arc<AtomicUsize>,
+    replica_timeout: Duration,
+}
+
+impl ReplicatedPool {
+    pub fn new(primary: DieselPool, replicas: Vec<DieselPool>) -> Self {
+        Self {
+            primary,
+            replicas: replicas.into(),
+            next: Arc::default(),
+            replica_timeout: REPLICA_TIMEOUT,
+        }
+    }
+
+    /// Set how long a replica has to provide a connection before reads fall back to the primary.
+    pub fn with_replica_timeout(mut self, timeout: Duration) -> Self {
+        self.replica_timeout = timeout;
+        self
+    }
+
+    /// Builds a pool with the default settings for each URL.
+    pub fn from_urls(primary: &str, replicas: &[&str]) -> Result<Self, diesel::r2d2::PoolError> {
+        let primary = Pool::builder().build(ConnectionManager::new(primary))?;
+        let replicas = replicas
+            .iter()
+            .map(|url| Pool::builder().build(ConnectionManager::new(*url)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(primary, replicas))
+    }
+
+    pub fn primary(&self) -> &DieselPool {
+        &self.primary
+    }
+
+    /// A connection from the next replica, or from the primary if there are no replicas or the replica is unavailable.
+    pub async fn connect_read(&self) -> Result<ReadConnection, diesel::r2d2::PoolError> {
+        if self.replicas.is_empty() {
+            return checkout(&self.primary, None).await.map(ReadConnection);
+        }
+
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        match checkout(&self.replicas[i], Some(self.replica_timeout)).await {
+            Ok(conn) => Ok(ReadConnection(conn)),
+            Err(e) => {
+                warn!("Replica {i} unavailable, reading from the primary: {e}");
+                checkout(&self.primary, None).await.map(ReadConnection)
+            }
+        }
+    }
+
+    /// A driver connecting to the replicas, for repositories that only read.
+    pub fn reads(&self) -> ReadReplicas {
+        ReadReplicas(self.clone())
+    }
+}
+
+impl Driver for ReplicatedPool {
+    type Connection = DieselConnection;
+    type Error = diesel::r2d2::PoolError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.primary.get()
+    }
+}
+
+/// Checks out a connection on the blocking thread pool, waiting for at most `timeout` if given
+/// and the pool's connection timeout otherwise.
+async fn checkout(
+    pool: &DieselPool,
+    timeout: Option<Duration>,
+) -> Result<DieselConnection, diesel::r2d2::PoolError> {
+    let pool = pool.clone();
+    let result = tokio::task::spawn_blocking(move || match timeout {
+        Some(timeout) => pool.get_timeout(timeout),
+        None => pool.get(),
+    })
+    .await;
+    match result {
+        Ok(conn) => conn,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Connects to the replicas of a [ReplicatedPool].
+#[derive(Debug, Clone)]
+pub struct ReadReplicas(ReplicatedPool);
+
+impl Driver for ReadReplicas {
+    type Connection = ReadConnection;
+    type Error = diesel::r2d2::PoolError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.0.connect_read().await
+    }
+}
+
+/// A connection obtained for reading, usually from a replica. Derefs to the pooled connection.
+pub struct ReadConnection(DieselConnection);
+
+impl std::fmt::Debug for ReadConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ReadConnection").finish_non_exhaustive()
+    }
+}
+
+impl Deref for ReadConnection {
+    type Target = DieselConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ReadConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Error for diesel adapters. Unique constraint violations are separated into [Conflict][RepoAdapterError::Conflict]
+/// so services can respond with a meaningful message, i.e. a 409 with "username already taken",
+/// instead of treating them as generic database errors.
+#[derive(Debug, Error)]
+pub enum RepoAdapterError {
+    #[error("Conflict: {}", constraint.as_deref().unwrap_or("unique constraint violated"))]
+    Conflict { constraint: Option<String> },
+
+    #[error("Diesel: {0}")]
+    Diesel(DieselError),
+
+    #[error("Pool: {0}")]
+    Pool(#[from] diesel::r2d2::PoolError),
+
+    #[error("Blocking task: {0}")]
+    Blocking(#[from] tokio::task::JoinError),
+}
+
+impl From<DieselError> for RepoAdapterError {
+    fn from(value: DieselError) -> Self {
+        match value {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, ref info) => {
+                // SQLite does not report constraint names, but it does report the columns
+                let constraint = info.constraint_name().map(String::from).or_else(|| {
+                    info.message()
+                        .strip_prefix("UNIQUE constraint failed: ")
+                        .map(String::from)
+                });
+                Self::Conflict { constraint }
+            }
+            e => Self::Diesel(e),
+        }
+    }
+}
+
+/// Diesel is synchronous, so calling it directly from async code blocks the executor thread for the duration
+/// of the query. This obtains a connection from the pool and runs `f` with it on tokio's blocking thread pool instead,
+/// returning the connection to the pool afterwards.
+///
+/// ### Example
+///
+/// ```ignore
+/// let user = blocking(&self.pool, move |conn| find_one!(conn, users::table, users::id => id)).await?;
+/// ```
+pub async fn blocking<F, R, E>(pool: &DieselPool, f: F) -> Result<R, E>
+where
+    F: FnOnce(&mut DieselConnection) -> Result<R, E> + Send + 'static,
+    R: Send + 'static,
+    E: From<RepoAdapterError> + Send + 'static,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool.get().map_err(RepoAdapterError::from)?;
+        f(&mut conn)
+    })
+    .await
+    .map_err(|e| E::from(RepoAdapterError::from(e)))?
+}
+
+/// Loads the first row of `table` matching all of the given `column => value` equality filters.
+/// Expands to a plain diesel query so it returns a `QueryResult`, which `?` converts to the adapter's error.
+/// Chain `.optional()` to get `None` instead of `NotFound`.
+///
+/// ### Example
+///
+/// ```ignore
+/// use crate::db::schema::sessions::dsl;
+///
+/// let session: Session = find_one!(&mut conn, dsl::sessions, dsl::id => id, dsl::revoked => false)?;
+/// ```
+#[macro_export]
+macro_rules! find_one {
+    ($conn:expr, $table:expr $(, $col:expr => $val:expr)* $(,)?) => {{
+        #[allow(unused_imports)]
+        use diesel::{ExpressionMethods as _, QueryDsl as _, RunQueryDsl as _};
+        $table $(.filter($col.eq($val)))* .first($conn)
+    }};
+}
+
+/// Loads all rows of `table` matching all of the given `column => value` equality filters.
+/// See [find_one] for details.
+///
+/// ### Example
+///
+/// ```ignore
+/// use crate::db::schema::sessions::dsl;
+///
+/// let sessions: Vec<Session> = find_many!(&mut conn, dsl::sessions, dsl::user_id => user_id)?;
+/// ```
+#[macro_export]
+macro_rules! find_many {
+    ($conn:expr, $table:expr $(, $col:expr => $val:expr)* $(,)?) => {{
+        #[allow(unused_imports)]
+        use diesel::{ExpressionMethods as _, QueryDsl as _, RunQueryDsl as _};
+        $table $(.filter($col.eq($val)))* .load($conn)
+    }};
+}
+
+/// Direction for [order_by].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Orders a boxed query by the column mapped to a validated sort key, in the given [SortDirection].
+///
+/// Every sortable column is listed explicitly as `pattern => column`, so clients choose the sort
+/// without any SQL being built from their input. The query must be boxed, i.e. `into_boxed()`,
+/// since each column yields a different type otherwise. Chain `then_order_by` on the primary key
+/// for a total order when paginating.
+///
+/// ### Example
+///
+/// ```ignore
+/// use crate::db::schema::users::dsl;
+///
+/// let query = order_by!(dsl::users.into_boxed(), sort, direction, {
+///     UserSortBy::Username => dsl::username,
+///     UserSortBy::CreatedAt => dsl::created_at,
+/// });
+/// let users: Vec<User> = query.then_order_by(dsl::id).load(&mut conn)?;
+/// ```
+#[macro_export]
+macro_rules! order_by {
+    ($query:expr, $sort:expr, $direction:expr, { $($key:pat => $col:expr),+ $(,)? }) => {{
+        #[allow(unused_imports)]
+        use diesel::{ExpressionMethods as _, QueryDsl as _};
+        let query = $query;
+        match ($sort, $direction) {
+            $(
+                ($key, $crate::adapters::db::sql::diesel::SortDirection::Asc) => query.order_by($col.asc()),
+                ($key, $crate::adapters::db::sql::diesel::SortDirection::Desc) => query.order_by($col.desc()),
+            )+
+        }
+    }};
+}
+
+/// Counts the queries executed on the connections it is installed on, so tests can catch N+1 queries
+/// by asserting an upper bound on the queries an operation issues.
+///
+/// Install it on every connection of a pool with [connection_customizer][diesel::r2d2::Builder::connection_customizer],
+/// or on a single connection with [instrument][QueryCounter::instrument]. Clones share the count, so use a separate
+/// counter for tests running in parallel. Pools validate connections with a query on checkout by default,
+/// disable it with `test_on_check_out(false)` to count only the queries issued by the code under test.
+///
+/// ### Example
+///
+/// ```ignore
+/// let counter = QueryCounter::new();
+/// let pool = DieselPool::builder()
+///     .test_on_check_out(false)
+///     .connection_customizer(Box::new(counter.clone()))
+///     .build(ConnectionManager::new(url))?;
+///
+/// let scope = counter.scope();
+/// repo.get_user_with_sessions(id).await?;
+/// scope.assert_at_most(2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryCounter {
+    count: Arc<AtomicUsize>,
+}
+
+impl QueryCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn instrument<C: diesel::Connection>(&self, conn: &mut C) {
+        conn.set_instrumentation(self.clone())
+    }
+
+    /// Queries executed since the counter was created.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Start counting queries from zero.
+    pub fn scope(&self) -> QueryScope {
+        QueryScope {
+            counter: self.clone(),
+            start: self.count(),
+        }
+    }
+}
+
+impl Instrumentation for QueryCounter {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        if let InstrumentationEvent::StartQuery { .. } = event {
+            self.count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl CustomizeConnection<Connection, diesel::r2d2::Error> for QueryCounter {
+    fn on_acquire(&self, conn: &mut Connection) -> Result<(), diesel::r2d2::Error> {
+        self.instrument(conn);
+        Ok(())
+    }
+}
+
+/// Queries counted by a [QueryCounter] since the scope was started.
+#[derive(Debug)]
+pub struct QueryScope {
+    counter: QueryCounter,
+    start: usize,
+}
+
+impl QueryScope {
+    pub fn queries(&self) -> usize {
+        self.counter.count() - self.start
+    }
+
+    /// Panics if more than `max` queries were executed in the scope.
+    #[track_caller]
+    pub fn assert_at_most(&self, max: usize) {
+        let queries = self.queries();
+        assert!(
+            queries <= max,
+            "Expected at most {max} queries, {queries} were executed"
+        );
+    }
+}
+
+#[cfg(all(test, feature = "db-sqlite-diesel"))]
+mod tests {
+    use diesel::{prelude::*, sql_query, Connection, SqliteConnection};
+
+    diesel::table! {
+        sessions (id) {
+            id -> Text,
+            user_id -> Text,
+            revoked -> Bool,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Queryable)]
+    struct Session {
+        id: String,
+        user_id: String,
+        revoked: bool,
+    }
+
+    fn setup() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        sql_query("CREATE TABLE sessions (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, revoked BOOLEAN NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+        sql_query("INSERT INTO sessions VALUES ('a', 'bob', 0), ('b', 'bob', 1), ('c', 'bob', 0), ('d', 'alice', 0)")
+            .execute(&mut conn)
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn find_one_matches_hand_written_query() {
+        let mut conn = setup();
+
+        let expected = sessions::table
+            .filter(sessions::id.eq("a"))
+            .filter(sessions::revoked.eq(false))
+            .first::<Session>(&mut conn)
+            .unwrap();
+
+        let found: Session =
+            find_one!(&mut conn, sessions::table, sessions::id => "a", sessions::revoked => false)
+                .unwrap();
+        assert_eq!(found, expected);
+
+        let revoked: Option<Session> =
+            find_one!(&mut conn, sessions::table, sessions::id => "b", sessions::revoked => false)
+                .optional()
+                .unwrap();
+        assert!(revoked.is_none());
+    }
+
+    #[test]
+    fn unique_violation_is_a_conflict() {
+        use super::RepoAdapterError;
+
+        let mut conn = setup();
+
+        let result: Result<usize, RepoAdapterError> =
+            sql_query("INSERT INTO sessions VALUES ('a', 'eve', 0)")
+                .execute(&mut conn)
+                .map_err(RepoAdapterError::from);
+
+        match result {
+            Err(RepoAdapterError::Conflict { constraint }) => {
+                assert_eq!(constraint.as_deref(), Some("sessions.id"))
+            }
+            other => panic!("expected conflict, got {other:?}"),
+        }
+
+        let result = sql_query("INSERT INTO nope VALUES (1)")
+            .execute(&mut conn)
+            .map_err(RepoAdapterError::from);
+        assert!(matches!(result, Err(RepoAdapterError::Diesel(_))));
+    }
+
+    /// Connects to an in memory SQLite database, so SQLite needs to be the selected backend.
+    #[cfg(not(any(feature = "db-postgres-diesel", feature = "db-mysql-diesel")))]
+    #[tokio::test]
+    async fn blocking_queries_do_not_block_the_executor() {
+        use super::{blocking, DieselPool, RepoAdapterError};
+        use diesel::r2d2::ConnectionManager;
+        use std::{
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+            time::Duration,
+        };
+
+        let pool = DieselPool::builder()
+            .max_size(1)
+            .build(ConnectionManager::new(":memory:"))
+            .unwrap();
+
+        // The test runtime is single threaded so the ticker only progresses if the query runs elsewhere
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+
+        let result = blocking(&pool, |conn| {
+            std::thread::sleep(Duration::from_millis(200));
+            sql_query("SELECT 1")
+                .execute(conn)
+                .map_err(RepoAdapterError::from)
+        })
+        .await
+        .unwrap();
+
+        ticker.abort();
+        assert_eq!(result, 0);
+        assert!(ticks.load(Ordering::SeqCst) >= 5);
+    }
+
+    #[test]
+    fn find_many_matches_hand_written_query() {
+        let mut conn = setup();
+
+        let expected = sessions::table
+            .filter(sessions::user_id.eq("bob"))
+            .filter(sessions::revoked.eq(false))
+            .load::<Session>(&mut conn)
+            .unwrap();
+
+        let found: Vec<Session> = find_many!(
+            &mut conn,
+            sessions::table,
+            sessions::user_id => "bob",
+            sessions::revoked => false,
+        )
+        .unwrap();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found, expected);
+
+        let all: Vec<Session> = find_many!(&mut conn, sessions::table).unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    diesel::table! {
+        users (id) {
+            id -> Integer,
+            username -> Text,
+            created_at -> Integer,
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum UserSortBy {
+        Username,
+        CreatedAt,
+    }
+
+    fn sorted(
+        conn: &mut SqliteConnection,
+        sort: UserSortBy,
+        direction: super::SortDirection,
+    ) -> Vec<i32> {
+        let query = crate::order_by!(users::table.into_boxed(), sort, direction, {
+            UserSortBy::Username => users::username,
+            UserSortBy::CreatedAt => users::created_at,
+        });
+        query
+            .then_order_by(users::id)
+            .select(users::id)
+            .load(conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn orders_by_validated_column() {
+        use super::SortDirection::{Asc, Desc};
+
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, created_at INTEGER NOT NULL)")
+            .execute(&mut conn)
+            .unwrap();
+        sql_query("INSERT INTO users VALUES (1, 'carol', 20), (2, 'alice', 30), (3, 'bob', 10), (4, 'dave', 10)")
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(sorted(&mut conn, UserSortBy::Username, Asc), [2, 3, 1, 4]);
+        assert_eq!(sorted(&mut conn, UserSortBy::Username, Desc), [4, 1, 3, 2]);
+        // Ties keep the primary key order in both directions
+        assert_eq!(sorted(&mut conn, UserSortBy::CreatedAt, Asc), [3, 4, 1, 2]);
+        assert_eq!(sorted(&mut conn, UserSortBy::CreatedAt, Desc), [2, 1, 3, 4]);
+    }
+
+    /// The counter is installed on `DieselPool`, so it needs SQLite to be the selected backend.
+    #[cfg(not(any(feature = "db-postgres-diesel", feature = "db-mysql-diesel")))]
+    mod query_counts {
+        use super::*;
+
+        fn user_with_sessions(conn: &mut SqliteConnection, username: &str) -> (i32, Vec<Session>) {
+            let id = users::table
+                .filter(users::username.eq(username))
+                .select(users::id)
+                .first(conn)
+                .unwrap();
+            let sessions = sessions::table
+                .filter(sessions::user_id.eq(username))
+                .load(conn)
+                .unwrap();
+            (id, sessions)
+        }
+
+        /// Loads every session separately.
+        fn user_with_sessions_n_plus_one(
+            conn: &mut SqliteConnection,
+            username: &str,
+        ) -> (i32, Vec<Session>) {
+            let id = users::table
+                .filter(users::username.eq(username))
+                .select(users::id)
+                .first(conn)
+                .unwrap();
+            let ids: Vec<String> = sessions::table
+                .filter(sessions::user_id.eq(username))
+                .select(sessions::id)
+                .load(conn)
+                .unwrap();
+            let sessions = ids
+                .into_iter()
+                .map(|id| find_one!(&mut *conn, sessions::table, sessions::id => id).unwrap())
+                .collect();
+            (id, sessions)
+        }
+
+        #[test]
+        fn counts_queries_per_scope() {
+            use super::super::{DieselPool, QueryCounter};
+            use diesel::r2d2::ConnectionManager;
+
+            let counter = QueryCounter::new();
+            let pool = DieselPool::builder()
+                .max_size(1)
+                .test_on_check_out(false)
+                .connection_customizer(Box::new(counter.clone()))
+                .build(ConnectionManager::new(":memory:"))
+                .unwrap();
+            let mut conn = pool.get().unwrap();
+
+            sql_query("CREATE TABLE sessions (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, revoked BOOLEAN NOT NULL)")
+                .execute(&mut *conn)
+                .unwrap();
+            sql_query("CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, created_at INTEGER NOT NULL)")
+                .execute(&mut *conn)
+                .unwrap();
+            sql_query("INSERT INTO users VALUES (1, 'bob', 10)")
+                .execute(&mut *conn)
+                .unwrap();
+            sql_query(
+                "INSERT INTO sessions VALUES ('a', 'bob', 0), ('b', 'bob', 1), ('c', 'bob', 0)",
+            )
+            .execute(&mut *conn)
+            .unwrap();
+            assert_eq!(counter.count(), 4);
+
+            let scope = counter.scope();
+            let (_, sessions) = user_with_sessions(&mut conn, "bob");
+            assert_eq!(sessions.len(), 3);
+            assert_eq!(scope.queries(), 2);
+            scope.assert_at_most(2);
+
+            let scope = counter.scope();
+            let (_, sessions) = user_with_sessions_n_plus_one(&mut conn, "bob");
+            assert_eq!(sessions.len(), 3);
+            assert_eq!(scope.queries(), 5);
+
+            let exceeded = std::panic::catch_unwind(|| scope.assert_at_most(2));
+            assert!(exceeded.is_err());
+        }
+    }
+
+    /// Replica pools are pools of the selected backend, so the SQLite databases they connect to
+    /// need SQLite to be the selected one.
+    #[cfg(not(any(feature = "db-postgres-diesel", feature = "db-mysql-diesel")))]
+    mod replicas {
+        use super::super::{ReadConnection, ReplicatedPool};
+        use crate::driver::{Atomic, Driver};
+        use diesel::{prelude::*, r2d2::ConnectionManager, sql_query, sql_types::Text};
+        use std::{
+            path::PathBuf,
+            time::{Duration, Instant},
+        };
+
+        #[derive(QueryableByName)]
+        struct Name {
+            #[diesel(sql_type = Text)]
+            name: String,
+        }
+
+        fn database(name: &str) -> PathBuf {
+            let path = std::env::temp_dir()
+                .join(format!("hextacy_replica_{name}_{}.db", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let mut conn = SqliteConnection::establish(path.to_str().unwrap()).unwrap();
+            sql_query("CREATE TABLE db (name TEXT NOT NULL)")
+                .execute(&mut conn)
+                .unwrap();
+            sql_query("CREATE TABLE items (name TEXT NOT NULL)")
+                .execute(&mut conn)
+                .unwrap();
+            sql_query(format!("INSERT INTO db VALUES ('{name}')"))
+                .execute(&mut conn)
+                .unwrap();
+            path
+        }
+
+        fn name(conn: &mut SqliteConnection) -> String {
+            sql_query("SELECT name FROM db")
+                .get_result::<Name>(conn)
+                .unwrap()
+                .name
+        }
+
+        fn items(conn: &mut SqliteConnection) -> i64 {
+            diesel::dsl::sql::<diesel::sql_types::BigInt>("SELECT COUNT(*) FROM items")
+                .get_result(conn)
+                .unwrap()
+        }
+
+        fn read_from(conn: &mut ReadConnection) -> String {
+            name(conn)
+        }
+
+        #[tokio::test]
+        async fn writes_never_hit_replicas() {
+            let paths = ["primary", "replica_a", "replica_b"].map(database);
+            let urls = paths
+                .iter()
+                .map(|p| p.to_str().unwrap())
+                .collect::<Vec<_>>();
+            let pool = ReplicatedPool::from_urls(urls[0], &urls[1..]).unwrap();
+
+            let mut conn = pool.connect().await.unwrap();
+            assert_eq!(name(&mut conn), "primary");
+            sql_query("INSERT INTO items VALUES ('plain')")
+                .execute(&mut *conn)
+                .unwrap();
+
+            let mut tx = conn.start_transaction().await.unwrap();
+            assert_eq!(name(&mut tx), "primary");
+            sql_query("INSERT INTO items VALUES ('transaction')")
+                .execute(&mut *tx)
+                .unwrap();
+            <super::super::DieselConnection as Atomic>::commit_transaction(tx)
+                .await
+                .unwrap();
+
+            let reads = pool.reads();
+            let mut replicas = vec![];
+            for _ in 0..4 {
+                let mut conn = reads.connect().await.unwrap();
+                replicas.push(read_from(&mut conn));
+                assert_eq!(items(&mut conn), 0);
+            }
+            assert_eq!(
+                replicas,
+                ["replica_a", "replica_b", "replica_a", "replica_b"]
+            );
+            assert_eq!(items(&mut pool.connect().await.unwrap()), 2);
+
+            for path in paths {
+                std::fs::remove_file(path).unwrap();
+            }
+        }
+
+        #[tokio::test]
+        async fn falls_back_to_the_primary() {
+            let primary = database("fallback_primary");
+            let primary_pool = diesel::r2d2::Pool::builder()
+                .build(ConnectionManager::new(primary.to_str().unwrap()))
+                .unwrap();
+            // Keeps the default connection timeout of 30 seconds
+            let unavailable = diesel::r2d2::Pool::builder()
+                .build_unchecked(ConnectionManager::new("/nonexistent/replica.db"));
+            let pool = ReplicatedPool::new(primary_pool, vec![unavailable])
+                .with_replica_timeout(Duration::from_millis(100));
+
+            let start = Instant::now();
+            let mut conn = pool.connect_read().await.unwrap();
+            assert!(start.elapsed() < Duration::from_secs(5));
+            assert_eq!(read_from(&mut conn), "fallback_primary");
+
+            std::fs::remove_file(primary).unwrap();
+        }
+    }
 }
@@ -0,0 +1,421 @@
+use super::diesel::Connection;
+use cfg_if::cfg_if;
+use diesel::{
+    expression::Expression, sql_query, sql_types::Text, Column, QueryResult, QueryableByName,
+    RunQueryDsl, Table,
+};
+use std::fmt::Display;
+
+/// The schema the application expects, built from the tables generated by diesel in `schema.rs`.
+///
+/// Column types are taken from the diesel SQL types of the columns, so the expectation stays in sync
+/// with the schema the migrations generated.
+///
+/// ### Example
+///
+/// ```ignore
+/// let expected = Schema::new()
+///     .table::<users::table>("users")
+///     .table::<sessions::table>("sessions");
+///
+/// let discrepancies = schema::verify(&mut conn, &expected)?;
+/// for discrepancy in discrepancies.iter() {
+///     error!("Schema drift: {discrepancy}");
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schema {
+    tables: Vec<ExpectedTable>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect the table `T` under the given name.
+    pub fn table<T>(mut self, name: &'static str) -> Self
+    where
+        T: Table,
+        T::AllColumns: Columns,
+    {
+        self.tables.push(ExpectedTable {
+            name,
+            columns: T::AllColumns::describe(),
+        });
+        self
+    }
+
+    pub fn tables(&self) -> &[ExpectedTable] {
+        &self.tables
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedTable {
+    pub name: &'static str,
+    pub columns: Vec<ExpectedColumn>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedColumn {
+    pub name: &'static str,
+    /// The name of the diesel SQL type, e.g. `Text` for both `Text` and `Nullable<Text>`.
+    pub sql_type: &'static str,
+    pub nullable: bool,
+}
+
+impl ExpectedColumn {
+    fn of<C>() -> Self
+    where
+        C: Column + Expression,
+    {
+        let type_name = std::any::type_name::<C::SqlType>();
+        let (nullable, inner) = match type_name
+            .split_once("Nullable<")
+            .and_then(|(_, inner)| inner.strip_suffix('>'))
+        {
+            Some(inner) => (true, inner),
+            None => (false, type_name),
+        };
+        Self {
+            name: C::NAME,
+            sql_type: inner.rsplit("::").next().unwrap_or(inner),
+            nullable,
+        }
+    }
+}
+
+/// Implemented on the tuples of columns diesel tables consist of.
+pub trait Columns {
+    fn describe() -> Vec<ExpectedColumn>;
+}
+
+macro_rules! impl_columns {
+    ($($column:ident),+) => {
+        impl<$($column),+> Columns for ($($column,)+)
+        where
+            $($column: Column + Expression),+
+        {
+            fn describe() -> Vec<ExpectedColumn> {
+                vec![$(ExpectedColumn::of::<$column>()),+]
+            }
+        }
+    };
+}
+
+macro_rules! impl_columns_up_to {
+    ($first:ident $(, $rest:ident)*) => {
+        impl_columns!($first $(, $rest)*);
+        impl_columns_up_to!($($rest),*);
+    };
+    () => {};
+}
+
+impl_columns_up_to!(
+    C32, C31, C30, C29, C28, C27, C26, C25, C24, C23, C22, C21, C20, C19, C18, C17, C16, C15, C14,
+    C13, C12, C11, C10, C9, C8, C7, C6, C5, C4, C3, C2, C1
+);
+
+/// A difference between the expected and the live schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    MissingTable {
+        table: &'static str,
+    },
+    MissingColumn {
+        table: &'static str,
+        column: &'static str,
+    },
+    /// The live table has a column the schema does not know about. Harmless for queries
+    /// unless the column is not nullable and has no default, in which case inserts fail.
+    UnexpectedColumn {
+        table: &'static str,
+        column: String,
+    },
+    TypeMismatch {
+        table: &'static str,
+        column: &'static str,
+        expected: &'static str,
+        actual: String,
+    },
+    NullabilityMismatch {
+        table: &'static str,
+        column: &'static str,
+        expected_nullable: bool,
+    },
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingTable { table } => write!(f, "table {table} does not exist"),
+            Self::MissingColumn { table, column } => {
+                write!(f, "column {table}.{column} does not exist")
+            }
+            Self::UnexpectedColumn { table, column } => {
+                write!(f, "column {table}.{column} is not in the schema")
+            }
+            Self::TypeMismatch {
+                table,
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column {table}.{column} is {actual}, expected a type compatible with {expected}"
+            ),
+            Self::NullabilityMismatch {
+                table,
+                column,
+                expected_nullable,
+            } => {
+                let expected = if *expected_nullable {
+                    "nullable"
+                } else {
+                    "not nullable"
+                };
+                write!(f, "column {table}.{column} is expected to be {expected}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, QueryableByName)]
+struct LiveColumn {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    sql_type: String,
+    /// `YES` or `NO`, as in `information_schema.columns`.
+    #[diesel(sql_type = Text)]
+    is_nullable: String,
+}
+
+cfg_if!(
+    if #[cfg(feature = "db-postgres-diesel")] {
+        const COLUMNS_QUERY: &str = "SELECT column_name::text AS name, data_type::text AS sql_type, \
+            is_nullable::text AS is_nullable FROM information_schema.columns \
+            WHERE table_schema = current_schema() AND table_name = $1 ORDER BY ordinal_position";
+    } else if #[cfg(feature = "db-mysql-diesel")] {
+        const COLUMNS_QUERY: &str = "SELECT CAST(column_name AS CHAR) AS name, CAST(data_type AS CHAR) AS sql_type, \
+            CAST(is_nullable AS CHAR) AS is_nullable FROM information_schema.columns \
+            WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position";
+    } else {
+        // Integer primary keys are never null even though SQLite does not mark them as such
+        const COLUMNS_QUERY: &str = "SELECT name, type AS sql_type, \
+            CASE WHEN \"notnull\" = 0 AND pk = 0 THEN 'YES' ELSE 'NO' END AS is_nullable \
+            FROM pragma_table_info(?) ORDER BY cid";
+    }
+);
+
+/// Compare the expected schema against the live database's tables and columns, e.g. as a sanity check
+/// on startup. Returns the discrepancies found, an empty list means the schemas match.
+///
+/// Types are compared leniently, i.e. `Text` matches `varchar(255)`, and only for the common diesel SQL types.
+/// Columns of other types are only checked for existence and nullability.
+pub fn verify(conn: &mut Connection, schema: &Schema) -> QueryResult<Vec<Discrepancy>> {
+    let mut discrepancies = vec![];
+
+    for table in schema.tables.iter() {
+        let live = sql_query(COLUMNS_QUERY)
+            .bind::<Text, _>(table.name)
+            .load::<LiveColumn>(conn)?;
+
+        if live.is_empty() {
+            discrepancies.push(Discrepancy::MissingTable { table: table.name });
+            continue;
+        }
+
+        for expected in table.columns.iter() {
+            let Some(actual) = live.iter().find(|c| c.name == expected.name) else {
+                discrepancies.push(Discrepancy::MissingColumn {
+                    table: table.name,
+                    column: expected.name,
+                });
+                continue;
+            };
+
+            if compatible(expected.sql_type, &actual.sql_type) == Some(false) {
+                discrepancies.push(Discrepancy::TypeMismatch {
+                    table: table.name,
+                    column: expected.name,
+                    expected: expected.sql_type,
+                    actual: actual.sql_type.clone(),
+                });
+            }
+
+            if expected.nullable != actual.is_nullable.eq_ignore_ascii_case("YES") {
+                discrepancies.push(Discrepancy::NullabilityMismatch {
+                    table: table.name,
+                    column: expected.name,
+                    expected_nullable: expected.nullable,
+                });
+            }
+        }
+
+        for actual in live.iter() {
+            if !table.columns.iter().any(|c| c.name == actual.name) {
+                discrepancies.push(Discrepancy::UnexpectedColumn {
+                    table: table.name,
+                    column: actual.name.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(discrepancies)
+}
+
+/// Whether the live column type can hold the diesel SQL type, `None` if the SQL type is not known.
+fn compatible(expected: &str, actual: &str) -> Option<bool> {
+    let actual = actual.to_ascii_lowercase();
+    let actual = actual
+        .split_once('(')
+        .map_or(actual.as_str(), |(ty, _)| ty)
+        .trim();
+
+    let accepted: &[&str] = match expected {
+        "Text" | "VarChar" => &[
+            "text",
+            "varchar",
+            "character varying",
+            "char",
+            "character",
+            "tinytext",
+            "mediumtext",
+            "longtext",
+        ],
+        "SmallInt" => &["smallint", "int2"],
+        "Integer" => &["integer", "int", "int4", "mediumint"],
+        "BigInt" => &["bigint", "int8"],
+        "Bool" => &["boolean", "bool", "tinyint"],
+        "Float" => &["real", "float", "float4"],
+        "Double" => &["double precision", "double", "float8", "real"],
+        "Numeric" => &["numeric", "decimal"],
+        "Binary" => &["bytea", "blob", "binary", "varbinary", "longblob"],
+        "Date" => &["date"],
+        "Time" => &["time", "time without time zone"],
+        "Timestamp" => &["timestamp", "timestamp without time zone", "datetime"],
+        "Timestamptz" => &["timestamptz", "timestamp with time zone"],
+        "Uuid" => &["uuid"],
+        "Json" => &["json"],
+        "Jsonb" => &["jsonb"],
+        _ => return None,
+    };
+
+    Some(accepted.contains(&actual))
+}
+
+#[cfg(all(
+    test,
+    feature = "db-sqlite-diesel",
+    not(feature = "db-postgres-diesel"),
+    not(feature = "db-mysql-diesel")
+))]
+mod tests {
+    use super::*;
+    use crate::{adapters::db::sql::sqlite::SqliteDriver, driver::Driver};
+    use diesel::connection::SimpleConnection;
+
+    diesel::table! {
+        notes (id) {
+            id -> Integer,
+            body -> Text,
+            author -> Nullable<Text>,
+            pinned -> Bool,
+            created_at -> Timestamp,
+        }
+    }
+
+    diesel::table! {
+        tags (id) {
+            id -> Integer,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_dropped_columns() {
+        let driver = SqliteDriver::in_memory().unwrap();
+        let mut conn = driver.connect().await.unwrap();
+        conn.batch_execute(
+            "CREATE TABLE notes (
+                id INTEGER PRIMARY KEY,
+                body VARCHAR(255) NOT NULL,
+                author TEXT,
+                pinned BOOLEAN NOT NULL DEFAULT 0,
+                created_at DATETIME NOT NULL
+            )",
+        )
+        .unwrap();
+
+        let schema = Schema::new().table::<notes::table>("notes");
+        assert_eq!(
+            schema.tables()[0].columns[2],
+            ExpectedColumn {
+                name: "author",
+                sql_type: "Text",
+                nullable: true
+            }
+        );
+        assert_eq!(verify(&mut conn, &schema).unwrap(), []);
+
+        conn.batch_execute("ALTER TABLE notes DROP COLUMN pinned")
+            .unwrap();
+        assert_eq!(
+            verify(&mut conn, &schema).unwrap(),
+            [Discrepancy::MissingColumn {
+                table: "notes",
+                column: "pinned"
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_mismatches() {
+        let driver = SqliteDriver::in_memory().unwrap();
+        let mut conn = driver.connect().await.unwrap();
+        conn.batch_execute(
+            "CREATE TABLE notes (
+                id INTEGER PRIMARY KEY,
+                body BLOB NOT NULL,
+                author TEXT NOT NULL,
+                pinned BOOLEAN NOT NULL,
+                created_at DATETIME NOT NULL,
+                archived BOOLEAN
+            )",
+        )
+        .unwrap();
+
+        let schema = Schema::new()
+            .table::<notes::table>("notes")
+            .table::<tags::table>("tags");
+        let discrepancies = verify(&mut conn, &schema).unwrap();
+
+        assert_eq!(
+            discrepancies,
+            [
+                Discrepancy::TypeMismatch {
+                    table: "notes",
+                    column: "body",
+                    expected: "Text",
+                    actual: "BLOB".to_string()
+                },
+                Discrepancy::NullabilityMismatch {
+                    table: "notes",
+                    column: "author",
+                    expected_nullable: true
+                },
+                Discrepancy::UnexpectedColumn {
+                    table: "notes",
+                    column: "archived".to_string()
+                },
+                Discrepancy::MissingTable { table: "tags" },
+            ]
+        );
+        assert_eq!(
+            discrepancies[0].to_string(),
+            "column notes.body is BLOB, expected a type compatible with Text"
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use crate::driver::{Atomic, Driver};
+use crate::driver::{Atomic, Conn, Driver};
 use sea_orm::DatabaseTransaction;
-use sea_orm::TransactionTrait;
+use sea_orm::{
+    ConnectionTrait, DbBackend, DbErr, ExecResult, QueryResult, Statement, TransactionTrait,
+};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
 
 #[cfg(all(
     not(feature = "db-postgres-seaorm"),
@@ -21,21 +25,253 @@ impl Driver for DatabaseConnection {
         // that gets cloned via this
         Ok(self.clone())
     }
+
+    /// Pings the database on a pooled connection.
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        let start = Instant::now();
+        self.ping().await?;
+        Ok(start.elapsed())
+    }
 }
 
 impl Atomic for DatabaseConnection {
-    type TransactionResult = DatabaseTransaction;
+    type TransactionResult = SeaTransaction;
     type Error = sea_orm::DbErr;
 
     async fn start_transaction(self) -> Result<Self::TransactionResult, Self::Error> {
-        DatabaseConnection::begin(&self).await
+        Ok(SeaTransaction {
+            inner: DatabaseConnection::begin(&self).await?,
+            savepoints: 0,
+        })
     }
 
     async fn commit_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
-        DatabaseTransaction::commit(tx).await
+        DatabaseTransaction::commit(tx.inner).await
     }
 
     async fn abort_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
-        DatabaseTransaction::rollback(tx).await
+        DatabaseTransaction::rollback(tx.inner).await
+    }
+
+    /// Every level gets its own name since MySQL replaces savepoints with the same name.
+    async fn start_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        let name = savepoint(tx.savepoints + 1);
+        tx.execute_unprepared(&format!("SAVEPOINT {name}")).await?;
+        tx.savepoints += 1;
+        Ok(())
+    }
+
+    async fn release_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        let name = savepoint(tx.savepoints);
+        tx.execute_unprepared(&format!("RELEASE SAVEPOINT {name}"))
+            .await?;
+        tx.savepoints -= 1;
+        Ok(())
+    }
+
+    async fn rollback_to_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        let name = savepoint(tx.savepoints);
+        tx.execute_unprepared(&format!("ROLLBACK TO SAVEPOINT {name}"))
+            .await?;
+        // Rolling back keeps the savepoint, release it so the next one reuses its level
+        tx.execute_unprepared(&format!("RELEASE SAVEPOINT {name}"))
+            .await?;
+        tx.savepoints -= 1;
+        Ok(())
+    }
+}
+
+/// The name of the savepoint at the given level started through [Atomic].
+fn savepoint(level: usize) -> String {
+    format!("hextacy_sp_{level}")
+}
+
+/// A seaorm transaction which keeps track of the savepoints started on it through [Atomic].
+///
+/// Derefs to the [DatabaseTransaction] and implements [ConnectionTrait], so queries run on it directly.
+#[derive(Debug)]
+pub struct SeaTransaction {
+    inner: DatabaseTransaction,
+    savepoints: usize,
+}
+
+impl SeaTransaction {
+    pub fn into_inner(self) -> DatabaseTransaction {
+        self.inner
+    }
+}
+
+impl Deref for SeaTransaction {
+    type Target = DatabaseTransaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl ConnectionTrait for SeaTransaction {
+    fn get_database_backend(&self) -> DbBackend {
+        self.inner.get_database_backend()
+    }
+
+    async fn execute(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
+        self.inner.execute(stmt).await
+    }
+
+    async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
+        self.inner.execute_unprepared(sql).await
+    }
+
+    async fn query_one(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
+        self.inner.query_one(stmt).await
+    }
+
+    async fn query_all(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+        self.inner.query_all(stmt).await
+    }
+
+    fn support_returning(&self) -> bool {
+        self.inner.support_returning()
+    }
+
+    fn is_mock_connection(&self) -> bool {
+        self.inner.is_mock_connection()
+    }
+}
+
+/// Allows adapters to implement queries once for both connections and transactions, e.g.
+/// with `async fn insert<C: ConnectionTrait>(conn: &C, ..)`.
+#[async_trait::async_trait]
+impl ConnectionTrait for Conn<DatabaseConnection> {
+    fn get_database_backend(&self) -> DbBackend {
+        match self {
+            Conn::Plain(conn) => conn.get_database_backend(),
+            Conn::Transaction(tx) => tx.get_database_backend(),
+        }
+    }
+
+    async fn execute(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
+        match self {
+            Conn::Plain(conn) => conn.execute(stmt).await,
+            Conn::Transaction(tx) => tx.execute(stmt).await,
+        }
+    }
+
+    async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
+        match self {
+            Conn::Plain(conn) => conn.execute_unprepared(sql).await,
+            Conn::Transaction(tx) => tx.execute_unprepared(sql).await,
+        }
+    }
+
+    async fn query_one(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
+        match self {
+            Conn::Plain(conn) => conn.query_one(stmt).await,
+            Conn::Transaction(tx) => tx.query_one(stmt).await,
+        }
+    }
+
+    async fn query_all(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+        match self {
+            Conn::Plain(conn) => conn.query_all(stmt).await,
+            Conn::Transaction(tx) => tx.query_all(stmt).await,
+        }
+    }
+
+    fn support_returning(&self) -> bool {
+        match self {
+            Conn::Plain(conn) => conn.support_returning(),
+            Conn::Transaction(tx) => tx.support_returning(),
+        }
+    }
+
+    fn is_mock_connection(&self) -> bool {
+        match self {
+            Conn::Plain(conn) => conn.is_mock_connection(),
+            Conn::Transaction(tx) => tx.is_mock_connection(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "db-sqlite-seaorm"))]
+mod tests {
+    use super::*;
+    use sea_orm::Database;
+
+    /// The adapter method, implemented once.
+    async fn insert<C: ConnectionTrait>(conn: &C, id: i32) -> Result<u64, DbErr> {
+        conn.execute(Statement::from_string(
+            conn.get_database_backend(),
+            format!("INSERT INTO items (id) VALUES ({id})"),
+        ))
+        .await
+        .map(|res| res.rows_affected())
+    }
+
+    async fn count<C: ConnectionTrait>(conn: &C) -> i64 {
+        let stmt = Statement::from_string(
+            conn.get_database_backend(),
+            "SELECT COUNT(*) AS c FROM items",
+        );
+        let row = conn.query_one(stmt).await.unwrap().unwrap();
+        row.try_get("", "c").unwrap()
+    }
+
+    #[tokio::test]
+    async fn same_query_with_and_without_transaction() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared("CREATE TABLE items (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        let conn: Conn<DatabaseConnection> = db.clone().into();
+        assert_eq!(insert(&conn, 1).await.unwrap(), 1);
+
+        let tx = conn.start_transaction().await.unwrap();
+        assert!(tx.is_transaction());
+        assert_eq!(insert(&tx, 2).await.unwrap(), 1);
+        Conn::abort_transaction(tx).await.unwrap();
+
+        let tx = Conn::from(db.clone()).start_transaction().await.unwrap();
+        assert_eq!(insert(&tx, 3).await.unwrap(), 1);
+        Conn::commit_transaction(tx).await.unwrap();
+
+        assert_eq!(count(&db).await, 2);
+    }
+
+    #[tokio::test]
+    async fn nested_savepoints_roll_back_independently() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        db.execute_unprepared("CREATE TABLE items (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+
+        let mut tx = db.clone().start_transaction().await.unwrap();
+        insert(&tx, 1).await.unwrap();
+
+        DatabaseConnection::start_savepoint(&mut tx).await.unwrap();
+        insert(&tx, 2).await.unwrap();
+
+        DatabaseConnection::start_savepoint(&mut tx).await.unwrap();
+        insert(&tx, 3).await.unwrap();
+        DatabaseConnection::rollback_to_savepoint(&mut tx)
+            .await
+            .unwrap();
+        assert_eq!(count(&tx).await, 2);
+
+        // The outer savepoint is still open
+        DatabaseConnection::start_savepoint(&mut tx).await.unwrap();
+        insert(&tx, 4).await.unwrap();
+        DatabaseConnection::release_savepoint(&mut tx)
+            .await
+            .unwrap();
+        DatabaseConnection::rollback_to_savepoint(&mut tx)
+            .await
+            .unwrap();
+        assert_eq!(count(&tx).await, 1);
+
+        DatabaseConnection::commit_transaction(tx).await.unwrap();
+        assert_eq!(count(&db).await, 1);
     }
 }
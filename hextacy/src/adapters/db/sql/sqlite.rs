@@ -0,0 +1,224 @@
+use super::diesel::{DieselConnection, DieselPool};
+use crate::driver::Driver;
+use diesel::{
+    connection::SimpleConnection,
+    r2d2::{ConnectionManager, CustomizeConnection, Pool, PoolError},
+    SqliteConnection,
+};
+use std::time::Duration;
+
+/// The DSN of an in-memory database.
+pub const MEMORY: &str = ":memory:";
+
+/// How long a connection waits for another one to release its lock before erroring with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A diesel pool for SQLite, for local development and running repositories in tests without a database server.
+///
+/// Connections are the same [DieselConnection] the Postgres and MySQL pools provide, so repositories written
+/// against it and [Atomic][crate::driver::Atomic] work unchanged. Transactions are started with `BEGIN IMMEDIATE`
+/// so concurrent writers wait on each other instead of failing when upgrading their locks.
+///
+/// Every connection to [MEMORY] opens a separate database, so in-memory pools hold a single connection
+/// which is never closed. Holding a connection while connecting again will time out.
+/// File backed pools use WAL so reads are not blocked by writes.
+///
+/// Foreign keys are enforced on all connections.
+///
+/// ### Example
+///
+/// ```ignore
+/// let driver = SqliteDriver::new(MEMORY)?;
+/// let users = UserAdapter { driver };
+/// ```
+#[derive(Debug, Clone)]
+pub struct SqliteDriver {
+    pool: DieselPool,
+}
+
+impl SqliteDriver {
+    /// `dsn` is either [MEMORY] or the path of the database file, which is created if it does not exist.
+    pub fn new(dsn: &str) -> Result<Self, PoolError> {
+        let builder = Pool::builder().connection_customizer(Box::new(Pragmas {
+            in_memory: dsn == MEMORY,
+        }));
+
+        let builder = if dsn == MEMORY {
+            builder
+                .max_size(1)
+                .min_idle(Some(1))
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            builder
+        };
+
+        let pool = builder.build(ConnectionManager::new(dsn))?;
+        Ok(Self { pool })
+    }
+
+    pub fn in_memory() -> Result<Self, PoolError> {
+        Self::new(MEMORY)
+    }
+
+    pub fn pool(&self) -> &DieselPool {
+        &self.pool
+    }
+}
+
+impl Driver for SqliteDriver {
+    type Connection = DieselConnection;
+    type Error = PoolError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.pool.get()
+    }
+}
+
+#[derive(Debug)]
+struct Pragmas {
+    in_memory: bool,
+}
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for Pragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        let mut pragmas = format!(
+            "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+            BUSY_TIMEOUT.as_millis()
+        );
+        if !self.in_memory {
+            pragmas.push_str(" PRAGMA journal_mode = WAL;");
+        }
+        conn.batch_execute(&pragmas)
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::Atomic;
+    use diesel::{prelude::*, result::Error as DieselError, sql_query, sql_types::BigInt};
+
+    /// A repository as it would be written for any diesel backend.
+    trait NoteRepository {
+        fn create_table(conn: &mut DieselConnection) -> QueryResult<()>;
+        fn insert(conn: &mut DieselConnection, body: &str) -> QueryResult<usize>;
+        fn count(conn: &mut DieselConnection) -> QueryResult<i64>;
+    }
+
+    struct NoteAdapter;
+
+    impl NoteRepository for NoteAdapter {
+        fn create_table(conn: &mut DieselConnection) -> QueryResult<()> {
+            sql_query("CREATE TABLE IF NOT EXISTS notes (body TEXT NOT NULL)").execute(conn)?;
+            Ok(())
+        }
+
+        fn insert(conn: &mut DieselConnection, body: &str) -> QueryResult<usize> {
+            sql_query("INSERT INTO notes (body) VALUES (?)")
+                .bind::<diesel::sql_types::Text, _>(body)
+                .execute(conn)
+        }
+
+        fn count(conn: &mut DieselConnection) -> QueryResult<i64> {
+            diesel::dsl::sql::<BigInt>("SELECT COUNT(*) FROM notes").get_result(conn)
+        }
+    }
+
+    async fn run_repository(driver: &SqliteDriver) {
+        let mut conn = driver.connect().await.unwrap();
+        NoteAdapter::create_table(&mut conn).unwrap();
+        NoteAdapter::insert(&mut conn, "plain").unwrap();
+
+        let mut tx = conn.start_transaction().await.unwrap();
+        NoteAdapter::insert(&mut tx, "committed").unwrap();
+        DieselConnection::commit_transaction(tx).await.unwrap();
+
+        let conn = driver.connect().await.unwrap();
+        let mut tx = conn.start_transaction().await.unwrap();
+        NoteAdapter::insert(&mut tx, "aborted").unwrap();
+        assert_eq!(NoteAdapter::count(&mut tx).unwrap(), 3);
+        DieselConnection::abort_transaction(tx).await.unwrap();
+
+        let mut conn = driver.connect().await.unwrap();
+        assert_eq!(NoteAdapter::count(&mut conn).unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn in_memory() {
+        let driver = SqliteDriver::in_memory().unwrap();
+        run_repository(&driver).await;
+    }
+
+    #[tokio::test]
+    async fn file_backed() {
+        let path = std::env::temp_dir().join(format!("hextacy_sqlite_{}.db", std::process::id()));
+        let dsn = path.to_str().unwrap();
+
+        run_repository(&SqliteDriver::new(dsn).unwrap()).await;
+
+        // Persisted for other pools
+        let driver = SqliteDriver::new(dsn).unwrap();
+        let mut conn = driver.connect().await.unwrap();
+        assert_eq!(NoteAdapter::count(&mut conn).unwrap(), 2);
+        drop(conn);
+        drop(driver);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{dsn}{suffix}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactions_leave_no_rows() {
+        use crate::testing::with_test_transaction;
+
+        let driver = SqliteDriver::in_memory().unwrap();
+        NoteAdapter::create_table(&mut driver.connect().await.unwrap()).unwrap();
+
+        let count = with_test_transaction(&driver, |mut conn| async move {
+            NoteAdapter::insert(&mut conn, "temporary").unwrap();
+            let count = NoteAdapter::count(&mut conn).unwrap();
+            (conn, count)
+        })
+        .await;
+        assert_eq!(count, 1);
+
+        let mut conn = driver.connect().await.unwrap();
+        assert_eq!(NoteAdapter::count(&mut conn).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn savepoints_nest_in_transactions() {
+        let driver = SqliteDriver::in_memory().unwrap();
+        let mut conn = driver.connect().await.unwrap();
+        NoteAdapter::create_table(&mut conn).unwrap();
+
+        assert!(matches!(
+            DieselConnection::start_savepoint(&mut conn).await,
+            Err(DieselError::NotInTransaction)
+        ));
+
+        let mut tx = conn.start_transaction().await.unwrap();
+        NoteAdapter::insert(&mut tx, "outer").unwrap();
+
+        DieselConnection::start_savepoint(&mut tx).await.unwrap();
+        NoteAdapter::insert(&mut tx, "undone").unwrap();
+        DieselConnection::rollback_to_savepoint(&mut tx)
+            .await
+            .unwrap();
+        assert_eq!(NoteAdapter::count(&mut tx).unwrap(), 1);
+
+        DieselConnection::start_savepoint(&mut tx).await.unwrap();
+        NoteAdapter::insert(&mut tx, "released").unwrap();
+        DieselConnection::release_savepoint(&mut tx).await.unwrap();
+        assert_eq!(NoteAdapter::count(&mut tx).unwrap(), 2);
+
+        // Released savepoints are still undone with the transaction
+        DieselConnection::abort_transaction(tx).await.unwrap();
+
+        let mut conn = driver.connect().await.unwrap();
+        assert_eq!(NoteAdapter::count(&mut conn).unwrap(), 0);
+    }
+}
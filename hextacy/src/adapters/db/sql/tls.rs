@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// How strictly the TLS connection to Postgres is enforced. Corresponds to libpq's `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SslMode {
+    /// Connect without TLS.
+    Disable,
+
+    /// Connect with TLS, but do not verify the server certificate.
+    #[default]
+    Require,
+
+    /// Connect with TLS and verify the server certificate against the CA and its host name.
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Require => "require",
+            Self::VerifyFull => "verify-full",
+        }
+    }
+}
+
+/// TLS settings for Postgres connections. Managed databases usually require TLS and
+/// provide a CA certificate to verify the server with.
+///
+/// The settings are applied as libpq query parameters on the connection URL, which both diesel and
+/// sea-orm (sqlx) understand, so the resulting URL is passed to the pool the same way as before.
+///
+/// ### Example
+///
+/// ```ignore
+/// let url = PgTls::new(SslMode::VerifyFull)
+///     .root_cert("/run/secrets/rds-ca.pem")
+///     .apply(&env::get("DATABASE_URL")?)?;
+///
+/// let pool = DieselPool::builder().build(ConnectionManager::new(url))?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PgTls {
+    mode: SslMode,
+    root_cert: Option<PathBuf>,
+    client_cert: Option<PathBuf>,
+    client_key: Option<PathBuf>,
+}
+
+impl PgTls {
+    pub fn new(mode: SslMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// The CA certificate used to verify the server.
+    pub fn root_cert(mut self, path: impl AsRef<Path>) -> Self {
+        self.root_cert = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The certificate and key the client authenticates with.
+    pub fn client_identity(mut self, cert: impl AsRef<Path>, key: impl AsRef<Path>) -> Self {
+        self.client_cert = Some(cert.as_ref().to_path_buf());
+        self.client_key = Some(key.as_ref().to_path_buf());
+        self
+    }
+
+    /// Checks the settings are coherent. `verify-full` requires a CA certificate
+    /// and any certificate given must be accompanied by TLS being enabled.
+    pub fn validate(&self) -> Result<(), TlsConfigError> {
+        match self.mode {
+            SslMode::VerifyFull if self.root_cert.is_none() => Err(TlsConfigError::MissingRootCert),
+            SslMode::Disable
+                if self.root_cert.is_some()
+                    || self.client_cert.is_some()
+                    || self.client_key.is_some() =>
+            {
+                Err(TlsConfigError::CertsWithoutTls)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the settings and appends them to the connection URL. Any `ssl*` parameters
+    /// already present in the URL are replaced.
+    pub fn apply(&self, url: &str) -> Result<String, TlsConfigError> {
+        self.validate()?;
+
+        let (base, query) = url.split_once('?').unwrap_or((url, ""));
+
+        let mut params = query
+            .split('&')
+            .filter(|param| !param.is_empty() && !param.starts_with("ssl"))
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        params.push(format!("sslmode={}", self.mode.as_str()));
+
+        let paths = [
+            ("sslrootcert", &self.root_cert),
+            ("sslcert", &self.client_cert),
+            ("sslkey", &self.client_key),
+        ];
+        for (key, path) in paths {
+            if let Some(path) = path {
+                params.push(format!("{key}={}", encode(&path.to_string_lossy())));
+            }
+        }
+
+        Ok(format!("{base}?{}", params.join("&")))
+    }
+}
+
+/// Percent encodes everything except unreserved characters and path separators.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TlsConfigError {
+    #[error("TLS mode verify-full requires a root certificate")]
+    MissingRootCert,
+    #[error("Certificates were provided but TLS is disabled")]
+    CertsWithoutTls,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_reflects_options() {
+        let url = PgTls::new(SslMode::VerifyFull)
+            .root_cert("/certs/ca.pem")
+            .client_identity("/certs/client.crt", "/certs/my key.pem")
+            .apply("postgresql://user@db:5432/app?application_name=api&sslmode=prefer")
+            .unwrap();
+        assert_eq!(
+            url,
+            "postgresql://user@db:5432/app?application_name=api&sslmode=verify-full\
+             &sslrootcert=/certs/ca.pem&sslcert=/certs/client.crt&sslkey=/certs/my%20key.pem"
+        );
+
+        let url = PgTls::new(SslMode::Require)
+            .apply("postgresql://user@db/app")
+            .unwrap();
+        assert_eq!(url, "postgresql://user@db/app?sslmode=require");
+    }
+
+    #[test]
+    fn misconfiguration_errors() {
+        assert_eq!(
+            PgTls::new(SslMode::VerifyFull).apply("postgresql://db"),
+            Err(TlsConfigError::MissingRootCert)
+        );
+        assert_eq!(
+            PgTls::new(SslMode::Disable)
+                .root_cert("/certs/ca.pem")
+                .validate(),
+            Err(TlsConfigError::CertsWithoutTls)
+        );
+        assert!(PgTls::new(SslMode::Disable).validate().is_ok());
+    }
+}
@@ -1,9 +1,12 @@
+use crate::bulk::BulkResult;
 use crate::Constructor;
 use lettre::transport;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{message::header::ContentType, Message, SmtpTransport, Transport};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Debug, Display};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use std::{fs, path::Path};
 use thiserror::Error;
 use tracing::debug;
@@ -21,6 +24,7 @@ pub struct SimpleTemplateMailer {
     placeholders: HashMap<String, Vec<TemplatePlaceholder>>,
     target_delims: Option<(char, char)>,
     delim_len: usize,
+    throttle: Option<DomainThrottle>,
 }
 
 impl Debug for SimpleTemplateMailer {
@@ -31,6 +35,7 @@ impl Debug for SimpleTemplateMailer {
             .field("templates", &self.templates)
             .field("placeholders", &self.placeholders)
             .field("target_delims", &self.target_delims)
+            .field("throttle", &self.throttle)
             .finish()
     }
 }
@@ -62,6 +67,7 @@ impl SimpleTemplateMailer {
             placeholders: HashMap::new(),
             target_delims: None,
             delim_len: 2,
+            throttle: None,
         }
     }
 
@@ -107,6 +113,12 @@ impl SimpleTemplateMailer {
         self.delim_len = len;
     }
 
+    /// Pace sends per recipient domain according to the throttle. Sends that would exceed it block
+    /// until they fit in its window instead of failing.
+    pub fn throttle_domains(&mut self, throttle: DomainThrottle) {
+        self.throttle = Some(throttle);
+    }
+
     /// Send an email with the given params
     pub fn send<T: Display>(
         &self,
@@ -130,6 +142,7 @@ impl SimpleTemplateMailer {
 
         let Some(placeholders) = self.placeholders.get(&template) else {
             let email = email.subject(subject).body(body)?;
+            self.pace(&to);
             self.smtp.send(&email)?;
             return Ok(());
         };
@@ -144,10 +157,100 @@ impl SimpleTemplateMailer {
         replace_targets(&mut body, replacements, placeholders, self.delim_len)?;
 
         let email = email.subject(subject).body(body)?;
+        self.pace(&to);
         self.smtp.send(&email)?;
 
         Ok(())
     }
+
+    /// Send the same template to every recipient. Failing recipients do not stop the rest from being sent to
+    /// and are reported by their index in `to`. Succeeded contains the addresses the email was sent to.
+    pub fn send_bulk<T: Display>(
+        &self,
+        template: T,
+        to: impl IntoIterator<Item = RecipientInfo>,
+        replacements: Option<&[(&str, &str)]>,
+        subject: &str,
+    ) -> BulkResult<String, TemplateMailerError> {
+        let template = template.to_string();
+        to.into_iter()
+            .map(|recipient| {
+                let address = recipient.to_string();
+                self.send(&template, recipient, replacements, subject)
+                    .map(|_| address)
+            })
+            .collect()
+    }
+}
+
+impl SimpleTemplateMailer {
+    /// Blocks until the recipient's domain can be sent to, if throttling.
+    fn pace(&self, to: &str) {
+        let Some(ref throttle) = self.throttle else {
+            return;
+        };
+        let Some(domain) = recipient_domain(to) else {
+            return;
+        };
+        let delay = throttle.reserve(domain);
+        if !delay.is_zero() {
+            debug!("Delaying email to {domain} by {delay:?}");
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Extracts the domain from either a bare address or one formatted as `Name <address>`.
+fn recipient_domain(to: &str) -> Option<&str> {
+    let address = to.trim().trim_end_matches('>');
+    let (_, domain) = address.rsplit_once('@')?;
+    Some(domain).filter(|domain| !domain.is_empty())
+}
+
+/// Limits sends to at most `max` per recipient domain within any `window`.
+///
+/// Every send reserves the earliest slot in which it fits, so concurrent senders queue up
+/// behind each other instead of bursting once the window frees up.
+#[derive(Debug)]
+pub struct DomainThrottle {
+    max: usize,
+    window: Duration,
+    /// Reserved send times per domain, at most `max` of them.
+    sends: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl DomainThrottle {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max: max.max(1),
+            window,
+            sends: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserve a send to the domain and return how long to wait before sending.
+    pub fn reserve(&self, domain: &str) -> Duration {
+        self.reserve_at(domain, Instant::now())
+    }
+
+    fn reserve_at(&self, domain: &str, now: Instant) -> Duration {
+        let mut sends = self.sends.lock().expect("throttle lock poisoned");
+        let sends = sends.entry(domain.to_lowercase()).or_default();
+
+        // The oldest of the last `max` sends must leave the window before the next one fits
+        let slot = if sends.len() < self.max {
+            now
+        } else {
+            (sends[sends.len() - self.max] + self.window).max(now)
+        };
+
+        sends.push_back(slot);
+        while sends.len() > self.max {
+            sends.pop_front();
+        }
+
+        slot - now
+    }
 }
 
 fn replace_targets(
@@ -356,6 +459,55 @@ impl std::fmt::Display for RecipientInfo {
 mod tests {
     use super::*;
 
+    #[test]
+    fn paces_sends_per_domain() {
+        let throttle = DomainThrottle::new(3, Duration::from_millis(100));
+        let start = Instant::now();
+
+        let slots = (0..10)
+            .map(|_| start + throttle.reserve_at("example.com", start))
+            .collect::<Vec<_>>();
+
+        // Any 4 consecutive sends span at least the window
+        for sends in slots.windows(4) {
+            assert!(sends[3] - sends[0] >= Duration::from_millis(100));
+        }
+        assert_eq!(slots[2], start);
+        assert_eq!(slots[9] - start, Duration::from_millis(300));
+
+        // Domains are paced separately and case insensitively
+        assert!(throttle.reserve_at("other.com", start).is_zero());
+        assert_eq!(
+            throttle.reserve_at("EXAMPLE.com", start),
+            Duration::from_millis(300)
+        );
+
+        // Sends leave the window as time passes
+        let later = start + Duration::from_secs(1);
+        assert!(throttle.reserve_at("example.com", later).is_zero());
+    }
+
+    #[test]
+    fn blocks_until_within_rate() {
+        let throttle = DomainThrottle::new(2, Duration::from_millis(50));
+        let start = Instant::now();
+        for _ in 0..5 {
+            std::thread::sleep(throttle.reserve("example.com"));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn extracts_recipient_domain() {
+        assert_eq!(recipient_domain("user@example.com"), Some("example.com"));
+        assert_eq!(
+            recipient_domain("User <user@mail.example.com>"),
+            Some("mail.example.com")
+        );
+        assert_eq!(recipient_domain("user"), None);
+        assert_eq!(recipient_domain("user@"), None);
+    }
+
     #[test]
     fn loads_templates() {
         const TEMPLATE: &str =
@@ -0,0 +1,730 @@
+use std::{future::Future, time::Duration};
+use thiserror::Error;
+
+/// Determines how pooled connections are validated before being handed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecycleMethod {
+    /// Only check connections for errors they already encountered. Cheap, but stale connections are discovered
+    /// on the first query.
+    Fast,
+
+    /// Run a test query on each connection before handing it out, replacing the ones that fail.
+    /// Also known as pre-ping. Adds a round trip to every checkout.
+    #[default]
+    Verified,
+}
+
+/// Connections which can be checked with a lightweight query, used by pools to pre-ping connections
+/// before handing them out.
+pub trait Ping {
+    /// Whether the connection is alive.
+    fn ping(&mut self) -> impl Future<Output = bool> + Send;
+}
+
+#[cfg(feature = "cache-redis")]
+impl Ping for deadpool_redis::redis::aio::Connection {
+    async fn ping(&mut self) -> bool {
+        deadpool_redis::redis::cmd("PING")
+            .query_async::<_, String>(self)
+            .await
+            .is_ok()
+    }
+}
+
+/// Connection recycling settings for pools. Connections behind NAT and load balancers can be silently dropped
+/// while idle in the pool; setting a `max_lifetime` replaces connections once they get older than it.
+///
+/// Apply the settings to the pool's builder/options with the adapter specific methods.
+/// Redis connections obtained from deadpool are always verified with a `PING` when recycled,
+/// so the method only applies to SQL pools.
+///
+/// ### Example
+///
+/// ```ignore
+/// let recycling = Recycling::new(Some(Duration::from_secs(30 * 60)), RecycleMethod::Fast);
+///
+/// let mut opts = ConnectOptions::new(url);
+/// recycling.apply_seaorm(&mut opts);
+///
+/// let pool = recycling.apply_deadpool(redis_config.builder()?).build()?;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Recycling {
+    pub max_lifetime: Option<Duration>,
+    pub method: RecycleMethod,
+    /// How often idle connections are checked in the background, see
+    /// [spawn_health_checks][Recycling::spawn_health_checks].
+    pub health_check_interval: Option<Duration>,
+}
+
+impl Recycling {
+    pub fn new(max_lifetime: Option<Duration>, method: RecycleMethod) -> Self {
+        Self {
+            max_lifetime,
+            method,
+            health_check_interval: None,
+        }
+    }
+
+    /// Check idle connections every `interval`. Only applies to pools whose health checks are spawned.
+    pub fn health_checks(mut self, interval: Duration) -> Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Toggle validating connections before handing them out, transparently replacing dead ones.
+    /// Shorthand for [Verified][RecycleMethod::Verified] and [Fast][RecycleMethod::Fast].
+    pub fn pre_ping(mut self, enabled: bool) -> Self {
+        self.method = if enabled {
+            RecycleMethod::Verified
+        } else {
+            RecycleMethod::Fast
+        };
+        self
+    }
+
+    /// Whether a connection of the given age should be replaced.
+    pub fn is_expired(&self, age: Duration) -> bool {
+        self.max_lifetime.is_some_and(|max| age >= max)
+    }
+
+    #[cfg(any(
+        feature = "db-postgres-seaorm",
+        feature = "db-mysql-seaorm",
+        feature = "db-sqlite-seaorm"
+    ))]
+    pub fn apply_seaorm(&self, opts: &mut sea_orm::ConnectOptions) {
+        if let Some(max_lifetime) = self.max_lifetime {
+            opts.max_lifetime(max_lifetime);
+        }
+        opts.test_before_acquire(self.method == RecycleMethod::Verified);
+    }
+
+    #[cfg(any(
+        feature = "db-postgres-diesel",
+        feature = "db-mysql-diesel",
+        feature = "db-sqlite-diesel"
+    ))]
+    pub fn apply_diesel<M>(&self, builder: diesel::r2d2::Builder<M>) -> diesel::r2d2::Builder<M>
+    where
+        M: diesel::r2d2::ManageConnection,
+    {
+        builder
+            .max_lifetime(self.max_lifetime)
+            .test_on_check_out(self.method == RecycleMethod::Verified)
+    }
+
+    /// Adds a hook to the builder that discards connections older than the max lifetime
+    /// when they are checked out, creating new ones in their place.
+    #[cfg(feature = "cache-redis")]
+    pub fn apply_deadpool<M, W>(
+        &self,
+        builder: deadpool::managed::PoolBuilder<M, W>,
+    ) -> deadpool::managed::PoolBuilder<M, W>
+    where
+        M: deadpool::managed::Manager,
+        W: From<deadpool::managed::Object<M>>,
+    {
+        let this = *self;
+        builder.pre_recycle(deadpool::managed::Hook::sync_fn(move |_, metrics| {
+            if this.is_expired(metrics.age()) {
+                return Err(deadpool::managed::HookError::StaticMessage(
+                    "Connection exceeded its max lifetime",
+                ));
+            }
+            Ok(())
+        }))
+    }
+}
+
+impl Recycling {
+    /// Like [apply_deadpool][Recycling::apply_deadpool], additionally [pinging][Ping] recycled connections
+    /// before handing them out when the method is [Verified][RecycleMethod::Verified]. Connections failing
+    /// the ping are discarded and the next idle one is tried, creating a new one if none are left.
+    ///
+    /// Use for managers which do not test connections on their own when recycling them.
+    /// The redis manager already pings connections when recycling them.
+    #[cfg(feature = "cache-redis")]
+    pub fn apply_deadpool_pre_ping<M, W>(
+        &self,
+        builder: deadpool::managed::PoolBuilder<M, W>,
+    ) -> deadpool::managed::PoolBuilder<M, W>
+    where
+        M: deadpool::managed::Manager,
+        M::Type: Ping + Send,
+        W: From<deadpool::managed::Object<M>>,
+    {
+        let builder = self.apply_deadpool(builder);
+        if self.method != RecycleMethod::Verified {
+            return builder;
+        }
+        builder.post_recycle(deadpool::managed::Hook::async_fn(
+            |conn: &mut M::Type, _| {
+                Box::pin(async move {
+                    if conn.ping().await {
+                        Ok(())
+                    } else {
+                        Err(deadpool::managed::HookError::StaticMessage(
+                            "Connection failed pre-ping",
+                        ))
+                    }
+                })
+            },
+        ))
+    }
+
+    /// Check the pool's idle connections every [health_check_interval][Recycling::health_check_interval],
+    /// so dead ones are replaced in the background instead of by the checkout that stumbles upon them.
+    ///
+    /// Every idle connection is checked out at once, which runs the pool's recycling checks on them, i.e. the
+    /// redis manager's `PING` or the one added by [apply_deadpool_pre_ping][Recycling::apply_deadpool_pre_ping].
+    /// Connections failing them are discarded by the pool and new ones are created in their place, keeping
+    /// the pool warm. Connections in use are left alone.
+    ///
+    /// Returns `None` if no interval is set. The task stops once the pool is closed.
+    #[cfg(feature = "cache-redis")]
+    pub fn spawn_health_checks<M, W>(
+        &self,
+        pool: deadpool::managed::Pool<M, W>,
+    ) -> Option<tokio::task::JoinHandle<()>>
+    where
+        M: deadpool::managed::Manager + 'static,
+        M::Type: Send,
+        M::Error: std::fmt::Debug,
+        W: From<deadpool::managed::Object<M>> + Send + 'static,
+    {
+        let interval = self.health_check_interval?;
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if pool.is_closed() {
+                    return;
+                }
+
+                let idle = pool.status().available;
+                let mut checked = Vec::with_capacity(idle);
+                for _ in 0..idle {
+                    // Another checkout may have taken the connection in the meantime
+                    match tokio::time::timeout(interval, pool.get()).await {
+                        Ok(Ok(conn)) => checked.push(conn),
+                        Ok(Err(e)) => {
+                            tracing::warn!(
+                                "Pool health check could not replace a connection: {e:?}"
+                            );
+                            break;
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PoolConfigError {
+    #[error("Pool max size must be greater than 0")]
+    ZeroMaxSize,
+    #[error("Pool min idle ({min_idle}) exceeds its max size ({max_size})")]
+    MinIdleExceedsMaxSize { min_idle: u32, max_size: u32 },
+    #[error("Pool connection timeout must be greater than 0")]
+    ZeroConnectionTimeout,
+}
+
+/// Sizing and timeout settings for connection pools. Settings left as `None` keep the pool's defaults.
+///
+/// Apply the settings to the pool's builder/options with the adapter specific methods, which
+/// [validate][PoolConfig::validate] them first so invalid settings fail when the pool is built
+/// instead of panicking inside it.
+///
+/// Not every pool supports every setting:
+///
+/// - deadpool has no minimum of idle connections, the pool is filled on demand,
+/// - mongo has no max lifetime.
+///
+/// ### Example
+///
+/// ```ignore
+/// let config = PoolConfig {
+///     max_size: Some(32),
+///     connection_timeout: Some(Duration::from_secs(5)),
+///     ..Default::default()
+/// };
+///
+/// let mut opts = ConnectOptions::new(url);
+/// config.apply_seaorm(&mut opts)?;
+///
+/// let pool = config.apply_deadpool(redis_config.builder()?)?.runtime(Runtime::Tokio1).build()?;
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub max_size: Option<u32>,
+    pub min_idle: Option<u32>,
+    /// How long to wait for a connection before erroring.
+    pub connection_timeout: Option<Duration>,
+    /// How long a connection can stay idle in the pool before it is closed.
+    pub idle_timeout: Option<Duration>,
+    pub max_lifetime: Option<Duration>,
+}
+
+impl PoolConfig {
+    pub fn validate(&self) -> Result<(), PoolConfigError> {
+        if self.max_size == Some(0) {
+            return Err(PoolConfigError::ZeroMaxSize);
+        }
+        if let (Some(min_idle), Some(max_size)) = (self.min_idle, self.max_size) {
+            if min_idle > max_size {
+                return Err(PoolConfigError::MinIdleExceedsMaxSize { min_idle, max_size });
+            }
+        }
+        if self.connection_timeout == Some(Duration::ZERO) {
+            return Err(PoolConfigError::ZeroConnectionTimeout);
+        }
+        Ok(())
+    }
+
+    /// The connection timeout is used both for connecting and acquiring connections from the pool.
+    #[cfg(any(
+        feature = "db-postgres-seaorm",
+        feature = "db-mysql-seaorm",
+        feature = "db-sqlite-seaorm"
+    ))]
+    pub fn apply_seaorm(&self, opts: &mut sea_orm::ConnectOptions) -> Result<(), PoolConfigError> {
+        self.validate()?;
+        if let Some(max_size) = self.max_size {
+            opts.max_connections(max_size);
+        }
+        if let Some(min_idle) = self.min_idle {
+            opts.min_connections(min_idle);
+        }
+        if let Some(timeout) = self.connection_timeout {
+            opts.connect_timeout(timeout).acquire_timeout(timeout);
+        }
+        if let Some(idle_timeout) = self.idle_timeout {
+            opts.idle_timeout(idle_timeout);
+        }
+        if let Some(max_lifetime) = self.max_lifetime {
+            opts.max_lifetime(max_lifetime);
+        }
+        Ok(())
+    }
+
+    #[cfg(any(
+        feature = "db-postgres-diesel",
+        feature = "db-mysql-diesel",
+        feature = "db-sqlite-diesel"
+    ))]
+    pub fn apply_diesel<M>(
+        &self,
+        mut builder: diesel::r2d2::Builder<M>,
+    ) -> Result<diesel::r2d2::Builder<M>, PoolConfigError>
+    where
+        M: diesel::r2d2::ManageConnection,
+    {
+        self.validate()?;
+        if let Some(max_size) = self.max_size {
+            builder = builder.max_size(max_size);
+        }
+        if let Some(min_idle) = self.min_idle {
+            builder = builder.min_idle(Some(min_idle));
+        }
+        if let Some(timeout) = self.connection_timeout {
+            builder = builder.connection_timeout(timeout);
+        }
+        if let Some(idle_timeout) = self.idle_timeout {
+            builder = builder.idle_timeout(Some(idle_timeout));
+        }
+        if let Some(max_lifetime) = self.max_lifetime {
+            builder = builder.max_lifetime(Some(max_lifetime));
+        }
+        Ok(builder)
+    }
+
+    /// The connection timeout is used both for waiting on a connection and creating one, which requires the
+    /// builder to have a runtime. Connections idle or alive for longer than allowed are replaced when checked out.
+    #[cfg(feature = "cache-redis")]
+    pub fn apply_deadpool<M, W>(
+        &self,
+        mut builder: deadpool::managed::PoolBuilder<M, W>,
+    ) -> Result<deadpool::managed::PoolBuilder<M, W>, PoolConfigError>
+    where
+        M: deadpool::managed::Manager,
+        W: From<deadpool::managed::Object<M>>,
+    {
+        self.validate()?;
+        if let Some(max_size) = self.max_size {
+            builder = builder.max_size(max_size as usize);
+        }
+        if let Some(timeout) = self.connection_timeout {
+            builder = builder
+                .wait_timeout(Some(timeout))
+                .create_timeout(Some(timeout));
+        }
+        if self.idle_timeout.is_none() && self.max_lifetime.is_none() {
+            return Ok(builder);
+        }
+
+        let this = *self;
+        Ok(
+            builder.pre_recycle(deadpool::managed::Hook::sync_fn(move |_, metrics| {
+                if this
+                    .idle_timeout
+                    .is_some_and(|idle| metrics.last_used() >= idle)
+                {
+                    return Err(deadpool::managed::HookError::StaticMessage(
+                        "Connection exceeded its idle timeout",
+                    ));
+                }
+                if this.max_lifetime.is_some_and(|max| metrics.age() >= max) {
+                    return Err(deadpool::managed::HookError::StaticMessage(
+                        "Connection exceeded its max lifetime",
+                    ));
+                }
+                Ok(())
+            })),
+        )
+    }
+
+    #[cfg(feature = "db-mongo")]
+    pub fn apply_mongo(
+        &self,
+        opts: &mut mongodb::options::ClientOptions,
+    ) -> Result<(), PoolConfigError> {
+        self.validate()?;
+        if self.max_size.is_some() {
+            opts.max_pool_size = self.max_size;
+        }
+        if self.min_idle.is_some() {
+            opts.min_pool_size = self.min_idle;
+        }
+        if self.connection_timeout.is_some() {
+            opts.connect_timeout = self.connection_timeout;
+        }
+        if self.idle_timeout.is_some() {
+            opts.max_idle_time = self.idle_timeout;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "cache-redis"))]
+mod tests {
+    use super::*;
+    use deadpool::managed::{Manager, Metrics, Pool, RecycleResult};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Connections are the number of the creation.
+    #[derive(Debug, Default)]
+    struct Counting(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl Manager for Counting {
+        type Type = usize;
+        type Error = ();
+
+        async fn create(&self) -> Result<usize, ()> {
+            Ok(self.0.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        async fn recycle(&self, _: &mut usize, _: &Metrics) -> RecycleResult<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn recycles_after_max_lifetime() {
+        let recycling = Recycling::new(Some(Duration::from_millis(50)), RecycleMethod::Verified);
+        let pool: Pool<Counting> = recycling
+            .apply_deadpool(Pool::builder(Counting::default()).max_size(1))
+            .build()
+            .unwrap();
+
+        let conn = pool.get().await.unwrap();
+        assert_eq!(*conn, 1);
+        drop(conn);
+
+        // Still young, gets reused
+        let conn = pool.get().await.unwrap();
+        assert_eq!(*conn, 1);
+        drop(conn);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let conn = pool.get().await.unwrap();
+        assert_eq!(*conn, 2);
+        assert_eq!(pool.status().size, 1);
+    }
+
+    /// Connections are alive until their number is marked dead.
+    #[derive(Debug, Default)]
+    struct Flaky {
+        created: AtomicUsize,
+        dead: Arc<AtomicUsize>,
+    }
+
+    #[derive(Debug)]
+    struct FlakyConnection {
+        id: usize,
+        dead: Arc<AtomicUsize>,
+    }
+
+    impl Ping for FlakyConnection {
+        async fn ping(&mut self) -> bool {
+            self.dead.load(Ordering::SeqCst) != self.id
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Manager for Flaky {
+        type Type = FlakyConnection;
+        type Error = ();
+
+        async fn create(&self) -> Result<FlakyConnection, ()> {
+            Ok(FlakyConnection {
+                id: self.created.fetch_add(1, Ordering::SeqCst) + 1,
+                dead: self.dead.clone(),
+            })
+        }
+
+        async fn recycle(&self, _: &mut FlakyConnection, _: &Metrics) -> RecycleResult<()> {
+            Ok(())
+        }
+    }
+
+    async fn checkout_after_death(pre_ping: bool) -> usize {
+        let recycling = Recycling::default().pre_ping(pre_ping);
+        let manager = Flaky::default();
+        let dead = manager.dead.clone();
+        let pool: Pool<Flaky> = recycling
+            .apply_deadpool_pre_ping(Pool::builder(manager).max_size(1))
+            .build()
+            .unwrap();
+
+        let conn = pool.get().await.unwrap();
+        assert_eq!(conn.id, 1);
+        drop(conn);
+
+        // The idle connection gets dropped by the server
+        dead.store(1, Ordering::SeqCst);
+
+        let conn = pool.get().await.unwrap();
+        conn.id
+    }
+
+    #[tokio::test]
+    async fn pre_ping_replaces_dead_connections() {
+        assert_eq!(checkout_after_death(true).await, 2);
+        assert_eq!(checkout_after_death(false).await, 1);
+    }
+
+    #[tokio::test]
+    async fn health_checks_replace_dead_connections() {
+        let recycling = Recycling::default().health_checks(Duration::from_millis(20));
+        let manager = Flaky::default();
+        let dead = manager.dead.clone();
+        let pool: Pool<Flaky> = recycling
+            .apply_deadpool_pre_ping(Pool::builder(manager).max_size(2))
+            .build()
+            .unwrap();
+
+        let conns = (pool.get().await.unwrap(), pool.get().await.unwrap());
+        assert_eq!((conns.0.id, conns.1.id), (1, 2));
+        drop(conns);
+
+        let task = recycling.spawn_health_checks(pool.clone()).unwrap();
+        dead.store(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(70)).await;
+
+        // Replaced before any checkout noticed
+        assert_eq!(pool.manager().created.load(Ordering::SeqCst), 3);
+        assert_eq!(pool.status().size, 2);
+        assert_eq!(pool.status().available, 2);
+
+        let mut ids = [pool.get().await.unwrap(), pool.get().await.unwrap()].map(|conn| conn.id);
+        ids.sort();
+        assert_eq!(ids, [2, 3]);
+
+        pool.close();
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(Recycling::default().spawn_health_checks(pool).is_none());
+    }
+
+    #[tokio::test]
+    async fn pool_config_applies_to_deadpool() {
+        let config = PoolConfig {
+            max_size: Some(2),
+            idle_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let pool: Pool<Counting> = config
+            .apply_deadpool(Pool::builder(Counting::default()))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(pool.status().max_size, 2);
+
+        let conn = pool.get().await.unwrap();
+        assert_eq!(*conn, 1);
+        drop(conn);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(*pool.get().await.unwrap(), 2);
+
+        let zero = PoolConfig {
+            max_size: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            zero.apply_deadpool(Pool::<Counting>::builder(Counting::default()))
+                .err(),
+            Some(PoolConfigError::ZeroMaxSize)
+        );
+    }
+
+    #[test]
+    fn expiration() {
+        let recycling = Recycling::default();
+        assert!(!recycling.is_expired(Duration::MAX));
+
+        let recycling = Recycling::new(Some(Duration::from_secs(1)), RecycleMethod::Fast);
+        assert!(!recycling.is_expired(Duration::from_millis(999)));
+        assert!(recycling.is_expired(Duration::from_secs(1)));
+    }
+}
+
+#[cfg(all(test, feature = "db-sqlite-diesel"))]
+mod diesel_tests {
+    use super::*;
+    use diesel::r2d2::{ManageConnection, Pool};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// Connections are the number of the creation and are alive until their number is marked dead.
+    #[derive(Debug, Default)]
+    struct Flaky {
+        created: AtomicUsize,
+        dead: Arc<AtomicUsize>,
+    }
+
+    impl ManageConnection for Flaky {
+        type Connection = usize;
+        type Error = diesel::r2d2::Error;
+
+        fn connect(&self) -> Result<usize, Self::Error> {
+            Ok(self.created.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        fn is_valid(&self, conn: &mut usize) -> Result<(), Self::Error> {
+            if self.dead.load(Ordering::SeqCst) == *conn {
+                return Err(diesel::r2d2::Error::QueryError(
+                    diesel::result::Error::BrokenTransactionManager,
+                ));
+            }
+            Ok(())
+        }
+
+        fn has_broken(&self, _: &mut usize) -> bool {
+            false
+        }
+    }
+
+    fn checkout_after_death(pre_ping: bool) -> usize {
+        let manager = Flaky::default();
+        let dead = manager.dead.clone();
+        let pool = Recycling::default()
+            .pre_ping(pre_ping)
+            .apply_diesel(Pool::builder().max_size(1).min_idle(Some(0)))
+            .build(manager)
+            .unwrap();
+
+        let conn = pool.get().unwrap();
+        assert_eq!(*conn, 1);
+        drop(conn);
+
+        dead.store(1, Ordering::SeqCst);
+
+        let conn = pool.get().unwrap();
+        *conn
+    }
+
+    #[test]
+    fn pre_ping_replaces_dead_connections() {
+        assert_eq!(checkout_after_death(true), 2);
+        assert_eq!(checkout_after_death(false), 1);
+    }
+
+    #[test]
+    fn pool_config_applies_to_diesel() {
+        let config = PoolConfig {
+            max_size: Some(3),
+            min_idle: Some(1),
+            ..Default::default()
+        };
+        let pool = config
+            .apply_diesel(Pool::builder())
+            .unwrap()
+            .build(Flaky::default())
+            .unwrap();
+        assert_eq!(pool.max_size(), 3);
+        assert_eq!(pool.min_idle(), Some(1));
+
+        // r2d2 panics on a zero max size, it must be rejected before reaching it
+        let zero = PoolConfig {
+            max_size: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            zero.apply_diesel(Pool::<Flaky>::builder()),
+            Err(PoolConfigError::ZeroMaxSize)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn pool_config_validation() {
+        assert_eq!(PoolConfig::default().validate(), Ok(()));
+
+        let config = PoolConfig {
+            max_size: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Err(PoolConfigError::ZeroMaxSize));
+
+        let config = PoolConfig {
+            max_size: Some(4),
+            min_idle: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(PoolConfigError::MinIdleExceedsMaxSize {
+                min_idle: 8,
+                max_size: 4
+            })
+        );
+
+        let config = PoolConfig {
+            connection_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(PoolConfigError::ZeroConnectionTimeout)
+        );
+    }
+}
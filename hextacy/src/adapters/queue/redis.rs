@@ -71,6 +71,17 @@ impl RedisMessageQueue {
             stream: Box::pin(pubsub.into_on_message()),
         })
     }
+
+    /// Subscribe to all the channels with a single `SUBSCRIBE` so no message published
+    /// to any of them can be missed while subscribing.
+    pub async fn consumer_many(&self, channels: &[&str]) -> Result<RedisConsumer, RedisError> {
+        let conn = self.client.get_async_connection().await?;
+        let mut pubsub = conn.into_pubsub();
+        pubsub.subscribe(channels).await?;
+        Ok(RedisConsumer {
+            stream: Box::pin(pubsub.into_on_message()),
+        })
+    }
 }
 
 #[derive(Clone)]
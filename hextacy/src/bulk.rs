@@ -0,0 +1,96 @@
+/// The outcome of a bulk operation that keeps going when individual items fail.
+///
+/// Failures are stored with the index of the item in the original input, so callers can retry
+/// only the items that failed.
+///
+/// ### Example
+///
+/// ```ignore
+/// let result: BulkResult<User, AdapterError> = users
+///     .iter()
+///     .map(|user| repo.insert(user))
+///     .collect();
+///
+/// let retry = result.failed_indices().map(|i| &users[i]).collect::<Vec<_>>();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkResult<T, E> {
+    pub succeeded: Vec<T>,
+    pub failed: Vec<(usize, E)>,
+}
+
+impl<T, E> Default for BulkResult<T, E> {
+    fn default() -> Self {
+        Self {
+            succeeded: vec![],
+            failed: vec![],
+        }
+    }
+}
+
+impl<T, E> BulkResult<T, E> {
+    /// Record the result of the item at `index`.
+    pub fn push(&mut self, index: usize, result: Result<T, E>) {
+        match result {
+            Ok(value) => self.succeeded.push(value),
+            Err(e) => self.failed.push((index, e)),
+        }
+    }
+
+    /// Whether every item succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Indices of the items that failed, in input order.
+    pub fn failed_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.failed.iter().map(|(i, _)| *i)
+    }
+
+    /// The total amount of processed items.
+    pub fn len(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Collects the results in input order, indexing failures by their position in the iterator.
+impl<T, E> FromIterator<Result<T, E>> for BulkResult<T, E> {
+    fn from_iter<I: IntoIterator<Item = Result<T, E>>>(iter: I) -> Self {
+        let mut result = Self::default();
+        for (i, item) in iter.into_iter().enumerate() {
+            result.push(i, item);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_failed_indices_without_aborting() {
+        let mut processed = 0;
+        let result: BulkResult<i32, String> = ["1", "two", "3", "", "5"]
+            .iter()
+            .map(|item| {
+                processed += 1;
+                item.parse::<i32>().map_err(|_| format!("invalid: {item}"))
+            })
+            .collect();
+
+        assert_eq!(processed, 5);
+        assert_eq!(result.succeeded, [1, 3, 5]);
+        assert_eq!(result.failed_indices().collect::<Vec<_>>(), [1, 3]);
+        assert_eq!(result.failed[0].1, "invalid: two");
+        assert_eq!(result.len(), 5);
+        assert!(!result.is_complete());
+
+        let result: BulkResult<i32, String> = [Ok(1), Ok(2)].into_iter().collect();
+        assert!(result.is_complete());
+    }
+}
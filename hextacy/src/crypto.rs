@@ -1,8 +1,13 @@
 //! Common crypto functionalities used in web apps. Can be utilised to reduce the amount of imports.
 
 pub mod hmac;
+pub mod id;
 pub mod jwt;
+pub mod keyring;
 pub mod otp;
+#[cfg(feature = "pwned")]
+pub mod pwned;
+pub mod webhook;
 
 use bcrypt;
 pub use bcrypt::BcryptError;
@@ -68,4 +73,10 @@ pub enum CryptoError {
     Thotp(#[from] thotp::ThotpError),
     #[error("{0}")]
     FromUtf8(#[from] std::string::FromUtf8Error),
+    #[error("Unknown key version: {0}")]
+    UnknownKeyVersion(u32),
+    #[error("Malformed ciphertext")]
+    MalformedCiphertext,
+    #[error("{0}")]
+    Aead(aes_gcm::Error),
 }
@@ -0,0 +1,72 @@
+use uuid::Uuid;
+
+/// Generates IDs for new rows. Inject an implementation into repositories to choose the ID scheme
+/// without touching the queries.
+///
+/// ### Example
+///
+/// ```ignore
+/// struct UserAdapter<G> {
+///     driver: SeaormDriver,
+///     ids: G,
+/// }
+///
+/// impl<G: IdGenerator> UserAdapter<G> {
+///     async fn create(&self, username: &str) -> Result<User, AdapterError> {
+///         let user = User::new(self.ids.generate(), username);
+///         // ...
+///     }
+/// }
+/// ```
+pub trait IdGenerator: Send + Sync {
+    fn generate(&self) -> String;
+}
+
+/// Random UUID v4 IDs. The default scheme.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+    fn generate(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// Time ordered UUID v7 IDs. IDs generated by the same process sort in the order they were generated,
+/// which keeps B-tree indexes compact and allows ordering rows by ID.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7;
+
+impl IdGenerator for UuidV7 {
+    fn generate(&self) -> String {
+        Uuid::now_v7().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(ids: &impl IdGenerator) -> Vec<String> {
+        (0..1000).map(|_| ids.generate()).collect()
+    }
+
+    #[test]
+    fn v7_is_sortable() {
+        let ids = generate(&UuidV7);
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+        assert!(ids.windows(2).all(|w| w[0] != w[1]));
+        assert_eq!(Uuid::parse_str(&ids[0]).unwrap().get_version_num(), 7);
+    }
+
+    #[test]
+    fn v4_is_random() {
+        let ids = generate(&UuidV4);
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_ne!(ids, sorted);
+        assert_eq!(Uuid::parse_str(&ids[0]).unwrap().get_version_num(), 4);
+    }
+}
@@ -0,0 +1,154 @@
+use super::CryptoError;
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use std::collections::BTreeMap;
+
+/// Bytes used to store the key version at the start of the ciphertext.
+const VERSION_LEN: usize = 4;
+
+/// Bytes of the AES-GCM nonce stored after the version.
+const NONCE_LEN: usize = 12;
+
+/// Holds versioned AES-256-GCM keys, allowing keys to be rotated without losing access to data encrypted with older ones.
+///
+/// Data is always encrypted with the current key and the ciphertext is prefixed with the key's version,
+/// so decryption picks the key the data was encrypted with.
+///
+/// ### Example
+///
+/// ```ignore
+/// let mut ring = KeyRing::new(1, generate_key());
+/// let old = ring.encrypt(b"secret")?;
+///
+/// ring.rotate(2, generate_key());
+/// let new = ring.encrypt(b"secret")?;
+///
+/// assert_eq!(ring.decrypt(&old)?, ring.decrypt(&new)?);
+/// ```
+#[derive(Clone)]
+pub struct KeyRing {
+    current: u32,
+    keys: BTreeMap<u32, Key<Aes256Gcm>>,
+}
+
+impl std::fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("current", &self.current)
+            .field("versions", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Generates a random 256 bit key.
+pub fn generate_key() -> [u8; 32] {
+    Aes256Gcm::generate_key(OsRng).into()
+}
+
+impl KeyRing {
+    pub fn new(version: u32, key: [u8; 32]) -> Self {
+        Self {
+            current: version,
+            keys: BTreeMap::from([(version, key.into())]),
+        }
+    }
+
+    /// Add the key and encrypt with it from now on. Keys with other versions are kept for decryption.
+    pub fn rotate(&mut self, version: u32, key: [u8; 32]) {
+        self.keys.insert(version, key.into());
+        self.current = version;
+    }
+
+    /// Remove a key once nothing is encrypted with it anymore. The current key cannot be retired.
+    /// Returns whether the key was removed.
+    pub fn retire(&mut self, version: u32) -> bool {
+        version != self.current && self.keys.remove(&version).is_some()
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.current
+    }
+
+    /// Encrypt with the current key. The result is the key version, the nonce and the ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let key = &self.keys[&self.current];
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = Aes256Gcm::new(key)
+            .encrypt(&nonce, plaintext)
+            .map_err(CryptoError::Aead)?;
+
+        let mut out = Vec::with_capacity(VERSION_LEN + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(&self.current.to_be_bytes());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt data produced by [encrypt][KeyRing::encrypt] with any of the keys still in the ring.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if data.len() < VERSION_LEN + NONCE_LEN {
+            return Err(CryptoError::MalformedCiphertext);
+        }
+        let (version, rest) = data.split_at(VERSION_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let version = u32::from_be_bytes(version.try_into().expect("length checked"));
+        let key = self
+            .keys
+            .get(&version)
+            .ok_or(CryptoError::UnknownKeyVersion(version))?;
+
+        Aes256Gcm::new(key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(CryptoError::Aead)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() {
+        let mut ring = KeyRing::new(1, generate_key());
+        let v1 = ring.encrypt(b"encrypted with v1").unwrap();
+
+        ring.rotate(2, generate_key());
+        assert_eq!(ring.current_version(), 2);
+        let v2 = ring.encrypt(b"encrypted with v2").unwrap();
+
+        assert_eq!(&v1[..4], 1u32.to_be_bytes());
+        assert_eq!(&v2[..4], 2u32.to_be_bytes());
+
+        assert_eq!(ring.decrypt(&v1).unwrap(), b"encrypted with v1");
+        assert_eq!(ring.decrypt(&v2).unwrap(), b"encrypted with v2");
+
+        assert!(!ring.retire(2));
+        assert!(ring.retire(1));
+        assert!(matches!(
+            ring.decrypt(&v1),
+            Err(CryptoError::UnknownKeyVersion(1))
+        ));
+    }
+
+    #[test]
+    fn tampering() {
+        let ring = KeyRing::new(1, generate_key());
+        let mut data = ring.encrypt(b"secret").unwrap();
+
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(matches!(ring.decrypt(&data), Err(CryptoError::Aead(_))));
+
+        assert!(matches!(
+            ring.decrypt(&data[..10]),
+            Err(CryptoError::MalformedCiphertext)
+        ));
+
+        let other = KeyRing::new(1, generate_key());
+        let data = ring.encrypt(b"secret").unwrap();
+        assert!(other.decrypt(&data).is_err());
+    }
+}
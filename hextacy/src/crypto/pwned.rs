@@ -0,0 +1,153 @@
+use data_encoding::HEXUPPER;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+/// The HaveIBeenPwned range API.
+pub const HIBP_RANGE_URL: &str = "https://api.pwnedpasswords.com/range";
+
+/// Checks passwords against the HaveIBeenPwned breach corpus using k-anonymity.
+///
+/// Only the first 5 characters of the password's SHA-1 hash are sent to the API, which responds with the suffixes
+/// of every breached hash in that range. The suffix is then looked up locally, so neither the password nor
+/// its full hash ever leave the process.
+///
+/// ### Example
+///
+/// ```ignore
+/// if PwnedClient::default().is_pwned(&password).await? {
+///     return Err(Error::PasswordBreached);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PwnedClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Default for PwnedClient {
+    fn default() -> Self {
+        Self::new(HIBP_RANGE_URL)
+    }
+}
+
+impl PwnedClient {
+    /// `base_url` is the URL the hash prefix is appended to, usually [HIBP_RANGE_URL].
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// How many times the password appears in breaches, 0 if it doesn't.
+    pub async fn occurrences(&self, password: &str) -> Result<u64, PwnedError> {
+        let hash = HEXUPPER.encode(&Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+
+        let body = self
+            .client
+            .get(format!("{}/{prefix}", self.base_url))
+            // Pads responses with fake entries so their size does not reveal the prefix
+            .header("Add-Padding", "true")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(find_suffix(&body, suffix))
+    }
+
+    pub async fn is_pwned(&self, password: &str) -> Result<bool, PwnedError> {
+        Ok(self.occurrences(password).await? > 0)
+    }
+}
+
+/// Checks whether the password has been breached using the HaveIBeenPwned API.
+/// See [PwnedClient].
+pub async fn pwned_check(password: &str) -> Result<bool, PwnedError> {
+    PwnedClient::default().is_pwned(password).await
+}
+
+/// Finds the count of the suffix in a range response of `SUFFIX:COUNT` lines.
+/// Padding entries have a count of 0.
+fn find_suffix(body: &str, suffix: &str) -> u64 {
+    body.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.parse().ok())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Error)]
+pub enum PwnedError {
+    #[error("HaveIBeenPwned: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    // SHA-1 of "password" is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+    const RANGE: &str = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                         1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n\
+                         1E4C9B93F3F0682250B6CF8331B7EE68FD9:0\r\n\
+                         011053FD0102E94D6AE2F8B83D76FAF94F6:13";
+
+    /// Serves the range for every request and returns the requested paths.
+    async fn mock_server() -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/range", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut paths = vec![];
+            for _ in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                paths.push(request.split(' ').nth(1).unwrap().to_string());
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{RANGE}",
+                    RANGE.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            paths
+        });
+
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn checks_suffix_locally() {
+        let (url, server) = mock_server().await;
+        let client = PwnedClient::new(&url);
+
+        assert_eq!(client.occurrences("password").await.unwrap(), 9659365);
+        assert!(!client
+            .is_pwned("correct horse battery staple")
+            .await
+            .unwrap());
+
+        // Only the hash prefix is sent
+        let paths = server.await.unwrap();
+        assert_eq!(paths[0], "/range/5BAA6");
+        assert_eq!(paths[1].len(), "/range/".len() + 5);
+    }
+
+    #[test]
+    fn padding_is_not_a_breach() {
+        assert_eq!(find_suffix(RANGE, "1E4C9B93F3F0682250B6CF8331B7EE68FD9"), 0);
+        assert_eq!(
+            find_suffix(RANGE, "011053fd0102e94d6ae2f8b83d76faf94f6"),
+            13
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+use hmac::Mac;
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WebhookError {
+    #[error("Malformed signature header")]
+    MalformedHeader,
+    #[error("Timestamp outside of tolerance")]
+    StaleTimestamp,
+    #[error("Signature mismatch")]
+    InvalidSignature,
+}
+
+/// Verifies a webhook signature header in the `t=<timestamp>,v1=<signature>` format used by providers such as Stripe.
+///
+/// The signature is the hex encoded HMAC-SHA256 of `<timestamp>.<payload>`. Headers can contain multiple `v1` signatures,
+/// e.g. while the secret is being rolled, and the payload is valid if any of them match. Timestamps further than `tolerance`
+/// from now are rejected to prevent replaying captured requests.
+///
+/// `payload` must be the raw request body, exactly as received.
+///
+/// ### Example
+///
+/// ```ignore
+/// let header = req.headers().get("stripe-signature").and_then(|h| h.to_str().ok()).unwrap_or_default();
+/// webhook::verify(&body, header, secret.as_bytes(), Duration::from_secs(300))?;
+/// ```
+pub fn verify(
+    payload: &[u8],
+    header: &str,
+    secret: &[u8],
+    tolerance: Duration,
+) -> Result<(), WebhookError> {
+    verify_at(payload, header, secret, tolerance, unix_now())
+}
+
+/// Creates a signature header for the payload in the format accepted by [verify].
+pub fn sign(payload: &[u8], secret: &[u8], timestamp: u64) -> String {
+    let signature = HEXLOWER.encode(
+        &signed_payload_mac(payload, secret, timestamp)
+            .finalize()
+            .into_bytes(),
+    );
+    format!("t={timestamp},v1={signature}")
+}
+
+fn verify_at(
+    payload: &[u8],
+    header: &str,
+    secret: &[u8],
+    tolerance: Duration,
+    now: u64,
+) -> Result<(), WebhookError> {
+    let mut timestamp = None;
+    let mut signatures = vec![];
+
+    for pair in header.split(',') {
+        let Some((key, value)) = pair.trim().split_once('=') else {
+            return Err(WebhookError::MalformedHeader);
+        };
+        match key {
+            "t" => {
+                let t = value
+                    .parse::<u64>()
+                    .map_err(|_| WebhookError::MalformedHeader)?;
+                timestamp = Some(t);
+            }
+            "v1" => signatures.push(value),
+            // Other schemes, e.g. `v0` test signatures
+            _ => {}
+        }
+    }
+
+    let Some(timestamp) = timestamp else {
+        return Err(WebhookError::MalformedHeader);
+    };
+
+    if signatures.is_empty() {
+        return Err(WebhookError::MalformedHeader);
+    }
+
+    if now.abs_diff(timestamp) > tolerance.as_secs() {
+        return Err(WebhookError::StaleTimestamp);
+    }
+
+    let mac = signed_payload_mac(payload, secret, timestamp);
+
+    let valid = signatures.into_iter().any(|signature| {
+        HEXLOWER_PERMISSIVE
+            .decode(signature.as_bytes())
+            .is_ok_and(|signature| mac.clone().verify_slice(&signature).is_ok())
+    });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(WebhookError::InvalidSignature)
+    }
+}
+
+fn signed_payload_mac(payload: &[u8], secret: &[u8], timestamp: u64) -> hmac::Hmac<Sha256> {
+    let mut mac =
+        hmac::Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"whsec_test";
+    const PAYLOAD: &[u8] = br#"{"id":"evt_1","type":"charge.succeeded"}"#;
+    const TOLERANCE: Duration = Duration::from_secs(300);
+
+    #[test]
+    fn valid_signature() {
+        let now = unix_now();
+        let header = sign(PAYLOAD, SECRET, now);
+        assert_eq!(verify(PAYLOAD, &header, SECRET, TOLERANCE), Ok(()));
+
+        // Any of multiple signatures can match
+        let (_, signature) = header.split_once(',').unwrap();
+        let header = format!("t={now},v1=deadbeef,{signature}");
+        assert_eq!(verify(PAYLOAD, &header, SECRET, TOLERANCE), Ok(()));
+    }
+
+    #[test]
+    fn tampered_payload() {
+        let now = unix_now();
+        let header = sign(PAYLOAD, SECRET, now);
+
+        let tampered = br#"{"id":"evt_1","type":"charge.refunded"}"#;
+        assert_eq!(
+            verify(tampered, &header, SECRET, TOLERANCE),
+            Err(WebhookError::InvalidSignature)
+        );
+        assert_eq!(
+            verify(PAYLOAD, &header, b"other_secret", TOLERANCE),
+            Err(WebhookError::InvalidSignature)
+        );
+
+        // Signed timestamp cannot be swapped for a fresh one
+        let header = header.replace(&format!("t={now}"), &format!("t={}", now + 1));
+        assert_eq!(
+            verify(PAYLOAD, &header, SECRET, TOLERANCE),
+            Err(WebhookError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn stale_timestamp() {
+        let header = sign(PAYLOAD, SECRET, 1_000);
+
+        assert_eq!(
+            verify_at(PAYLOAD, &header, SECRET, TOLERANCE, 1_300),
+            Ok(())
+        );
+        assert_eq!(
+            verify_at(PAYLOAD, &header, SECRET, TOLERANCE, 1_301),
+            Err(WebhookError::StaleTimestamp)
+        );
+    }
+
+    #[test]
+    fn malformed_header() {
+        for header in ["", "v1=abc", "t=1000", "t=abc,v1=abc", "garbage"] {
+            assert_eq!(
+                verify_at(PAYLOAD, header, SECRET, TOLERANCE, 1_000),
+                Err(WebhookError::MalformedHeader),
+                "{header}"
+            );
+        }
+    }
+}
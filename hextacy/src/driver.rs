@@ -1,4 +1,10 @@
-use std::future::Future;
+use std::{
+    backtrace::Backtrace,
+    future::Future,
+    ops::{Deref, DerefMut},
+    time::{Duration, Instant},
+};
+use tracing::{info_span, warn, Instrument, Span};
 
 /// Drivers are intended to provide a simple interface for establishing generic connections that other components
 /// can use to remain decoupled from a concrete implementation. By utilising this trait, concrete data sources and clients
@@ -17,6 +23,19 @@ pub trait Driver {
     type Error;
 
     fn connect(&self) -> impl Future<Output = Result<Self::Connection, Self::Error>>;
+
+    /// Verifies the data source is reachable, e.g. before a load balancer routes traffic to the instance,
+    /// and returns how long the probe took so slow pools can be logged.
+    ///
+    /// By default this only acquires a connection. Adapters override it to make a round trip to the backend,
+    /// since pools may hand out connections without touching the network.
+    fn health_check(&self) -> impl Future<Output = Result<Duration, Self::Error>> {
+        async {
+            let start = Instant::now();
+            self.connect().await?;
+            Ok(start.elapsed())
+        }
+    }
 }
 
 /// Used for creating bounds on generic connections when the adapter needs to have atomic repository access.
@@ -30,6 +49,10 @@ pub trait Driver {
 /// When they are struct based, the adapter must implement a repository trait for both the
 /// connection and transaction (usually a trait is provided for both so one can use it to
 /// mitigate 2 different implementations).
+///
+/// Savepoints allow an operation running within an already open transaction to be undone without aborting
+/// the whole transaction, e.g. when a method that wants its own transaction is called from another one.
+/// They are stacked, releasing or rolling back always affects the most recent savepoint.
 pub trait Atomic: Sized {
     type TransactionResult;
     type Error;
@@ -43,6 +66,433 @@ pub trait Atomic: Sized {
     fn abort_transaction(
         tx: Self::TransactionResult,
     ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Mark a savepoint in the open transaction.
+    fn start_savepoint(
+        tx: &mut Self::TransactionResult,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Keep the changes made since the latest savepoint. They are still subject to the outcome
+    /// of the transaction.
+    fn release_savepoint(
+        tx: &mut Self::TransactionResult,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Undo the changes made since the latest savepoint, leaving the transaction open.
+    fn rollback_to_savepoint(
+        tx: &mut Self::TransactionResult,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Used by the `savepoint` variant of [transaction] so its block is atomic whether or not a transaction is open.
+    ///
+    /// By default this starts a savepoint and returns `None`. Implementations which can tell that no transaction
+    /// is open start one instead and return it, the block then runs in it and it gets committed or aborted.
+    fn start_nested(
+        tx: &mut Self::TransactionResult,
+    ) -> impl Future<Output = Result<Option<Self::TransactionResult>, Self::Error>> + Send
+    where
+        Self::TransactionResult: Send,
+    {
+        async move {
+            Self::start_savepoint(tx).await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Either a plain connection or one with an open transaction.
+///
+/// When the [transaction result][Atomic::TransactionResult] is a different type than the connection,
+/// repositories would have to be implemented for both. Wrapping the connection in this enum allows adapters to
+/// implement each query once against a single type. It implements [Atomic] with itself as the transaction result
+/// so it can be used with the [transaction] macro.
+///
+/// Starting a transaction on an already transactional `Conn` joins the open transaction instead of nesting one,
+/// and committing or aborting a plain `Conn` is a no-op, as are savepoints on it. The `savepoint` variant of
+/// [transaction] starts a transaction on a clone of a plain `Conn` instead, which is why the connection must be
+/// a cheaply cloned handle to a pool, like seaorm's.
+pub enum Conn<C: Atomic> {
+    Plain(C),
+    Transaction(C::TransactionResult),
+}
+
+impl<C: Atomic> Conn<C> {
+    pub fn is_transaction(&self) -> bool {
+        matches!(self, Self::Transaction(_))
+    }
+}
+
+impl<C: Atomic> From<C> for Conn<C> {
+    fn from(value: C) -> Self {
+        Self::Plain(value)
+    }
+}
+
+impl<C> std::fmt::Debug for Conn<C>
+where
+    C: Atomic + std::fmt::Debug,
+    C::TransactionResult: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Plain(c) => f.debug_tuple("Plain").field(c).finish(),
+            Self::Transaction(tx) => f.debug_tuple("Transaction").field(tx).finish(),
+        }
+    }
+}
+
+impl<C> Atomic for Conn<C>
+where
+    C: Atomic + Clone + Send,
+    C::TransactionResult: Send,
+{
+    type TransactionResult = Self;
+    type Error = C::Error;
+
+    async fn start_transaction(self) -> Result<Self, Self::Error> {
+        match self {
+            Self::Plain(conn) => Ok(Self::Transaction(conn.start_transaction().await?)),
+            tx => Ok(tx),
+        }
+    }
+
+    async fn commit_transaction(tx: Self) -> Result<(), Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::commit_transaction(tx).await,
+            Self::Plain(_) => Ok(()),
+        }
+    }
+
+    async fn abort_transaction(tx: Self) -> Result<(), Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::abort_transaction(tx).await,
+            Self::Plain(_) => Ok(()),
+        }
+    }
+
+    async fn start_savepoint(tx: &mut Self) -> Result<(), Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::start_savepoint(tx).await,
+            Self::Plain(_) => Ok(()),
+        }
+    }
+
+    async fn release_savepoint(tx: &mut Self) -> Result<(), Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::release_savepoint(tx).await,
+            Self::Plain(_) => Ok(()),
+        }
+    }
+
+    async fn rollback_to_savepoint(tx: &mut Self) -> Result<(), Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::rollback_to_savepoint(tx).await,
+            Self::Plain(_) => Ok(()),
+        }
+    }
+
+    async fn start_nested(tx: &mut Self) -> Result<Option<Self>, Self::Error> {
+        match tx {
+            Self::Transaction(tx) => C::start_savepoint(tx).await.map(|_| None),
+            Self::Plain(conn) => Ok(Some(Self::Transaction(
+                conn.clone().start_transaction().await?,
+            ))),
+        }
+    }
+}
+
+/// Attaches a human readable label to a [Driver] and the connections it provides, i.e. `"primary-pg"` or `"analytics-replica"`,
+/// so logs indicate which data source was hit when multiple drivers are in use.
+///
+/// Connecting happens in a `db.connect` span with the label in its `db` field. Connections obtained from a labeled
+/// driver are labeled as well and provide [span][Labeled::span] for instrumenting queries.
+///
+/// Derefs to the wrapped driver or connection.
+///
+/// ### Example
+///
+/// ```ignore
+/// let driver = Labeled::new("analytics-replica", pool);
+/// let conn = driver.connect().await?;
+/// let rows = Entity::find().all(&*conn).instrument(conn.span("find_all")).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Labeled<T> {
+    label: &'static str,
+    inner: T,
+}
+
+impl<T> Labeled<T> {
+    pub fn new(label: &'static str, inner: T) -> Self {
+        Self { label, inner }
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns a `db.query` span with the label in its `db` field and `query` set to `name`.
+    pub fn span(&self, name: &str) -> Span {
+        info_span!("db.query", db = self.label, query = name)
+    }
+}
+
+impl<T> Deref for Labeled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Labeled<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<D> Driver for Labeled<D>
+where
+    D: Driver,
+{
+    type Connection = Labeled<D::Connection>;
+    type Error = D::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let conn = self
+            .inner
+            .connect()
+            .instrument(info_span!("db.connect", db = self.label))
+            .await?;
+        Ok(Labeled::new(self.label, conn))
+    }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.inner
+            .health_check()
+            .instrument(info_span!("db.health_check", db = self.label))
+            .await
+    }
+}
+
+impl<C> Atomic for Labeled<C>
+where
+    C: Atomic + Send,
+    C::TransactionResult: Send,
+{
+    type TransactionResult = Labeled<C::TransactionResult>;
+    type Error = C::Error;
+
+    async fn start_transaction(self) -> Result<Self::TransactionResult, Self::Error> {
+        let label = self.label;
+        let tx = self.inner.start_transaction().await?;
+        Ok(Labeled::new(label, tx))
+    }
+
+    async fn commit_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        C::commit_transaction(tx.inner).await
+    }
+
+    async fn abort_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        C::abort_transaction(tx.inner).await
+    }
+
+    async fn start_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::start_savepoint(&mut tx.inner).await
+    }
+
+    async fn release_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::release_savepoint(&mut tx.inner).await
+    }
+
+    async fn rollback_to_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::rollback_to_savepoint(&mut tx.inner).await
+    }
+
+    async fn start_nested(
+        tx: &mut Self::TransactionResult,
+    ) -> Result<Option<Self::TransactionResult>, Self::Error> {
+        let nested = C::start_nested(&mut tx.inner).await?;
+        Ok(nested.map(|nested| Labeled::new(tx.label, nested)))
+    }
+}
+
+/// Wraps a [Driver] and logs a warning when a connection obtained from it is held for longer than `threshold`,
+/// e.g. across an await on an external API, which starves the pool.
+///
+/// The warning is emitted when the connection is released and includes the span that was current when it was
+/// checked out. If backtraces are enabled with `RUST_BACKTRACE`, the backtrace of the checkout is included as well.
+///
+/// Derefs to the wrapped driver.
+///
+/// ### Example
+///
+/// ```ignore
+/// let driver = Watched::new(pool, Duration::from_secs(5));
+/// let conn = driver.connect().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Watched<D> {
+    inner: D,
+    threshold: Duration,
+}
+
+impl<D> Watched<D> {
+    pub fn new(inner: D, threshold: Duration) -> Self {
+        Self { inner, threshold }
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D> Deref for Watched<D> {
+    type Target = D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<D> Driver for Watched<D>
+where
+    D: Driver,
+{
+    type Connection = WatchedConnection<D::Connection>;
+    type Error = D::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let conn = self.inner.connect().await?;
+        Ok(WatchedConnection {
+            inner: conn,
+            checkout: Checkout {
+                at: Instant::now(),
+                threshold: self.threshold,
+                span: Span::current(),
+                backtrace: Backtrace::capture(),
+            },
+        })
+    }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.inner.health_check().await
+    }
+}
+
+/// A connection obtained from a [Watched] driver. Transactions started on it remain watched
+/// from the original checkout.
+///
+/// Derefs to the wrapped connection.
+#[derive(Debug)]
+pub struct WatchedConnection<C> {
+    inner: C,
+    checkout: Checkout,
+}
+
+#[derive(Debug)]
+struct Checkout {
+    at: Instant,
+    threshold: Duration,
+    span: Span,
+    backtrace: Backtrace,
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let held = self.at.elapsed();
+        if held <= self.threshold {
+            return;
+        }
+        self.span.in_scope(|| {
+            warn!(
+                held = ?held,
+                threshold = ?self.threshold,
+                backtrace = %self.backtrace,
+                "Connection held for {held:?}, longer than the {:?} threshold",
+                self.threshold
+            )
+        });
+    }
+}
+
+impl<C> Deref for WatchedConnection<C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<C> DerefMut for WatchedConnection<C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<C> Atomic for WatchedConnection<C>
+where
+    C: Atomic + Send,
+    C::TransactionResult: Send,
+{
+    type TransactionResult = WatchedConnection<C::TransactionResult>;
+    type Error = C::Error;
+
+    async fn start_transaction(self) -> Result<Self::TransactionResult, Self::Error> {
+        let Self { inner, checkout } = self;
+        let tx = inner.start_transaction().await?;
+        Ok(WatchedConnection {
+            inner: tx,
+            checkout,
+        })
+    }
+
+    async fn commit_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        let WatchedConnection { inner, checkout } = tx;
+        let result = C::commit_transaction(inner).await;
+        drop(checkout);
+        result
+    }
+
+    async fn abort_transaction(tx: Self::TransactionResult) -> Result<(), Self::Error> {
+        let WatchedConnection { inner, checkout } = tx;
+        let result = C::abort_transaction(inner).await;
+        drop(checkout);
+        result
+    }
+
+    async fn start_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::start_savepoint(&mut tx.inner).await
+    }
+
+    async fn release_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::release_savepoint(&mut tx.inner).await
+    }
+
+    async fn rollback_to_savepoint(tx: &mut Self::TransactionResult) -> Result<(), Self::Error> {
+        C::rollback_to_savepoint(&mut tx.inner).await
+    }
+
+    /// A transaction started in place of the savepoint is watched from when it started.
+    async fn start_nested(
+        tx: &mut Self::TransactionResult,
+    ) -> Result<Option<Self::TransactionResult>, Self::Error> {
+        let threshold = tx.checkout.threshold;
+        let nested = C::start_nested(&mut tx.inner).await?;
+        Ok(nested.map(|nested| WatchedConnection {
+            inner: nested,
+            checkout: Checkout {
+                at: Instant::now(),
+                threshold,
+                span: Span::current(),
+                backtrace: Backtrace::capture(),
+            },
+        }))
+    }
 }
 
 /// Utility for grouping actions together in a transaction.
@@ -68,19 +518,420 @@ pub trait Atomic: Sized {
 /// ```
 ///
 /// If any of the above create actions fail, none of them will leave any side effects.
+///
+/// Methods which can be called from within another transaction take the open transaction by mutable reference
+/// and use the `savepoint` variant. The block runs in a savepoint which is released if it succeeds and rolled
+/// back to if it fails, leaving the outer transaction open either way. `Connection` is the type implementing
+/// [Atomic], the reference is to its transaction result. With a [Conn] the caller does not need to know
+/// whether a transaction is open, on a plain one the block runs in its own transaction.
+///
+/// ```ignore
+/// async fn add_member(conn: &mut Conn<DatabaseConnection>, /* ... */) -> Result<Member, Error> {
+///     transaction!(
+///         savepoint conn: Conn<DatabaseConnection> => {
+///             insert_member(conn, /* ... */).await?;
+///             Ok(update_member_count(conn, /* ... */).await?)
+///         }
+///     )
+/// }
+/// ```
 #[macro_export]
 macro_rules! transaction {
+    (savepoint $conn:ident : $id:ty => $b:block) => {{
+        match <$id as $crate::Atomic>::start_nested($conn).await? {
+            None => match $b {
+                Ok(v) => match <$id as $crate::Atomic>::release_savepoint($conn).await {
+                    Ok(_) => Ok(v),
+                    Err(e) => Err(e),
+                },
+                Err(e) => match <$id as $crate::Atomic>::rollback_to_savepoint($conn).await {
+                    Ok(_) => Err(e),
+                    Err(er) => Err(er),
+                },
+            },
+            Some(mut tx) => {
+                let result = {
+                    let $conn = &mut tx;
+                    $b
+                };
+                match result {
+                    Ok(v) => match <$id as $crate::Atomic>::commit_transaction(tx).await {
+                        Ok(_) => Ok(v),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => match <$id as $crate::Atomic>::abort_transaction(tx).await {
+                        Ok(_) => Err(e),
+                        Err(er) => Err(er),
+                    },
+                }
+            }
+        }
+    }};
     ($conn:ident : $id:ident => $b:block) => {{
-        let mut $conn = <$id as hextacy::Atomic>::start_transaction($conn).await?;
+        let mut $conn = <$id as $crate::Atomic>::start_transaction($conn).await?;
         match $b {
-            Ok(v) => match <$id as hextacy::Atomic>::commit_transaction($conn).await {
+            Ok(v) => match <$id as $crate::Atomic>::commit_transaction($conn).await {
                 Ok(_) => Ok(v),
                 Err(e) => Err(e),
             },
-            Err(e) => match <$id as hextacy::Atomic>::abort_transaction($conn).await {
+            Err(e) => match <$id as $crate::Atomic>::abort_transaction($conn).await {
                 Ok(_) => Err(e),
                 Err(er) => Err(er),
             },
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Logs statements, transactions get their own type like with seaorm.
+    #[derive(Debug, Clone, Default)]
+    struct FakeConnection(Arc<Mutex<Vec<String>>>);
+
+    #[derive(Debug)]
+    struct FakeTransaction(FakeConnection);
+
+    trait Execute {
+        fn execute(&self, stmt: &str);
+    }
+
+    impl Execute for FakeConnection {
+        fn execute(&self, stmt: &str) {
+            self.0.lock().unwrap().push(stmt.to_string())
+        }
+    }
+
+    impl Execute for Conn<FakeConnection> {
+        fn execute(&self, stmt: &str) {
+            match self {
+                Conn::Plain(conn) => conn.execute(stmt),
+                Conn::Transaction(tx) => tx.0.execute(stmt),
+            }
+        }
+    }
+
+    impl Atomic for FakeConnection {
+        type TransactionResult = FakeTransaction;
+        type Error = ();
+
+        async fn start_transaction(self) -> Result<FakeTransaction, ()> {
+            self.execute("BEGIN");
+            Ok(FakeTransaction(self))
+        }
+
+        async fn commit_transaction(tx: FakeTransaction) -> Result<(), ()> {
+            tx.0.execute("COMMIT");
+            Ok(())
+        }
+
+        async fn abort_transaction(tx: FakeTransaction) -> Result<(), ()> {
+            tx.0.execute("ROLLBACK");
+            Ok(())
+        }
+
+        async fn start_savepoint(tx: &mut FakeTransaction) -> Result<(), ()> {
+            tx.0.execute("SAVEPOINT");
+            Ok(())
+        }
+
+        async fn release_savepoint(tx: &mut FakeTransaction) -> Result<(), ()> {
+            tx.0.execute("RELEASE");
+            Ok(())
+        }
+
+        async fn rollback_to_savepoint(tx: &mut FakeTransaction) -> Result<(), ()> {
+            tx.0.execute("ROLLBACK TO");
+            Ok(())
+        }
+    }
+
+    /// The adapter method, implemented once.
+    fn insert(conn: &impl Execute) {
+        conn.execute("INSERT")
+    }
+
+    #[tokio::test]
+    async fn conn_works_in_and_out_of_transactions() {
+        let fake = FakeConnection::default();
+
+        let conn = Conn::from(fake.clone());
+        insert(&conn);
+
+        let tx = conn.start_transaction().await.unwrap();
+        insert(&tx);
+
+        // Joins the open transaction
+        let tx = tx.start_transaction().await.unwrap();
+        insert(&tx);
+        Conn::commit_transaction(tx).await.unwrap();
+
+        let tx = Conn::from(fake.clone()).start_transaction().await.unwrap();
+        insert(&tx);
+        Conn::abort_transaction(tx).await.unwrap();
+
+        // No-ops
+        Conn::commit_transaction(Conn::from(fake.clone()))
+            .await
+            .unwrap();
+        Conn::abort_transaction(Conn::from(fake.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *fake.0.lock().unwrap(),
+            ["INSERT", "BEGIN", "INSERT", "INSERT", "COMMIT", "BEGIN", "INSERT", "ROLLBACK"]
+        );
+    }
+
+    async fn add_member(conn: &mut Conn<FakeConnection>, fail: bool) -> Result<(), ()> {
+        transaction!(
+            savepoint conn: Conn<FakeConnection> => {
+                insert(conn);
+                if fail {
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            }
+        )
+    }
+
+    #[tokio::test]
+    async fn savepoint_blocks_are_atomic_in_and_out_of_transactions() {
+        let fake = FakeConnection::default();
+
+        // Plain connections get their own transaction and stay plain
+        let mut conn = Conn::from(fake.clone());
+        add_member(&mut conn, false).await.unwrap();
+        add_member(&mut conn, true).await.unwrap_err();
+        assert!(!conn.is_transaction());
+
+        let mut tx = conn.start_transaction().await.unwrap();
+        add_member(&mut tx, false).await.unwrap();
+        add_member(&mut tx, true).await.unwrap_err();
+        Conn::abort_transaction(tx).await.unwrap();
+
+        assert_eq!(
+            *fake.0.lock().unwrap(),
+            [
+                "BEGIN",
+                "INSERT",
+                "COMMIT",
+                "BEGIN",
+                "INSERT",
+                "ROLLBACK",
+                "BEGIN",
+                "SAVEPOINT",
+                "INSERT",
+                "RELEASE",
+                "SAVEPOINT",
+                "INSERT",
+                "ROLLBACK TO",
+                "ROLLBACK"
+            ]
+        );
+    }
+
+    type Fields = Vec<(String, String)>;
+
+    /// Records the names and fields of created spans.
+    #[derive(Debug, Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<(String, Fields)>>>);
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{value:?}")))
+        }
+
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()))
+        }
+    }
+
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = vec![];
+            span.record(&mut FieldVisitor(&mut fields));
+            let mut spans = self.0.lock().unwrap();
+            spans.push((span.metadata().name().to_string(), fields));
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    struct FakeDriver(FakeConnection);
+
+    impl Driver for FakeDriver {
+        type Connection = FakeConnection;
+        type Error = ();
+
+        async fn connect(&self) -> Result<FakeConnection, ()> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transactions_always_roll_back() {
+        let fake = FakeConnection::default();
+        let driver = FakeDriver(fake.clone());
+
+        let inserted = crate::testing::with_test_transaction(&driver, |tx| async move {
+            insert(&tx.0);
+            (tx, Ok::<_, ()>(1))
+        })
+        .await;
+        assert_eq!(inserted, Ok(1));
+
+        assert_eq!(*fake.0.lock().unwrap(), ["BEGIN", "INSERT", "ROLLBACK"]);
+    }
+
+    #[test]
+    fn labels_appear_on_spans() {
+        let recorder = SpanRecorder::default();
+        let driver = Labeled::new("primary-pg", FakeDriver(FakeConnection::default()));
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            futures::executor::block_on(async {
+                let conn = driver.connect().await.unwrap();
+                async { insert(&*conn) }
+                    .instrument(conn.span("insert"))
+                    .await;
+
+                let tx = conn.start_transaction().await.unwrap();
+                assert_eq!(tx.label(), "primary-pg");
+                Labeled::<FakeConnection>::commit_transaction(tx)
+                    .await
+                    .unwrap();
+            })
+        });
+
+        let field = |name: &str, value: &str| (name.to_string(), value.to_string());
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                ("db.connect".to_string(), vec![field("db", "primary-pg")]),
+                (
+                    "db.query".to_string(),
+                    vec![field("db", "primary-pg"), field("query", "insert")]
+                ),
+            ]
+        );
+        assert_eq!(*driver.0 .0.lock().unwrap(), ["INSERT", "BEGIN", "COMMIT"]);
+    }
+
+    /// Records the fields of emitted events.
+    #[derive(Debug, Clone, Default)]
+    struct EventRecorder(Arc<Mutex<Vec<Fields>>>);
+
+    impl tracing::Subscriber for EventRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = vec![];
+            event.record(&mut FieldVisitor(&mut fields));
+            self.0.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn warns_when_connections_are_held_too_long() {
+        let recorder = EventRecorder::default();
+        let driver = Watched::new(
+            FakeDriver(FakeConnection::default()),
+            Duration::from_millis(50),
+        );
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            futures::executor::block_on(async {
+                let conn = driver.connect().await.unwrap();
+                insert(&*conn);
+                drop(conn);
+
+                let conn = driver.connect().await.unwrap();
+                let tx = conn.start_transaction().await.unwrap();
+                std::thread::sleep(Duration::from_millis(100));
+                WatchedConnection::<FakeConnection>::commit_transaction(tx)
+                    .await
+                    .unwrap();
+            })
+        });
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+
+        let field = |name: &str| {
+            events[0]
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert!(field("message")
+            .unwrap()
+            .contains("longer than the 50ms threshold"));
+        assert_eq!(field("threshold").as_deref(), Some("50ms"));
+        assert!(field("backtrace").is_some());
+    }
+
+    /// Fails its probe even though connections can be acquired, like a pool handing out stale connections.
+    struct UnreachableDriver;
+
+    impl Driver for UnreachableDriver {
+        type Connection = ();
+        type Error = &'static str;
+
+        async fn connect(&self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> Result<Duration, Self::Error> {
+            Err("connection refused")
+        }
+    }
+
+    #[tokio::test]
+    async fn health_checks_reach_the_wrapped_driver() {
+        let driver = Labeled::new("primary-pg", FakeDriver(FakeConnection::default()));
+        assert!(driver.health_check().await.unwrap() < Duration::from_secs(1));
+
+        let driver = Labeled::new("primary-pg", UnreachableDriver);
+        assert!(driver.connect().await.is_ok());
+        assert_eq!(driver.health_check().await, Err("connection refused"));
+
+        let driver = Watched::new(UnreachableDriver, Duration::from_secs(1));
+        assert_eq!(driver.health_check().await, Err("connection refused"));
+    }
+}
@@ -1,13 +1,43 @@
 use std::{
     collections::HashMap,
     env::{self, VarError},
+    fmt::Display,
+    str::FromStr,
 };
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EnvError {
+    #[error("Missing variable: {0}")]
+    Missing(String),
+    #[error("Variable {0} is not valid unicode")]
+    NotUnicode(String),
+    #[error("Invalid value for {0}: {1}")]
+    Parse(String, String),
+}
 
 /// Gets an environment variable for the given key
 pub fn get(key: &str) -> Result<String, VarError> {
     env::var(key)
 }
 
+/// Gets an environment variable for the given key and parses it to `T`
+pub fn get_parsed<T>(key: &str) -> Result<T, EnvError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = match env::var(key) {
+        Ok(value) => value,
+        Err(VarError::NotPresent) => return Err(EnvError::Missing(key.to_string())),
+        Err(VarError::NotUnicode(_)) => return Err(EnvError::NotUnicode(key.to_string())),
+    };
+    value
+        .trim()
+        .parse()
+        .map_err(|e: T::Err| EnvError::Parse(key.to_string(), e.to_string()))
+}
+
 /// Sets an environment variable to the given key and value
 pub fn set(key: &str, value: &str) {
     env::set_var(key, value)
@@ -52,3 +82,25 @@ pub fn get_or_default_multiple<'a>(keys: &'a [(&'a str, &str)]) -> HashMap<&'a s
 pub fn load_from_file(path: &str) -> Result<(), dotenv::Error> {
     dotenv::from_path(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsed() {
+        set("HEXTACY_ENV_PORT", "8080");
+        assert_eq!(get_parsed::<u16>("HEXTACY_ENV_PORT"), Ok(8080));
+
+        set("HEXTACY_ENV_INVALID_PORT", "http");
+        assert!(matches!(
+            get_parsed::<u16>("HEXTACY_ENV_INVALID_PORT"),
+            Err(EnvError::Parse(key, _)) if key == "HEXTACY_ENV_INVALID_PORT"
+        ));
+
+        assert_eq!(
+            get_parsed::<String>("HEXTACY_ENV_MISSING"),
+            Err(EnvError::Missing("HEXTACY_ENV_MISSING".to_string()))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 /// Core traits for implementing on data sources.
 mod driver;
 
-pub use driver::{Atomic, Driver};
+pub use driver::{Atomic, Conn, Driver, Labeled, Watched, WatchedConnection};
 
 /// Provides out of the box implementations for the [Driver][driver::Driver] trait.
 /// Re-exports the underlying libraries used for the implementation.
@@ -21,6 +21,9 @@ pub mod adapters;
 
 pub mod queue;
 
+/// Reporting partial failures of bulk operations.
+pub mod bulk;
+
 #[cfg(feature = "crypto")]
 /// Cryptographic utilities
 pub mod crypto;
@@ -28,6 +31,22 @@ pub mod crypto;
 /// Utilities for loading dotenv and grabbing stuff from the env.
 pub mod env;
 
+/// Backend agnostic metrics with Prometheus and StatsD sinks.
+pub mod metrics;
+
+/// Serde helpers for representing enums as integers in select contexts.
+pub mod repr;
+
+/// Retrying fallible operations within a process wide retry budget.
+pub mod retry;
+
+/// Sources for credentials drivers can be configured with.
+pub mod secrets;
+
+/// Helpers for testing repositories, enabled with the `test-utils` feature.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
 /// A logger that can be set up to use stdout or a file.
 pub mod logger;
 
@@ -42,6 +61,9 @@ pub mod web;
 #[cfg(feature = "web")]
 pub use hextacy_macros::RestResponse;
 
+#[cfg(any(feature = "cache-redis", feature = "cache-inmem"))]
+pub use hextacy_macros::CacheKeyable;
+
 /// Quality of life macros.
 pub use hextacy_macros::{component, contract, Constructor, State};
 
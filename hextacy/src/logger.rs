@@ -1,15 +1,32 @@
 use env_logger::fmt::Color;
-use log::{Level, LevelFilter};
+use log::{Level, LevelFilter, Log, Metadata, Record};
 use log4rs::{
     append::file::FileAppender,
     config::{Appender, Root},
     encode::pattern::PatternEncoder,
     Config,
 };
-use std::{env, io::Write};
+use std::{
+    env,
+    io::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 /// Errors and warns are always logged.
 pub fn init(level: &str) {
+    builder(level).init()
+}
+
+/// The same as [init], but only logs 1 in `rate` debug and trace records so high volume code paths
+/// do not flood the logs. Errors, warns and infos are always logged.
+pub fn init_sampled(level: &str, rate: u64) {
+    let logger = builder(level).build();
+    let max_level = logger.filter();
+    log::set_boxed_logger(Box::new(SampledLogger::new(logger, rate))).expect("Couldn't set logger");
+    log::set_max_level(max_level);
+}
+
+fn builder(level: &str) -> env_logger::Builder {
     // Set to trace since we filter out everything with our custom log level
     match level {
         "info" | "INFO" | "debug" | "DEBUG" | "trace" | "TRACE" | "error" | "ERROR" | "warn"
@@ -17,7 +34,8 @@ pub fn init(level: &str) {
         _ => env::set_var("RUST_LOG", "info"),
     };
 
-    env_logger::builder()
+    let mut builder = env_logger::Builder::from_default_env();
+    builder
         .format_timestamp_secs()
         .format_target(true)
         .format_suffix("\n")
@@ -39,8 +57,50 @@ pub fn init(level: &str) {
                 format_args!("{:^50}", record.target()),
                 record.args(),
             )
-        })
-        .init()
+        });
+    builder
+}
+
+/// Wraps a logger and passes through only 1 in `rate` debug and trace records.
+/// Records of any other level are always passed through.
+#[derive(Debug)]
+pub struct SampledLogger<L> {
+    inner: L,
+    rate: u64,
+    seen: AtomicU64,
+}
+
+impl<L> SampledLogger<L> {
+    /// A `rate` of 0 or 1 passes through everything.
+    pub fn new(inner: L, rate: u64) -> Self {
+        Self {
+            inner,
+            rate: rate.max(1),
+            seen: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<L: Log> Log for SampledLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() >= Level::Debug
+            && !self
+                .seen
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.rate)
+        {
+            return;
+        }
+        self.inner.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
 }
 
 /// Initiates a logger that logs to the provided file
@@ -69,3 +129,52 @@ pub fn init_file(level: &str, path: &str) {
 
     log4rs::init_config(config).expect("Couldn't load log4rs");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Debug, Default)]
+    struct Counting {
+        debug: AtomicUsize,
+        error: AtomicUsize,
+    }
+
+    impl Log for Counting {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            match record.level() {
+                Level::Error => self.error.fetch_add(1, Ordering::SeqCst),
+                _ => self.debug.fetch_add(1, Ordering::SeqCst),
+            };
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn log(logger: &impl Log, level: Level) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .args(format_args!("message"))
+                .build(),
+        )
+    }
+
+    #[test]
+    fn samples_debug_records() {
+        let logger = SampledLogger::new(Counting::default(), 10);
+
+        for _ in 0..1000 {
+            log(&logger, Level::Debug);
+            log(&logger, Level::Error);
+        }
+
+        assert_eq!(logger.inner.debug.load(Ordering::SeqCst), 100);
+        assert_eq!(logger.inner.error.load(Ordering::SeqCst), 1000);
+    }
+}
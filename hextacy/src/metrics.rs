@@ -0,0 +1,477 @@
+use crate::Driver;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    future::Future,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::net::UdpSocket;
+use tracing::warn;
+
+/// Label key value pairs attached to a metric.
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+/// A destination for metrics, chosen at startup so instrumented code does not depend on a backend.
+///
+/// Names are dot separated, e.g. `http.requests`; sinks convert them to whatever their backend expects.
+/// Recording a metric never fails, sinks are expected to log their errors instead.
+pub trait MetricsSink {
+    fn incr_counter(
+        &self,
+        name: &str,
+        labels: Labels<'_>,
+        value: u64,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn observe_histogram(
+        &self,
+        name: &str,
+        labels: Labels<'_>,
+        value: f64,
+    ) -> impl Future<Output = ()> + Send;
+
+    fn set_gauge(
+        &self,
+        name: &str,
+        labels: Labels<'_>,
+        value: f64,
+    ) -> impl Future<Output = ()> + Send;
+}
+
+/// Upper bounds of the histogram buckets, in seconds.
+pub const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    counters: BTreeMap<(String, String), u64>,
+    gauges: BTreeMap<(String, String), f64>,
+    histograms: BTreeMap<(String, String), Histogram>,
+}
+
+/// Keeps metrics in memory and [renders][PrometheusSink::render] them in the Prometheus text format,
+/// to be served on the endpoint Prometheus scrapes. Clones share metrics.
+#[derive(Debug, Clone)]
+pub struct PrometheusSink {
+    registry: Arc<Mutex<Registry>>,
+    buckets: Arc<[f64]>,
+}
+
+impl Default for PrometheusSink {
+    fn default() -> Self {
+        Self::new(&DEFAULT_BUCKETS)
+    }
+}
+
+impl PrometheusSink {
+    /// `buckets` are the upper bounds used for all histograms, in ascending order.
+    pub fn new(buckets: &[f64]) -> Self {
+        Self {
+            registry: Arc::default(),
+            buckets: buckets.into(),
+        }
+    }
+
+    /// Renders all recorded metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().expect("metrics lock poisoned");
+        let mut out = String::new();
+
+        let mut last = None;
+        for ((name, labels), value) in registry.counters.iter() {
+            if last != Some(name) {
+                let _ = writeln!(out, "# TYPE {name} counter");
+                last = Some(name);
+            }
+            let _ = writeln!(out, "{name}{} {value}", braced(labels));
+        }
+
+        let mut last = None;
+        for ((name, labels), value) in registry.gauges.iter() {
+            if last != Some(name) {
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                last = Some(name);
+            }
+            let _ = writeln!(out, "{name}{} {value}", braced(labels));
+        }
+
+        let mut last = None;
+        for ((name, labels), histogram) in registry.histograms.iter() {
+            if last != Some(name) {
+                let _ = writeln!(out, "# TYPE {name} histogram");
+                last = Some(name);
+            }
+            let sep = if labels.is_empty() { "" } else { "," };
+            for (bound, count) in self.buckets.iter().zip(histogram.buckets.iter()) {
+                let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {count}");
+            }
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(out, "{name}_sum{} {}", braced(labels), histogram.sum);
+            let _ = writeln!(out, "{name}_count{} {}", braced(labels), histogram.count);
+        }
+
+        out
+    }
+
+    fn key(name: &str, labels: Labels<'_>) -> (String, String) {
+        let name = name.replace(['.', '-'], "_");
+        let mut labels = labels
+            .iter()
+            .map(|(k, v)| format!("{k}=\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect::<Vec<_>>();
+        labels.sort();
+        (name, labels.join(","))
+    }
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    async fn incr_counter(&self, name: &str, labels: Labels<'_>, value: u64) {
+        let mut registry = self.registry.lock().expect("metrics lock poisoned");
+        *registry
+            .counters
+            .entry(Self::key(name, labels))
+            .or_default() += value;
+    }
+
+    async fn observe_histogram(&self, name: &str, labels: Labels<'_>, value: f64) {
+        let mut registry = self.registry.lock().expect("metrics lock poisoned");
+        let histogram = registry
+            .histograms
+            .entry(Self::key(name, labels))
+            .or_insert_with(|| Histogram {
+                buckets: vec![0; self.buckets.len()],
+                ..Default::default()
+            });
+        for (bound, count) in self.buckets.iter().zip(histogram.buckets.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    async fn set_gauge(&self, name: &str, labels: Labels<'_>, value: f64) {
+        let mut registry = self.registry.lock().expect("metrics lock poisoned");
+        registry.gauges.insert(Self::key(name, labels), value);
+    }
+}
+
+/// Sends metrics to a StatsD agent over UDP. Labels are sent as DogStatsD style tags.
+#[derive(Debug, Clone)]
+pub struct StatsdSink {
+    socket: Arc<UdpSocket>,
+    prefix: Option<String>,
+}
+
+impl StatsdSink {
+    /// Connect to the agent at `addr`. If given, `prefix` is prepended to all metric names.
+    pub async fn connect(addr: SocketAddr, prefix: Option<&str>) -> io::Result<Self> {
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(addr).await?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            prefix: prefix.map(ToString::to_string),
+        })
+    }
+
+    fn line(&self, name: &str, labels: Labels<'_>, value: &str, kind: &str) -> String {
+        let mut line = match self.prefix {
+            Some(ref prefix) => format!("{prefix}.{name}:{value}|{kind}"),
+            None => format!("{name}:{value}|{kind}"),
+        };
+        if !labels.is_empty() {
+            line.push_str("|#");
+            let tags = labels
+                .iter()
+                .map(|(k, v)| format!("{k}:{v}"))
+                .collect::<Vec<_>>();
+            line.push_str(&tags.join(","));
+        }
+        line
+    }
+
+    async fn send(&self, line: String) {
+        if let Err(e) = self.socket.send(line.as_bytes()).await {
+            warn!("Error occurred while sending metric: {e}");
+        }
+    }
+}
+
+impl MetricsSink for StatsdSink {
+    async fn incr_counter(&self, name: &str, labels: Labels<'_>, value: u64) {
+        self.send(self.line(name, labels, &value.to_string(), "c"))
+            .await
+    }
+
+    async fn observe_histogram(&self, name: &str, labels: Labels<'_>, value: f64) {
+        self.send(self.line(name, labels, &value.to_string(), "h"))
+            .await
+    }
+
+    async fn set_gauge(&self, name: &str, labels: Labels<'_>, value: f64) {
+        self.send(self.line(name, labels, &value.to_string(), "g"))
+            .await
+    }
+}
+
+/// Records a handled HTTP request as an `http.requests` counter and an `http.request.duration` histogram,
+/// both labeled with the method and status. Intended to be called from request middleware.
+#[cfg(feature = "web")]
+pub async fn record_request<S: MetricsSink>(
+    sink: &S,
+    method: &http::Method,
+    status: http::StatusCode,
+    elapsed: Duration,
+) {
+    let labels = [("method", method.as_str()), ("status", status.as_str())];
+    sink.incr_counter("http.requests", &labels, 1).await;
+    sink.observe_histogram("http.request.duration", &labels, elapsed.as_secs_f64())
+        .await;
+}
+
+/// Wraps a driver and records its connection attempts as a `db.connections` counter labeled with the outcome
+/// and a `db.connect.duration` histogram. Both are labeled with the driver's `db` label.
+#[derive(Debug, Clone)]
+pub struct Metered<D, S> {
+    label: &'static str,
+    inner: D,
+    sink: S,
+}
+
+impl<D, S> Metered<D, S> {
+    pub fn new(label: &'static str, inner: D, sink: S) -> Self {
+        Self { label, inner, sink }
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D, S> Driver for Metered<D, S>
+where
+    D: Driver,
+    S: MetricsSink,
+{
+    type Connection = D::Connection;
+    type Error = D::Error;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let start = Instant::now();
+        let result = self.inner.connect().await;
+        let elapsed = start.elapsed();
+
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        self.sink
+            .incr_counter(
+                "db.connections",
+                &[("db", self.label), ("outcome", outcome)],
+                1,
+            )
+            .await;
+        self.sink
+            .observe_histogram(
+                "db.connect.duration",
+                &[("db", self.label)],
+                elapsed.as_secs_f64(),
+            )
+            .await;
+
+        result
+    }
+
+    async fn health_check(&self) -> Result<Duration, Self::Error> {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(kind, name, labels)`
+    type Call = (&'static str, String, Vec<(String, String)>);
+
+    #[derive(Debug, Clone, Default)]
+    struct Recording(Arc<Mutex<Vec<Call>>>);
+
+    impl Recording {
+        fn record(&self, kind: &'static str, name: &str, labels: Labels<'_>) {
+            let labels = labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            self.0
+                .lock()
+                .unwrap()
+                .push((kind, name.to_string(), labels));
+        }
+
+        fn names(&self) -> Vec<(&'static str, String)> {
+            self.0
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(kind, name, _)| (*kind, name.clone()))
+                .collect()
+        }
+    }
+
+    impl MetricsSink for Recording {
+        async fn incr_counter(&self, name: &str, labels: Labels<'_>, _: u64) {
+            self.record("counter", name, labels)
+        }
+
+        async fn observe_histogram(&self, name: &str, labels: Labels<'_>, _: f64) {
+            self.record("histogram", name, labels)
+        }
+
+        async fn set_gauge(&self, name: &str, labels: Labels<'_>, _: f64) {
+            self.record("gauge", name, labels)
+        }
+    }
+
+    struct FakeDriver(bool);
+
+    impl Driver for FakeDriver {
+        type Connection = ();
+        type Error = ();
+
+        async fn connect(&self) -> Result<(), ()> {
+            if self.0 {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn driver_emits_connection_metrics() {
+        let sink = Recording::default();
+
+        let driver = Metered::new("users", FakeDriver(true), sink.clone());
+        driver.connect().await.unwrap();
+        let driver = Metered::new("users", FakeDriver(false), sink.clone());
+        driver.connect().await.unwrap_err();
+
+        assert_eq!(
+            sink.names(),
+            [
+                ("counter", "db.connections".to_string()),
+                ("histogram", "db.connect.duration".to_string()),
+                ("counter", "db.connections".to_string()),
+                ("histogram", "db.connect.duration".to_string()),
+            ]
+        );
+
+        let calls = sink.0.lock().unwrap();
+        let outcome = |i: usize| calls[i].2[1].1.clone();
+        assert_eq!(outcome(0), "ok");
+        assert_eq!(outcome(2), "error");
+    }
+
+    #[cfg(feature = "web")]
+    #[tokio::test]
+    async fn request_metrics() {
+        let sink = Recording::default();
+        record_request(
+            &sink,
+            &http::Method::GET,
+            http::StatusCode::OK,
+            Duration::from_millis(5),
+        )
+        .await;
+
+        assert_eq!(
+            sink.names(),
+            [
+                ("counter", "http.requests".to_string()),
+                ("histogram", "http.request.duration".to_string()),
+            ]
+        );
+        assert_eq!(
+            sink.0.lock().unwrap()[0].2,
+            [
+                ("method".to_string(), "GET".to_string()),
+                ("status".to_string(), "200".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn prometheus_rendering() {
+        let sink = PrometheusSink::new(&[0.1, 1.0]);
+
+        sink.incr_counter("http.requests", &[("status", "200")], 1)
+            .await;
+        sink.incr_counter("http.requests", &[("status", "200")], 2)
+            .await;
+        sink.set_gauge("db.pool.idle", &[], 4.0).await;
+        sink.observe_histogram("http.request.duration", &[], 0.05)
+            .await;
+        sink.observe_histogram("http.request.duration", &[], 0.5)
+            .await;
+
+        assert_eq!(
+            sink.render(),
+            "# TYPE http_requests counter\n\
+             http_requests{status=\"200\"} 3\n\
+             # TYPE db_pool_idle gauge\n\
+             db_pool_idle 4\n\
+             # TYPE http_request_duration histogram\n\
+             http_request_duration_bucket{le=\"0.1\"} 1\n\
+             http_request_duration_bucket{le=\"1\"} 2\n\
+             http_request_duration_bucket{le=\"+Inf\"} 2\n\
+             http_request_duration_sum 0.55\n\
+             http_request_duration_count 2\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn statsd_lines() {
+        let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sink = StatsdSink::connect(agent.local_addr().unwrap(), Some("app"))
+            .await
+            .unwrap();
+
+        sink.incr_counter("http.requests", &[("status", "200")], 1)
+            .await;
+        sink.set_gauge("db.pool.idle", &[], 4.0).await;
+
+        let mut buf = [0; 128];
+        let n = agent.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"app.http.requests:1|c|#status:200");
+        let n = agent.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"app.db.pool.idle:4|g");
+    }
+}
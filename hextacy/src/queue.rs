@@ -4,10 +4,19 @@
 //! The traits are designed to work on enums, meaning you want to implement the [QueueHandler]
 //! with the `M` as an enum.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 use std::{fmt::Display, marker::PhantomData};
+use tokio::sync::mpsc::{self, error::TrySendError};
 use tokio::sync::oneshot::{self, Receiver, Sender};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 use tracing::{debug, error, warn};
 
 /// Implement on structs that need to handle messages.
@@ -29,6 +38,594 @@ pub trait Producer {
         M: Serialize + Send + Sync + 'static;
 }
 
+/// Wraps a [Producer] and bounds the amount of messages that can be in flight at any given time so that
+/// a flood of messages cannot back up memory.
+///
+/// [publish][Producer::publish] waits for a free slot, while [try_publish][BoundedProducer::try_publish]
+/// returns [QueueError::Full] immediately if there are none, allowing callers to shed load.
+#[derive(Debug, Clone)]
+pub struct BoundedProducer<P> {
+    producer: P,
+    permits: Arc<Semaphore>,
+    metrics: Arc<ProducerMetrics>,
+}
+
+impl<P> BoundedProducer<P>
+where
+    P: Producer,
+{
+    pub fn new(producer: P, capacity: usize) -> Self {
+        Self {
+            producer,
+            permits: Arc::new(Semaphore::new(capacity)),
+            metrics: Arc::new(ProducerMetrics::default()),
+        }
+    }
+
+    /// Publish the message if there is room for it, otherwise return [QueueError::Full] without waiting.
+    pub async fn try_publish<M>(&self, message: M) -> Result<(), QueueError>
+    where
+        M: Serialize + Send + Sync + 'static,
+    {
+        let Ok(_permit) = self.permits.try_acquire() else {
+            self.metrics.shed.fetch_add(1, Ordering::Relaxed);
+            return Err(QueueError::Full);
+        };
+        self.forward(message).await
+    }
+
+    /// The amount of messages that can currently be published without waiting.
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    pub fn metrics(&self) -> &ProducerMetrics {
+        &self.metrics
+    }
+
+    async fn forward<M>(&self, message: M) -> Result<(), QueueError>
+    where
+        M: Serialize + Send + Sync + 'static,
+    {
+        let result = self.producer.publish(message).await;
+        let counter = match result {
+            Ok(_) => &self.metrics.published,
+            Err(_) => &self.metrics.failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<P> Producer for BoundedProducer<P>
+where
+    P: Producer,
+{
+    async fn publish<M>(&self, message: M) -> Result<(), QueueError>
+    where
+        M: Serialize + Send + Sync + 'static,
+    {
+        let Ok(_permit) = self.permits.acquire().await else {
+            return Err(QueueError::Full);
+        };
+        self.forward(message).await
+    }
+}
+
+/// Counters for messages going through a [BoundedProducer].
+#[derive(Debug, Default)]
+pub struct ProducerMetrics {
+    published: AtomicU64,
+    failed: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl ProducerMetrics {
+    /// Messages successfully published.
+    pub fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// Messages the underlying producer failed to publish.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Messages dropped because the producer was at capacity.
+    pub fn shed(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+}
+
+/// Fans messages out to in-process subscribers, e.g. websocket sessions, each through its own bounded buffer.
+///
+/// Broadcasting never waits on subscribers. A subscriber that falls `buffer` messages behind misses new messages
+/// until it catches up, and the drops are counted in its [dropped][Subscriber::dropped] and the
+/// broadcast's [metrics][Broadcast::metrics]. Fast subscribers are therefore never held back by slow ones.
+///
+/// Subscribers implement [Consumer] and are removed once dropped.
+#[derive(Debug)]
+pub struct Broadcast<M> {
+    buffer: usize,
+    subscribers: Arc<Mutex<Vec<Subscription<M>>>>,
+    metrics: Arc<BroadcastMetrics>,
+}
+
+impl<M> Clone for Broadcast<M> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            subscribers: self.subscribers.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Decides in the broker whether a message is delivered to a subscription.
+type Filter<M> = Arc<dyn Fn(&M) -> bool + Send + Sync>;
+
+struct Subscription<M> {
+    tx: mpsc::Sender<M>,
+    dropped: Arc<AtomicU64>,
+    filter: Option<Filter<M>>,
+}
+
+impl<M> Clone for Subscription<M> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            dropped: self.dropped.clone(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<M> std::fmt::Debug for Subscription<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("dropped", &self.dropped)
+            .field("filtered", &self.filter.is_some())
+            .finish()
+    }
+}
+
+impl<M> Subscription<M> {
+    fn accepts(&self, message: &M) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter(message))
+    }
+}
+
+impl<M> Broadcast<M>
+where
+    M: Clone,
+{
+    /// `buffer` is the amount of messages each subscriber can fall behind before messages are dropped for it.
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            buffer: buffer.max(1),
+            subscribers: Arc::default(),
+            metrics: Arc::default(),
+        }
+    }
+
+    pub fn subscribe(&self) -> Subscriber<M> {
+        self.register(None)
+    }
+
+    /// Subscribe only to messages matching the predicate. The predicate runs in the broker on a borrow of
+    /// the message before it is cloned, so non-matching messages never reach the subscriber's buffer,
+    /// wake it up or count towards its drops.
+    pub fn subscribe_filtered(
+        &self,
+        predicate: impl Fn(&M) -> bool + Send + Sync + 'static,
+    ) -> Subscriber<M> {
+        self.register(Some(Arc::new(predicate)))
+    }
+
+    fn register(&self, filter: Option<Filter<M>>) -> Subscriber<M> {
+        let (tx, rx) = mpsc::channel(self.buffer);
+        let dropped = Arc::new(AtomicU64::new(0));
+        self.subscribers
+            .lock()
+            .expect("broadcast lock poisoned")
+            .push(Subscription {
+                tx,
+                dropped: dropped.clone(),
+                filter,
+            });
+        Subscriber { rx, dropped }
+    }
+
+    /// Deliver the message to every subscriber with room in its buffer. Returns the amount of subscribers
+    /// it was delivered to.
+    pub fn broadcast(&self, message: M) -> usize {
+        let mut subscribers = self.subscribers.lock().expect("broadcast lock poisoned");
+        fan_out(&mut subscribers, &message, &self.metrics)
+    }
+
+    /// The amount of subscribers as of the last broadcast or reap.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers
+            .lock()
+            .expect("broadcast lock poisoned")
+            .len()
+    }
+
+    /// Remove dropped subscribers. Returns how many were removed.
+    ///
+    /// Broadcasting removes them as well, so this is only needed when broadcasts are infrequent.
+    pub fn reap(&self) -> usize {
+        reap_subscriptions(&mut self.subscribers.lock().expect("broadcast lock poisoned"))
+    }
+
+    /// [Reap][Broadcast::reap] dropped subscribers every `interval`. The task stops once every
+    /// handle to the broadcast is dropped.
+    pub fn spawn_reaper(&self, interval: Duration) -> JoinHandle<()>
+    where
+        M: Send + 'static,
+    {
+        spawn_reaper(
+            Arc::downgrade(&self.subscribers),
+            interval,
+            reap_subscriptions,
+        )
+    }
+
+    pub fn metrics(&self) -> &BroadcastMetrics {
+        &self.metrics
+    }
+}
+
+/// Counters for messages going through a [Broadcast], summed over all subscribers.
+#[derive(Debug, Default)]
+pub struct BroadcastMetrics {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl BroadcastMetrics {
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Messages not delivered because the subscriber's buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Receiving end of a [Broadcast].
+#[derive(Debug)]
+pub struct Subscriber<M> {
+    rx: mpsc::Receiver<M>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<M> Subscriber<M> {
+    /// Messages this subscriber missed because it fell too far behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<M> Consumer<M> for Subscriber<M>
+where
+    M: Send + 'static,
+{
+    async fn poll_queue(&mut self) -> Result<Option<M>, QueueError> {
+        Ok(self.rx.recv().await)
+    }
+}
+
+/// Sends the message to every subscription accepting it with room in its buffer, removing closed ones.
+fn fan_out<M: Clone>(
+    subscribers: &mut Vec<Subscription<M>>,
+    message: &M,
+    metrics: &BroadcastMetrics,
+) -> usize {
+    let mut delivered = 0;
+
+    subscribers.retain(|sub| {
+        if !sub.accepts(message) {
+            return !sub.tx.is_closed();
+        }
+        match sub.tx.try_send(message.clone()) {
+            Ok(_) => {
+                delivered += 1;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                sub.dropped.fetch_add(1, Ordering::Relaxed);
+                metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    });
+
+    metrics
+        .delivered
+        .fetch_add(delivered as u64, Ordering::Relaxed);
+    delivered
+}
+
+/// A [Broadcast] partitioned by topic. Subscribers receive messages from all the topics they subscribed to
+/// through a single buffer, tagged with the topic they were published to.
+#[derive(Debug)]
+pub struct Topics<M> {
+    buffer: usize,
+    topics: Arc<Mutex<TopicRegistry<M>>>,
+    metrics: Arc<BroadcastMetrics>,
+}
+
+impl<M> Clone for Topics<M> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer,
+            topics: self.topics.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+type TopicRegistry<M> = HashMap<String, Vec<Subscription<TopicMessage<M>>>>;
+
+/// A message received through [Topics].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicMessage<M> {
+    pub topic: String,
+    pub message: M,
+}
+
+impl<M> Topics<M>
+where
+    M: Clone,
+{
+    /// `buffer` is the amount of messages each subscriber can fall behind, across all of its topics,
+    /// before messages are dropped for it.
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            buffer: buffer.max(1),
+            topics: Arc::default(),
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Subscribe to all the topics at once. The topics are registered under a single lock, so no publish
+    /// can observe the subscriber on some of the topics but not the others.
+    pub fn subscribe_many<T: AsRef<str>>(&self, topics: &[T]) -> Subscriber<TopicMessage<M>> {
+        self.register(topics, None)
+    }
+
+    /// Subscribe to all the topics at once, receiving only the messages matching the predicate.
+    /// See [Broadcast::subscribe_filtered].
+    pub fn subscribe_filtered<T: AsRef<str>>(
+        &self,
+        topics: &[T],
+        predicate: impl Fn(&M) -> bool + Send + Sync + 'static,
+    ) -> Subscriber<TopicMessage<M>> {
+        self.register(
+            topics,
+            Some(Arc::new(move |m: &TopicMessage<M>| predicate(&m.message))),
+        )
+    }
+
+    fn register<T: AsRef<str>>(
+        &self,
+        topics: &[T],
+        filter: Option<Filter<TopicMessage<M>>>,
+    ) -> Subscriber<TopicMessage<M>> {
+        let (tx, rx) = mpsc::channel(self.buffer);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let subscription = Subscription {
+            tx,
+            dropped: dropped.clone(),
+            filter,
+        };
+
+        let mut registry = self.topics.lock().expect("topics lock poisoned");
+        for topic in topics {
+            let subscribers = registry.entry(topic.as_ref().to_string()).or_default();
+            // Subscribing to the same topic twice would deliver its messages twice
+            if !subscribers
+                .iter()
+                .any(|sub| sub.tx.same_channel(&subscription.tx))
+            {
+                subscribers.push(subscription.clone());
+            }
+        }
+
+        Subscriber { rx, dropped }
+    }
+
+    pub fn subscribe(&self, topic: &str) -> Subscriber<TopicMessage<M>> {
+        self.subscribe_many(&[topic])
+    }
+
+    /// Deliver the message to every subscriber of the topic with room in its buffer. Returns the amount of
+    /// subscribers it was delivered to.
+    pub fn publish(&self, topic: &str, message: M) -> usize {
+        let mut registry = self.topics.lock().expect("topics lock poisoned");
+        let Some(subscribers) = registry.get_mut(topic) else {
+            return 0;
+        };
+
+        let message = TopicMessage {
+            topic: topic.to_string(),
+            message,
+        };
+        let delivered = fan_out(subscribers, &message, &self.metrics);
+
+        if subscribers.is_empty() {
+            registry.remove(topic);
+        }
+        delivered
+    }
+
+    /// The amount of subscribers of the topic as of the last publish to it or reap.
+    pub fn subscribers(&self, topic: &str) -> usize {
+        self.topics
+            .lock()
+            .expect("topics lock poisoned")
+            .get(topic)
+            .map_or(0, Vec::len)
+    }
+
+    /// A snapshot of every topic and the amount of its live subscribers. Topics without any are left out.
+    pub fn topology(&self) -> HashMap<String, usize> {
+        self.topics
+            .lock()
+            .expect("topics lock poisoned")
+            .iter()
+            .map(|(topic, subscribers)| {
+                let live = subscribers.iter().filter(|sub| !sub.tx.is_closed()).count();
+                (topic.clone(), live)
+            })
+            .filter(|(_, live)| *live > 0)
+            .collect()
+    }
+
+    /// The amount of live subscriptions across all topics. Subscribers of multiple topics are
+    /// counted once per topic.
+    pub fn total_subscribers(&self) -> usize {
+        self.topics
+            .lock()
+            .expect("topics lock poisoned")
+            .values()
+            .flatten()
+            .filter(|sub| !sub.tx.is_closed())
+            .count()
+    }
+
+    /// Remove dropped subscribers from every topic. Returns how many subscriptions were removed.
+    pub fn reap(&self) -> usize {
+        reap_topics(&mut self.topics.lock().expect("topics lock poisoned"))
+    }
+
+    /// [Reap][Topics::reap] dropped subscribers every `interval`. The task stops once every
+    /// handle to the topics is dropped.
+    pub fn spawn_reaper(&self, interval: Duration) -> JoinHandle<()>
+    where
+        M: Send + 'static,
+    {
+        spawn_reaper(Arc::downgrade(&self.topics), interval, reap_topics)
+    }
+
+    pub fn metrics(&self) -> &BroadcastMetrics {
+        &self.metrics
+    }
+}
+
+fn reap_subscriptions<M>(subscribers: &mut Vec<Subscription<M>>) -> usize {
+    let before = subscribers.len();
+    subscribers.retain(|sub| !sub.tx.is_closed());
+    before - subscribers.len()
+}
+
+fn reap_topics<M>(registry: &mut TopicRegistry<M>) -> usize {
+    let mut reaped = 0;
+    registry.retain(|_, subscribers| {
+        reaped += reap_subscriptions(subscribers);
+        !subscribers.is_empty()
+    });
+    reaped
+}
+
+fn spawn_reaper<T>(
+    registry: Weak<Mutex<T>>,
+    interval: Duration,
+    reap: fn(&mut T) -> usize,
+) -> JoinHandle<()>
+where
+    T: Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let Some(registry) = registry.upgrade() else {
+                return;
+            };
+            let reaped = reap(&mut registry.lock().expect("subscriber lock poisoned"));
+            if reaped > 0 {
+                debug!("Reaped {reaped} dropped subscribers");
+            }
+        }
+    })
+}
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// Run the future with the correlation ID set, e.g. to the ID of the request being handled.
+/// Messages published through a [CorrelatingProducer] within the future carry it.
+pub async fn with_correlation_id<F>(id: impl Into<String>, fut: F) -> F::Output
+where
+    F: Future,
+{
+    CORRELATION_ID.scope(id.into(), fut).await
+}
+
+/// The correlation ID of the current task, if it runs within [with_correlation_id].
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(Clone::clone).ok()
+}
+
+/// A message together with the correlation ID of whatever caused it to be published,
+/// so the work it triggers can be traced back to it.
+///
+/// Consumers of messages published by a [CorrelatingProducer] should poll for `Correlated<M>` and
+/// handle the message within [scope][Correlated::scope], so any messages they publish in turn carry the same ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Correlated<M> {
+    pub correlation_id: Option<String>,
+    pub message: M,
+}
+
+impl<M> Correlated<M> {
+    /// Run the future with this message's correlation ID set, if any.
+    pub async fn scope<F>(&self, fut: F) -> F::Output
+    where
+        F: Future,
+    {
+        match self.correlation_id {
+            Some(ref id) => with_correlation_id(id.clone(), fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+/// Wraps a [Producer] and publishes messages as [Correlated] with the [current correlation ID][correlation_id].
+#[derive(Debug, Clone)]
+pub struct CorrelatingProducer<P> {
+    producer: P,
+}
+
+impl<P> CorrelatingProducer<P> {
+    pub fn new(producer: P) -> Self {
+        Self { producer }
+    }
+}
+
+impl<P> Producer for CorrelatingProducer<P>
+where
+    P: Producer,
+{
+    async fn publish<M>(&self, message: M) -> Result<(), QueueError>
+    where
+        M: Serialize + Send + Sync + 'static,
+    {
+        self.producer
+            .publish(Correlated {
+                correlation_id: correlation_id(),
+                message,
+            })
+            .await
+    }
+}
+
 /// Implemented on concrete queue consumers. Check out the `adapters` module for
 /// concrete implementations.
 pub trait Consumer<M>: Sized + Send + 'static
@@ -116,6 +713,8 @@ where
 pub enum QueueError {
     Serde(serde_json::Error),
     Driver(Box<dyn Error + Send>),
+    /// Returned when a [BoundedProducer] has no room for more messages.
+    Full,
 }
 
 impl Display for QueueError {
@@ -123,6 +722,7 @@ impl Display for QueueError {
         match self {
             QueueError::Serde(e) => write!(f, "{e}"),
             QueueError::Driver(e) => write!(f, "{e}"),
+            QueueError::Full => write!(f, "Queue at capacity"),
         }
     }
 }
@@ -132,3 +732,284 @@ impl From<serde_json::Error> for QueueError {
         Self::Serde(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, poll};
+    use std::pin::pin;
+    use std::sync::atomic::AtomicBool;
+    use tokio::sync::Notify;
+
+    /// Does not complete publishing until opened.
+    #[derive(Debug, Default)]
+    struct Stalled {
+        open: AtomicBool,
+        notify: Notify,
+    }
+
+    impl Producer for Arc<Stalled> {
+        async fn publish<M>(&self, _: M) -> Result<(), QueueError>
+        where
+            M: Serialize + Send + Sync + 'static,
+        {
+            if !self.open.load(Ordering::SeqCst) {
+                self.notify.notified().await;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bounded_producer_sheds_when_full() {
+        let stalled = Arc::new(Stalled::default());
+        let producer = BoundedProducer::new(stalled.clone(), 2);
+
+        block_on(async {
+            let mut first = pin!(producer.publish(1));
+            let mut second = pin!(producer.publish(2));
+
+            assert!(poll!(first.as_mut()).is_pending());
+            assert!(poll!(second.as_mut()).is_pending());
+            assert_eq!(producer.available(), 0);
+
+            assert!(matches!(
+                producer.try_publish(3).await,
+                Err(QueueError::Full)
+            ));
+            assert_eq!(producer.metrics().shed(), 1);
+
+            stalled.open.store(true, Ordering::SeqCst);
+            stalled.notify.notify_waiters();
+
+            first.await.unwrap();
+            second.await.unwrap();
+
+            producer.try_publish(4).await.unwrap();
+        });
+
+        assert_eq!(producer.available(), 2);
+        assert_eq!(producer.metrics().published(), 3);
+        assert_eq!(producer.metrics().failed(), 0);
+        assert_eq!(producer.metrics().shed(), 1);
+    }
+
+    #[tokio::test]
+    async fn slow_subscribers_do_not_hold_back_fast_ones() {
+        let broadcast = Broadcast::new(4);
+        let mut fast = broadcast.subscribe();
+        let slow = broadcast.subscribe();
+
+        for i in 0..100 {
+            broadcast.broadcast(i);
+            assert_eq!(fast.poll_queue().await.unwrap(), Some(i));
+        }
+
+        assert_eq!(fast.dropped(), 0);
+        assert_eq!(slow.dropped(), 96);
+        assert_eq!(broadcast.metrics().delivered(), 104);
+        assert_eq!(broadcast.metrics().dropped(), 96);
+
+        // The slow subscriber gets what fit in its buffer
+        let mut slow = slow;
+        for i in 0..4 {
+            assert_eq!(slow.poll_queue().await.unwrap(), Some(i));
+        }
+
+        drop(slow);
+        assert_eq!(broadcast.broadcast(100), 1);
+        assert_eq!(broadcast.subscribers(), 1);
+    }
+
+    #[tokio::test]
+    async fn filtered_subscribers_only_receive_matches() {
+        let broadcast = Broadcast::new(4);
+        let mut even = broadcast.subscribe_filtered(|i: &u32| i.is_multiple_of(2));
+        let mut all = broadcast.subscribe();
+
+        for i in 0..8 {
+            broadcast.broadcast(i);
+            assert_eq!(all.poll_queue().await.unwrap(), Some(i));
+        }
+
+        // Only the 4 matches were buffered, so none were dropped
+        for i in [0, 2, 4, 6] {
+            assert_eq!(even.poll_queue().await.unwrap(), Some(i));
+        }
+        assert!(even.rx.try_recv().is_err());
+        assert_eq!(even.dropped(), 0);
+        assert_eq!(broadcast.metrics().delivered(), 12);
+
+        let topics = Topics::new(4);
+        let mut large =
+            topics.subscribe_filtered(&["orders", "refunds"], |amount: &u32| *amount > 100);
+        topics.publish("orders", 50);
+        topics.publish("refunds", 150);
+        topics.publish("orders", 500);
+
+        let TopicMessage { topic, message } = large.poll_queue().await.unwrap().unwrap();
+        assert_eq!((topic.as_str(), message), ("refunds", 150));
+        let TopicMessage { topic, message } = large.poll_queue().await.unwrap().unwrap();
+        assert_eq!((topic.as_str(), message), ("orders", 500));
+        assert!(large.rx.try_recv().is_err());
+
+        // Dropped filtered subscribers are still removed when a message doesn't match them
+        drop(even);
+        assert_eq!(broadcast.broadcast(1), 1);
+        assert_eq!(broadcast.subscribers(), 1);
+    }
+
+    #[tokio::test]
+    async fn subscribing_to_many_topics_misses_nothing() {
+        const TOPICS: [&str; 3] = ["users", "sessions", "emails"];
+        const MESSAGES: usize = 30_000;
+
+        let topics = Topics::new(MESSAGES);
+
+        // Publish a global sequence round robin across the topics while the subscription is made
+        let published = Arc::new(AtomicU64::new(0));
+        let publisher = std::thread::spawn({
+            let topics = topics.clone();
+            let published = published.clone();
+            move || {
+                for i in 0..MESSAGES {
+                    topics.publish(TOPICS[i % TOPICS.len()], i);
+                    published.store(i as u64, Ordering::Relaxed);
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        while published.load(Ordering::Relaxed) < 1000 {
+            std::thread::yield_now();
+        }
+        let mut subscriber = topics.subscribe_many(&TOPICS);
+        publisher.join().unwrap();
+        drop(topics);
+
+        let mut received = vec![];
+        while let Some(TopicMessage { topic, message }) = subscriber.poll_queue().await.unwrap() {
+            assert_eq!(topic, TOPICS[message % TOPICS.len()]);
+            received.push(message);
+        }
+
+        // Once the first message arrived, every later one on every topic must arrive too
+        let first = received.first().copied().unwrap_or(MESSAGES);
+        assert!(first < MESSAGES, "subscribed after the publisher finished");
+        assert_eq!(received, (first..MESSAGES).collect::<Vec<_>>());
+        assert_eq!(subscriber.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn topology_counts_live_subscribers() {
+        let topics = Topics::<u8>::new(4);
+        assert!(topics.topology().is_empty());
+
+        let _users = topics.subscribe_many(&["users", "sessions"]);
+        let _also_users = topics.subscribe("users");
+        let _billing = topics.subscribe("billing");
+        let gone = topics.subscribe("audit");
+        drop(gone);
+
+        let topology = topics.topology();
+        assert_eq!(
+            topology,
+            HashMap::from([
+                ("users".to_string(), 2),
+                ("sessions".to_string(), 1),
+                ("billing".to_string(), 1),
+            ])
+        );
+        assert_eq!(topics.total_subscribers(), 4);
+    }
+
+    #[tokio::test]
+    async fn reaper_prunes_dropped_subscribers() {
+        let broadcast = Broadcast::<u8>::new(4);
+        let topics = Topics::<u8>::new(4);
+        let broadcast_reaper = broadcast.spawn_reaper(Duration::from_millis(10));
+        let topics_reaper = topics.spawn_reaper(Duration::from_millis(10));
+
+        let alive = broadcast.subscribe();
+        let dead = broadcast.subscribe();
+        let subscribed = topics.subscribe_many(&["users", "sessions"]);
+        let _also_subscribed = topics.subscribe("users");
+        assert_eq!(broadcast.subscribers(), 2);
+        assert_eq!(topics.subscribers("users"), 2);
+
+        drop(dead);
+        drop(subscribed);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Nothing was published, the reaper pruned them
+        assert_eq!(broadcast.subscribers(), 1);
+        assert_eq!(topics.subscribers("users"), 1);
+        assert_eq!(topics.subscribers("sessions"), 0);
+        assert_eq!(broadcast.reap(), 0);
+
+        // Reapers stop once there is nothing left to reap
+        drop(alive);
+        drop(broadcast);
+        drop(topics);
+        tokio::time::timeout(Duration::from_secs(1), broadcast_reaper)
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), topics_reaper)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    /// Sends messages serialized to JSON to a channel.
+    #[derive(Debug, Clone)]
+    struct ChannelProducer(mpsc::UnboundedSender<String>);
+
+    impl Producer for ChannelProducer {
+        async fn publish<M>(&self, message: M) -> Result<(), QueueError>
+        where
+            M: Serialize + Send + Sync + 'static,
+        {
+            self.0.send(serde_json::to_string(&message)?).unwrap();
+            Ok(())
+        }
+    }
+
+    struct ChannelConsumer(mpsc::UnboundedReceiver<String>);
+
+    impl<M> Consumer<M> for ChannelConsumer
+    where
+        M: serde::de::DeserializeOwned + Send + 'static,
+    {
+        async fn poll_queue(&mut self) -> Result<Option<M>, QueueError> {
+            match self.0.recv().await {
+                Some(message) => Ok(Some(serde_json::from_str(&message)?)),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn correlation_id_reaches_subscribers() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let producer = CorrelatingProducer::new(ChannelProducer(tx));
+        let mut consumer = ChannelConsumer(rx);
+
+        with_correlation_id("request-1", producer.publish("user registered"))
+            .await
+            .unwrap();
+        producer.publish("uncorrelated").await.unwrap();
+
+        let received: Correlated<String> = consumer.poll_queue().await.unwrap().unwrap();
+        assert_eq!(received.correlation_id.as_deref(), Some("request-1"));
+        assert_eq!(received.message, "user registered");
+
+        // Work triggered by the message runs with the same id
+        let id = received.scope(async { correlation_id() }).await;
+        assert_eq!(id.as_deref(), Some("request-1"));
+
+        let received: Correlated<String> = consumer.poll_queue().await.unwrap().unwrap();
+        assert_eq!(received.correlation_id, None);
+        assert_eq!(correlation_id(), None);
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+/// Maps the variants of a fieldless enum to integers so they can be represented as such in contexts
+/// where compactness matters, e.g. in responses, while the enum's own serde implementation
+/// (usually strings) is used everywhere else, e.g. in the database.
+///
+/// Select the integer representation per field with `#[serde(with = "hextacy::repr::as_int")]`.
+///
+/// ### Example
+///
+/// ```ignore
+/// #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// #[serde(rename_all = "lowercase")]
+/// pub enum Role {
+///     User,
+///     Admin,
+/// }
+///
+/// impl IntRepr for Role {
+///     const REPR: &'static [(Self, i64)] = &[(Role::User, 0), (Role::Admin, 1)];
+/// }
+///
+/// #[derive(Serialize)]
+/// struct UserResponse {
+///     username: String,
+///     #[serde(with = "hextacy::repr::as_int")]
+///     role: Role,
+/// }
+/// ```
+pub trait IntRepr: Sized + Copy + PartialEq + 'static {
+    /// Every variant with its integer representation.
+    const REPR: &'static [(Self, i64)];
+
+    fn to_int(&self) -> i64 {
+        Self::REPR
+            .iter()
+            .find_map(|(variant, int)| (variant == self).then_some(*int))
+            .expect("every variant must be listed in IntRepr::REPR")
+    }
+
+    fn from_int(value: i64) -> Option<Self> {
+        Self::REPR
+            .iter()
+            .find_map(|(variant, int)| (*int == value).then_some(*variant))
+    }
+}
+
+/// Serde `with` module representing an [IntRepr] enum as an integer.
+pub mod as_int {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: IntRepr,
+        S: Serializer,
+    {
+        serializer.serialize_i64(value.to_int())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: IntRepr,
+        D: Deserializer<'de>,
+    {
+        let value = i64::deserialize(deserializer)?;
+        T::from_int(value).ok_or_else(|| D::Error::custom(format!("invalid variant: {value}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[cfg_attr(
+        feature = "db-postgres-seaorm",
+        derive(sea_orm::EnumIter, sea_orm::DeriveActiveEnum)
+    )]
+    #[cfg_attr(
+        feature = "db-postgres-seaorm",
+        sea_orm(rs_type = "String", db_type = "String(None)")
+    )]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        #[cfg_attr(feature = "db-postgres-seaorm", sea_orm(string_value = "user"))]
+        User,
+        #[cfg_attr(feature = "db-postgres-seaorm", sea_orm(string_value = "admin"))]
+        Admin,
+    }
+
+    impl IntRepr for Role {
+        const REPR: &'static [(Self, i64)] = &[(Role::User, 0), (Role::Admin, 1)];
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct UserResponse {
+        username: String,
+        #[serde(with = "as_int")]
+        role: Role,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct UserRow {
+        username: String,
+        role: Role,
+    }
+
+    #[test]
+    fn role_as_int_in_response() {
+        let response = UserResponse {
+            username: "bob".to_string(),
+            role: Role::Admin,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"username":"bob","role":1}"#);
+        assert_eq!(
+            serde_json::from_str::<UserResponse>(&json).unwrap(),
+            response
+        );
+
+        let invalid = serde_json::from_str::<UserResponse>(r#"{"username":"bob","role":7}"#);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn role_as_string_in_db() {
+        let row = UserRow {
+            username: "bob".to_string(),
+            role: Role::Admin,
+        };
+        assert_eq!(
+            serde_json::to_string(&row).unwrap(),
+            r#"{"username":"bob","role":"admin"}"#
+        );
+
+        #[cfg(feature = "db-postgres-seaorm")]
+        {
+            use sea_orm::ActiveEnum;
+            assert_eq!(Role::Admin.to_value(), "admin");
+            assert_eq!(
+                Role::try_from_value(&"user".to_string()).unwrap(),
+                Role::User
+            );
+        }
+    }
+}
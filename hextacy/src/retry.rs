@@ -0,0 +1,471 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+static GLOBAL_BUDGET: RwLock<Option<RetryBudget>> = RwLock::new(None);
+
+/// Caps how many retries happen across the whole process, so that components retrying independently
+/// don't multiply the load on a dependency that is already struggling.
+///
+/// Implemented as a token bucket holding at most `capacity` tokens which regains one token every `refill`.
+/// Every retry takes a token. First attempts are free, so once the bucket is empty calls still go through
+/// once but fail fast instead of retrying.
+///
+/// Clones share the bucket.
+///
+/// ### Example
+///
+/// ```ignore
+/// // At startup
+/// retry::set_global_budget(RetryBudget::new(50, Duration::from_millis(100)));
+///
+/// // Anywhere retries happen
+/// let user = Retry::new(3, Duration::from_millis(50))
+///     .run(|| repo.get_by_id(id))
+///     .await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    capacity: u32,
+    refill: Duration,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RetryBudget {
+    pub fn new(capacity: u32, refill: Duration) -> Self {
+        Self {
+            capacity,
+            refill,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: f64::from(capacity),
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take a token for a retry. Returns `false` if the budget is exhausted, in which case the caller
+    /// should give up instead of retrying.
+    pub fn try_withdraw(&self) -> bool {
+        self.try_withdraw_at(Instant::now())
+    }
+
+    /// The number of retries currently allowed.
+    pub fn remaining(&self) -> u32 {
+        let mut bucket = self.bucket.lock().expect("retry budget lock poisoned");
+        self.refill(&mut bucket, Instant::now());
+        bucket.tokens as u32
+    }
+
+    fn try_withdraw_at(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().expect("retry budget lock poisoned");
+        self.refill(&mut bucket, now);
+        if bucket.tokens < 1. {
+            return false;
+        }
+        bucket.tokens -= 1.;
+        true
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        let refilled = if self.refill.is_zero() {
+            f64::INFINITY
+        } else {
+            elapsed.as_secs_f64() / self.refill.as_secs_f64()
+        };
+        bucket.tokens = (bucket.tokens + refilled).min(f64::from(self.capacity));
+        bucket.updated = now;
+    }
+}
+
+/// Sets the budget shared by every [Retry] that wasn't given its own. Should be called once at startup,
+/// until then retries are not limited.
+pub fn set_global_budget(budget: RetryBudget) {
+    *GLOBAL_BUDGET.write().expect("retry budget lock poisoned") = Some(budget);
+}
+
+/// The budget set with [set_global_budget], if any.
+pub fn global_budget() -> Option<RetryBudget> {
+    GLOBAL_BUDGET
+        .read()
+        .expect("retry budget lock poisoned")
+        .clone()
+}
+
+/// Errors which may go away by themselves, such as pool exhaustion or dropped connections, as opposed to
+/// errors caused by the operation itself, which fail the same way when retried.
+pub trait Transient {
+    fn is_transient(&self) -> bool;
+}
+
+#[cfg(feature = "cache-redis")]
+impl Transient for deadpool_redis::PoolError {
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout(_) => true,
+            Self::Backend(e) => {
+                e.is_io_error()
+                    || e.is_timeout()
+                    || e.is_connection_dropped()
+                    || e.is_connection_refusal()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "db-postgres-seaorm",
+    feature = "db-mysql-seaorm",
+    feature = "db-sqlite-seaorm"
+))]
+impl Transient for sea_orm::DbErr {
+    fn is_transient(&self) -> bool {
+        matches!(self, Self::ConnectionAcquire(_) | Self::Conn(_))
+    }
+}
+
+/// r2d2 only errors when it times out waiting for a connection.
+#[cfg(any(
+    feature = "db-postgres-diesel",
+    feature = "db-mysql-diesel",
+    feature = "db-sqlite-diesel"
+))]
+impl Transient for diesel::r2d2::PoolError {
+    fn is_transient(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "db-mongo")]
+impl Transient for mongodb::error::Error {
+    fn is_transient(&self) -> bool {
+        use mongodb::error::{ErrorKind, TRANSIENT_TRANSACTION_ERROR};
+        self.contains_label(TRANSIENT_TRANSACTION_ERROR)
+            || matches!(
+                *self.kind,
+                ErrorKind::Io(_)
+                    | ErrorKind::ConnectionPoolCleared { .. }
+                    | ErrorKind::ServerSelection { .. }
+            )
+    }
+}
+
+/// How long to wait before each retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed(Duration),
+    /// Doubles the delay with every retry starting from `base`, up to `max`. With `jitter` a random delay
+    /// up to that is used instead, so clients failing at the same time don't retry in lockstep.
+    Exponential {
+        base: Duration,
+        max: Duration,
+        jitter: bool,
+    },
+}
+
+impl Backoff {
+    /// The delay before the `retry`th retry, starting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Self::Fixed(delay) => delay,
+            Self::Exponential { base, max, jitter } => {
+                let factor = 2_u32.saturating_pow(retry.saturating_sub(1));
+                let delay = base.saturating_mul(factor).min(max);
+                if jitter {
+                    delay.mul_f64(random_fraction())
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+/// A random duration within `base ± jitter`, e.g. for cache TTLs and `Retry-After` values, so that clients
+/// can't time their attempts to the exact moment a limit expires and entries set together don't expire together.
+pub fn jittered(base: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return base;
+    }
+    (base + jitter.mul_f64(2. * random_fraction())).saturating_sub(jitter)
+}
+
+/// A random number in `[0, 1)`, good enough for spreading out retries without pulling in a RNG.
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    // Every RandomState is seeded with different keys
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (random >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// Runs fallible operations up to `attempts` times, waiting according to its [Backoff] between attempts.
+///
+/// Every retry is withdrawn from a [RetryBudget], the global one unless another one is given.
+/// When the budget is exhausted the last error is returned immediately.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    attempts: u32,
+    backoff: Backoff,
+    budget: Option<RetryBudget>,
+}
+
+impl Retry {
+    /// Waits a fixed `delay` between attempts.
+    pub fn new(attempts: u32, delay: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff: Backoff::Fixed(delay),
+            budget: None,
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Use the given budget instead of the global one.
+    pub fn budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub async fn run<T, E, F, Fut>(&self, op: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.run_if(|_| true, op).await
+    }
+
+    /// Like [run][Self::run], but only retries [transient][Transient] errors, returning any other error immediately.
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let conn = Retry::new(5, Duration::ZERO)
+    ///     .backoff(Backoff::Exponential {
+    ///         base: Duration::from_millis(50),
+    ///         max: Duration::from_secs(2),
+    ///         jitter: true,
+    ///     })
+    ///     .run_transient(|| driver.connect())
+    ///     .await?;
+    /// ```
+    pub async fn run_transient<T, E, F, Fut>(&self, op: F) -> Result<T, E>
+    where
+        E: Transient,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.run_if(E::is_transient, op).await
+    }
+
+    /// Like [run][Self::run], but only retries errors for which `retryable` returns `true`.
+    pub async fn run_if<T, E, F, Fut>(
+        &self,
+        retryable: impl Fn(&E) -> bool,
+        mut op: F,
+    ) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let budget = self.budget.clone().or_else(global_budget);
+        let mut attempt = 1;
+        loop {
+            let err = match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            if attempt >= self.attempts || !retryable(&err) {
+                return Err(err);
+            }
+
+            if let Some(ref budget) = budget {
+                if !budget.try_withdraw() {
+                    warn!("Retry budget exhausted, giving up after {attempt} attempt(s)");
+                    return Err(err);
+                }
+            }
+
+            tokio::time::sleep(self.backoff.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn failing(calls: &AtomicU32) -> Result<(), &'static str> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err("unavailable")
+    }
+
+    #[test]
+    fn budget_refills() {
+        let budget = RetryBudget::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert!(budget.try_withdraw_at(start));
+        assert!(budget.try_withdraw_at(start));
+        assert!(!budget.try_withdraw_at(start));
+
+        assert!(!budget.try_withdraw_at(start + Duration::from_millis(500)));
+        assert!(budget.try_withdraw_at(start + Duration::from_secs(1)));
+        assert!(!budget.try_withdraw_at(start + Duration::from_secs(1)));
+
+        // Never exceeds the capacity
+        let later = start + Duration::from_secs(60);
+        assert!(budget.try_withdraw_at(later));
+        assert!(budget.try_withdraw_at(later));
+        assert!(!budget.try_withdraw_at(later));
+    }
+
+    #[tokio::test]
+    async fn fails_fast_once_budget_is_exhausted() {
+        let budget = RetryBudget::new(3, Duration::from_secs(3600));
+        let retry = Retry::new(3, Duration::ZERO).budget(budget.clone());
+        let calls = AtomicU32::new(0);
+
+        // Two retries out of three tokens
+        assert!(retry.run(|| failing(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(budget.remaining(), 1);
+
+        // Only one retry left
+        calls.store(0, Ordering::SeqCst);
+        assert!(retry.run(|| failing(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Budget is empty, a single attempt and no retries
+        calls.store(0, Ordering::SeqCst);
+        assert!(retry.run(|| failing(&calls)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(budget.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let retry = Retry::new(5, Duration::ZERO).budget(RetryBudget::new(10, Duration::ZERO));
+        let calls = AtomicU32::new(0);
+
+        let result = retry
+            .run(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err("unavailable"),
+                    n => Ok(n),
+                }
+            })
+            .await;
+        assert_eq!(result, Ok(2));
+
+        calls.store(0, Ordering::SeqCst);
+        let result = retry.run_if(
+            |e| *e != "fatal",
+            || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>("fatal")
+            },
+        );
+        assert_eq!(result.await, Err("fatal"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum FakeDriverError {
+        PoolTimeout,
+        UniqueViolation,
+    }
+
+    impl Transient for FakeDriverError {
+        fn is_transient(&self) -> bool {
+            matches!(self, Self::PoolTimeout)
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_transient_errors() {
+        let retry = Retry::new(4, Duration::ZERO).budget(RetryBudget::new(10, Duration::ZERO));
+        let calls = AtomicU32::new(0);
+
+        let result = retry
+            .run_transient(|| async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(FakeDriverError::PoolTimeout),
+                    n => Ok(n),
+                }
+            })
+            .await;
+        assert_eq!(result, Ok(2));
+
+        calls.store(0, Ordering::SeqCst);
+        let result = retry
+            .run_transient(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(FakeDriverError::UniqueViolation)
+            })
+            .await;
+        assert_eq!(result, Err(FakeDriverError::UniqueViolation));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn backoff_delays() {
+        let fixed = Backoff::Fixed(Duration::from_millis(100));
+        assert_eq!(fixed.delay(1), fixed.delay(10));
+
+        let exponential = Backoff::Exponential {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            jitter: false,
+        };
+        let delays = (1..=6)
+            .map(|i| exponential.delay(i).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(exponential.delay(u32::MAX), Duration::from_secs(1));
+
+        let jittered = Backoff::Exponential {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            jitter: true,
+        };
+        for retry in 1..=6 {
+            assert!(jittered.delay(retry) <= exponential.delay(retry));
+        }
+    }
+
+    #[test]
+    fn jittered_durations_vary_within_the_window() {
+        let base = Duration::from_secs(3600);
+        let jitter = Duration::from_secs(300);
+
+        let ttls = (0..50)
+            .map(|_| jittered(base, jitter))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(ttls.len() > 1);
+        assert!(ttls
+            .iter()
+            .all(|ttl| *ttl >= base - jitter && *ttl <= base + jitter));
+
+        assert_eq!(jittered(base, Duration::ZERO), base);
+        assert!(jittered(Duration::ZERO, jitter) <= jitter);
+    }
+}
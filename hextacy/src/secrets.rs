@@ -0,0 +1,133 @@
+use std::{
+    env::VarError,
+    future::Future,
+    path::{Component, Path, PathBuf},
+};
+use thiserror::Error;
+
+/// The directory Docker and Kubernetes mount secrets to.
+pub const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+    #[error("Invalid secret key: {0}")]
+    InvalidKey(String),
+    #[error("Secret is not valid unicode: {0}")]
+    NotUnicode(String),
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A source of credentials such as database passwords.
+///
+/// Reading credentials from the env is fine for development, but in production they should come from
+/// wherever the deployment keeps them, e.g. [files mounted by the orchestrator][FileSecrets].
+/// Drivers can be configured the same way regardless of the source.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn redis_driver(secrets: &impl SecretSource) -> Result<RedisDriver, SecretError> {
+///     let password = secrets.get_secret("REDIS_PASSWORD").await?;
+///     Ok(RedisDriver::new("localhost", 6379, None, Some(&password), 0))
+/// }
+/// ```
+pub trait SecretSource {
+    fn get_secret(&self, key: &str) -> impl Future<Output = Result<String, SecretError>> + Send;
+}
+
+/// Reads secrets from environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecrets;
+
+impl SecretSource for EnvSecrets {
+    async fn get_secret(&self, key: &str) -> Result<String, SecretError> {
+        match std::env::var(key) {
+            Ok(secret) => Ok(secret),
+            Err(VarError::NotPresent) => Err(SecretError::NotFound(key.to_string())),
+            Err(VarError::NotUnicode(_)) => Err(SecretError::NotUnicode(key.to_string())),
+        }
+    }
+}
+
+/// Reads secrets from files named after their keys in a directory, [/run/secrets][DEFAULT_SECRETS_DIR] by default.
+/// Trailing newlines are trimmed from the contents.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl Default for FileSecrets {
+    fn default() -> Self {
+        Self::new(DEFAULT_SECRETS_DIR)
+    }
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SecretSource for FileSecrets {
+    async fn get_secret(&self, key: &str) -> Result<String, SecretError> {
+        // Keys must not be able to escape the directory
+        let mut components = Path::new(key).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(SecretError::InvalidKey(key.to_string()));
+        }
+
+        match tokio::fs::read(self.dir.join(key)).await {
+            Ok(secret) => String::from_utf8(secret)
+                .map(|secret| secret.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|_| SecretError::NotUnicode(key.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(SecretError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn env_source() {
+        std::env::set_var("HEXTACY_SECRET_TEST", "hunter2");
+
+        let secret = EnvSecrets.get_secret("HEXTACY_SECRET_TEST").await.unwrap();
+        assert_eq!(secret, "hunter2");
+
+        let err = EnvSecrets.get_secret("HEXTACY_SECRET_MISSING").await;
+        assert!(matches!(err, Err(SecretError::NotFound(key)) if key == "HEXTACY_SECRET_MISSING"));
+    }
+
+    #[tokio::test]
+    async fn file_source() {
+        let dir = std::env::temp_dir().join("hextacy_secrets");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("db_password"), "hunter2\n").unwrap();
+
+        let secrets = FileSecrets::new(&dir);
+
+        let secret = secrets.get_secret("db_password").await.unwrap();
+        assert_eq!(secret, "hunter2");
+
+        let err = secrets.get_secret("missing").await;
+        assert!(matches!(err, Err(SecretError::NotFound(_))));
+
+        for key in ["../db_password", "/etc/passwd", "nested/db_password", ""] {
+            let err = secrets.get_secret(key).await;
+            assert!(matches!(err, Err(SecretError::InvalidKey(_))), "{key}");
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use crate::driver::{Atomic, Driver};
+use std::future::Future;
+
+/// Runs `f` in a transaction which is always rolled back, so tests can use a shared database without
+/// cleaning up after themselves.
+///
+/// `f` receives the transaction and must hand it back along with its result. If `f` panics the transaction is
+/// dropped without being committed, which rolls it back when the connection is closed or discarded by its pool.
+///
+/// Panics if connecting or managing the transaction fails, since it is meant for tests.
+///
+/// ### Example
+///
+/// ```ignore
+/// let user = with_test_transaction(&driver, |mut conn| async move {
+///     let user = UserAdapter::create(&mut conn, "alice").unwrap();
+///     (conn, user)
+/// })
+/// .await;
+/// ```
+pub async fn with_test_transaction<D, F, Fut, R>(driver: &D, f: F) -> R
+where
+    D: Driver,
+    D::Error: std::fmt::Debug,
+    D::Connection: Atomic,
+    <D::Connection as Atomic>::Error: std::fmt::Debug,
+    F: FnOnce(<D::Connection as Atomic>::TransactionResult) -> Fut,
+    Fut: Future<Output = (<D::Connection as Atomic>::TransactionResult, R)>,
+{
+    let conn = driver
+        .connect()
+        .await
+        .expect("test transaction could not connect");
+    let tx = conn
+        .start_transaction()
+        .await
+        .expect("test transaction could not start");
+    let (tx, result) = f(tx).await;
+    D::Connection::abort_transaction(tx)
+        .await
+        .expect("test transaction could not be rolled back");
+    result
+}
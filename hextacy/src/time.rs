@@ -16,4 +16,11 @@ pub fn date_now() -> chrono::NaiveDate {
     chrono::Utc::now().date_naive()
 }
 
+/// Converting date times to a configurable display timezone, enabled with the `timezone` feature.
+#[cfg(feature = "timezone")]
+mod tz;
+
+#[cfg(feature = "timezone")]
+pub use tz::*;
+
 pub use chrono;
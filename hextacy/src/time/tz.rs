@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use thiserror::Error;
+
+/// The env variable holding the IANA name of the timezone used for display, e.g. `Europe/Zagreb`.
+pub const TIMEZONE_ENV: &str = "APP_TIMEZONE";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TimezoneError {
+    #[error("Unknown timezone '{0}', expected an IANA name such as Europe/Zagreb")]
+    Unknown(String),
+}
+
+/// Parse an IANA timezone name.
+pub fn parse_timezone(name: &str) -> Result<Tz, TimezoneError> {
+    name.trim()
+        .parse()
+        .map_err(|_| TimezoneError::Unknown(name.to_string()))
+}
+
+/// The timezone set in [TIMEZONE_ENV], or UTC if it is not set.
+pub fn configured_tz() -> Result<Tz, TimezoneError> {
+    match std::env::var(TIMEZONE_ENV) {
+        Ok(name) => parse_timezone(&name),
+        Err(_) => Ok(Tz::UTC),
+    }
+}
+
+/// Convert a UTC date time to the [configured timezone][configured_tz] for display.
+/// Naive date times from this module can be converted with `.and_utc()`.
+pub fn to_configured_tz(dt: DateTime<Utc>) -> Result<DateTime<Tz>, TimezoneError> {
+    Ok(dt.with_timezone(&configured_tz()?))
+}
+
+pub use chrono_tz;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn converts_across_dst() {
+        let zagreb = parse_timezone("Europe/Zagreb").unwrap();
+        let new_york = parse_timezone("America/New_York").unwrap();
+
+        // Zagreb moves to CEST at 01:00 UTC on the last Sunday of March
+        let before = utc(2024, 3, 31, 0, 59).with_timezone(&zagreb);
+        let after = utc(2024, 3, 31, 1, 0).with_timezone(&zagreb);
+        assert_eq!(before.to_rfc3339(), "2024-03-31T01:59:00+01:00");
+        assert_eq!(after.to_rfc3339(), "2024-03-31T03:00:00+02:00");
+
+        // New York falls back to EST at 06:00 UTC on the first Sunday of November
+        let before = utc(2024, 11, 3, 5, 59).with_timezone(&new_york);
+        let after = utc(2024, 11, 3, 6, 0).with_timezone(&new_york);
+        assert_eq!(before.to_rfc3339(), "2024-11-03T01:59:00-04:00");
+        assert_eq!(after.to_rfc3339(), "2024-11-03T01:00:00-05:00");
+    }
+
+    #[test]
+    fn configured_from_env() {
+        let dt = utc(2024, 7, 1, 12, 0);
+
+        std::env::set_var(TIMEZONE_ENV, "Europe/Zagreb");
+        assert_eq!(
+            to_configured_tz(dt).unwrap().to_rfc3339(),
+            "2024-07-01T14:00:00+02:00"
+        );
+
+        std::env::set_var(TIMEZONE_ENV, "Mars/Olympus_Mons");
+        assert_eq!(
+            to_configured_tz(dt),
+            Err(TimezoneError::Unknown("Mars/Olympus_Mons".to_string()))
+        );
+
+        std::env::remove_var(TIMEZONE_ENV);
+        assert_eq!(to_configured_tz(dt).unwrap().timezone(), Tz::UTC);
+    }
+}
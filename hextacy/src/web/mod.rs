@@ -1,6 +1,16 @@
 /// Utilities for working with http. The big boy of this module is the the [RestResponse][xhttp::response::RestResponse].
 pub mod xhttp;
 
+/// OAuth related types.
+pub mod oauth;
+
+/// Reloading config without restarting the server.
+pub mod reload;
+
+/// Building OpenSSL acceptors with a minimum TLS version.
+#[cfg(feature = "web-tls")]
+pub mod tls;
+
 pub use cookie;
 pub use http;
 pub use mime;
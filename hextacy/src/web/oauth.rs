@@ -0,0 +1,164 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+use thiserror::Error;
+
+/// A set of OAuth scopes, as per [RFC 6749](https://www.rfc-editor.org/rfc/rfc6749#section-3.3).
+///
+/// Parsed from and rendered to the space delimited form. The rendered form is canonical, i.e. scopes are
+/// deduplicated and sorted, so equal sets always render the same and can be compared as strings.
+///
+/// ### Example
+///
+/// ```ignore
+/// let existing: Scopes = stored.scope.parse()?;
+/// let granted: Scopes = token_response.scope.parse()?;
+/// let scope = existing.union(&granted).to_string();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scopes(BTreeSet<String>);
+
+impl Scopes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Whether every scope in `other` is in this set.
+    pub fn contains_all(&self, other: &Scopes) -> bool {
+        self.0.is_superset(&other.0)
+    }
+
+    /// Add the scope to the set. Returns whether it was not already present.
+    pub fn add(&mut self, scope: &str) -> Result<bool, ScopeError> {
+        validate(scope)?;
+        Ok(self.0.insert(scope.to_string()))
+    }
+
+    pub fn remove(&mut self, scope: &str) -> bool {
+        self.0.remove(scope)
+    }
+
+    /// The scopes present in either set.
+    pub fn union(&self, other: &Scopes) -> Scopes {
+        Self(self.0.union(&other.0).cloned().collect())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Checks the scope is a valid `scope-token`, i.e. non empty printable ASCII without spaces,
+/// double quotes and backslashes.
+fn validate(scope: &str) -> Result<(), ScopeError> {
+    let valid = !scope.is_empty()
+        && scope
+            .bytes()
+            .all(|b| matches!(b, 0x21 | 0x23..=0x5B | 0x5D..=0x7E));
+    if valid {
+        Ok(())
+    } else {
+        Err(ScopeError(scope.to_string()))
+    }
+}
+
+impl FromStr for Scopes {
+    type Err = ScopeError;
+
+    /// Parses a space delimited list of scopes. Repeated spaces are tolerated.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scopes = Self::new();
+        for scope in s.split(' ').filter(|scope| !scope.is_empty()) {
+            scopes.add(scope)?;
+        }
+        Ok(scopes)
+    }
+}
+
+impl Display for Scopes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, scope) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{scope}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid scope: {0:?}")]
+pub struct ScopeError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let scopes: Scopes = "repo  read:user repo".parse().unwrap();
+        assert_eq!(scopes.len(), 2);
+        assert!(scopes.contains("repo"));
+        assert!(scopes.contains("read:user"));
+        assert!(!scopes.contains("user"));
+
+        assert!("".parse::<Scopes>().unwrap().is_empty());
+        assert_eq!(
+            "repo \"quoted\"".parse::<Scopes>(),
+            Err(ScopeError("\"quoted\"".to_string()))
+        );
+        assert!("repo\tuser".parse::<Scopes>().is_err());
+    }
+
+    #[test]
+    fn union_and_rendering() {
+        let existing: Scopes = "email profile".parse().unwrap();
+        let granted: Scopes = "openid email".parse().unwrap();
+
+        let merged = existing.union(&granted);
+        assert_eq!(merged.to_string(), "email openid profile");
+        assert!(merged.contains_all(&existing));
+        assert!(merged.contains_all(&granted));
+        assert!(!existing.contains_all(&granted));
+
+        let mut scopes = Scopes::new();
+        assert!(scopes.add("profile").unwrap());
+        assert!(!scopes.add("profile").unwrap());
+        assert!(scopes.add("with space").is_err());
+        assert_eq!(scopes.to_string(), "profile");
+
+        assert_eq!(
+            serde_json::to_string(&merged).unwrap(),
+            r#""email openid profile""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Scopes>(r#""profile email openid""#).unwrap(),
+            merged
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use log::LevelFilter;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+use tracing::{info, warn};
+
+type Apply = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Re-reads a dotenv style config file and applies the settings that can change while the server
+/// is running, usually when the process receives `SIGHUP`.
+///
+/// Only keys registered with [on][ConfigReloader::on] are hot-reloadable. Their env variables are
+/// updated and their handlers called when their value changes. Changes to any other key are left untouched
+/// and logged as a warning, since they require a restart to take effect.
+///
+/// ### Example
+///
+/// ```ignore
+/// let reloader = ConfigReloader::new(".env")?
+///     .log_level("LOG_LEVEL")
+///     .on("MAINTENANCE", move |value| {
+///         maintenance.set(value.parse().map_err(|e| format!("{e}"))?);
+///         Ok(())
+///     });
+/// Arc::new(reloader).reload_on_sighup()?;
+/// ```
+pub struct ConfigReloader {
+    path: PathBuf,
+    handlers: HashMap<String, Apply>,
+    current: Mutex<HashMap<String, String>>,
+}
+
+impl Debug for ConfigReloader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigReloader")
+            .field("path", &self.path)
+            .field("hot", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// The outcome of a [reload][ConfigReloader::reload], listing the changed keys.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    pub applied: Vec<String>,
+    /// Keys which are not hot-reloadable.
+    pub skipped: Vec<String>,
+    /// Keys whose handler rejected the new value, along with the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error("Could not read config: {0}")]
+    Read(#[from] dotenv::Error),
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ConfigReloader {
+    /// Reads the file to know which values are currently in effect.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, ReloadError> {
+        let path = path.as_ref().to_path_buf();
+        let current = read(&path)?;
+        Ok(Self {
+            path,
+            handlers: HashMap::new(),
+            current: Mutex::new(current),
+        })
+    }
+
+    /// Make `key` hot-reloadable. `apply` receives the new value and returns an error if it is invalid,
+    /// in which case the previous value stays in effect.
+    pub fn on(
+        mut self,
+        key: &str,
+        apply: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.handlers.insert(key.to_string(), Box::new(apply));
+        self
+    }
+
+    /// Reload the max log level from `key`. The logger must have been initialised with the most verbose level
+    /// the setting can be reloaded to, since this only caps which records reach it.
+    pub fn log_level(self, key: &str) -> Self {
+        self.on(key, |value| {
+            let level = value
+                .trim()
+                .parse::<LevelFilter>()
+                .map_err(|e| e.to_string())?;
+            log::set_max_level(level);
+            Ok(())
+        })
+    }
+
+    /// Re-read the file and apply the changed hot-reloadable settings.
+    pub fn reload(&self) -> Result<ReloadReport, ReloadError> {
+        let loaded = read(&self.path)?;
+        let mut current = self.current.lock().expect("config reloader lock poisoned");
+        let mut report = ReloadReport::default();
+
+        let mut changed = loaded
+            .iter()
+            .filter(|(key, value)| current.get(*key) != Some(value))
+            .collect::<Vec<_>>();
+        changed.sort();
+
+        for (key, value) in changed {
+            let Some(apply) = self.handlers.get(key) else {
+                warn!("Config '{key}' changed but is not reloadable, restart to apply it");
+                report.skipped.push(key.clone());
+                continue;
+            };
+
+            match apply(value) {
+                Ok(()) => {
+                    std::env::set_var(key, value);
+                    current.insert(key.clone(), value.clone());
+                    report.applied.push(key.clone());
+                }
+                Err(e) => {
+                    warn!("Invalid value for '{key}', keeping the previous one: {e}");
+                    report.failed.push((key.clone(), e));
+                }
+            }
+        }
+
+        info!(
+            "Config reloaded, applied: {:?}, skipped: {:?}",
+            report.applied, report.skipped
+        );
+        Ok(report)
+    }
+
+    /// [Reload][Self::reload] the config every time the process receives `SIGHUP`.
+    /// The handler is installed before returning, so signals sent afterwards are never missed.
+    #[cfg(unix)]
+    pub fn reload_on_sighup(self: Arc<Self>) -> Result<tokio::task::JoinHandle<()>, ReloadError> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        Ok(tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                if let Err(e) = self.reload() {
+                    warn!("Config reload failed: {e}");
+                }
+            }
+        }))
+    }
+}
+
+// `from_path` never overrides variables which are already set, so it cannot be used to reload them
+#[allow(deprecated)]
+fn read(path: &Path) -> Result<HashMap<String, String>, ReloadError> {
+    dotenv::from_path_iter(path)?
+        .map(|item| item.map_err(ReloadError::Read))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write(path: &Path, config: &str) {
+        std::fs::write(path, config).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sighup_reloads_log_level() {
+        let path = std::env::temp_dir().join(format!("hextacy_reload_{}.env", std::process::id()));
+        write(&path, "LOG_LEVEL=info\nPORT=8000\n");
+        log::set_max_level(LevelFilter::Info);
+
+        let reloader = Arc::new(ConfigReloader::new(&path).unwrap().log_level("LOG_LEVEL"));
+        let task = reloader.clone().reload_on_sighup().unwrap();
+
+        write(&path, "LOG_LEVEL=debug\nPORT=9000\n");
+        let status = std::process::Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let mut waited = Duration::ZERO;
+        while log::max_level() != LevelFilter::Debug && waited < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            waited += Duration::from_millis(10);
+        }
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+
+        // The port needs a restart and must not be touched
+        assert_ne!(std::env::var("PORT").ok().as_deref(), Some("9000"));
+
+        task.abort();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_changes() {
+        let path =
+            std::env::temp_dir().join(format!("hextacy_reload_report_{}.env", std::process::id()));
+        write(&path, "RELOAD_A=1\nRELOAD_B=1\nRELOAD_C=1\n");
+
+        let applied = Arc::new(Mutex::new(vec![]));
+        let reloader = ConfigReloader::new(&path)
+            .unwrap()
+            .on("RELOAD_A", {
+                let applied = applied.clone();
+                move |value| {
+                    applied.lock().unwrap().push(value.to_string());
+                    Ok(())
+                }
+            })
+            .on("RELOAD_B", |value| Err(format!("{value} is not allowed")));
+
+        // Nothing changed
+        assert_eq!(reloader.reload().unwrap(), ReloadReport::default());
+
+        write(&path, "RELOAD_A=2\nRELOAD_B=2\nRELOAD_C=2\n");
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.applied, ["RELOAD_A"]);
+        assert_eq!(report.skipped, ["RELOAD_C"]);
+        assert_eq!(
+            report.failed,
+            [("RELOAD_B".to_string(), "2 is not allowed".to_string())]
+        );
+        assert_eq!(*applied.lock().unwrap(), ["2"]);
+        assert_eq!(std::env::var("RELOAD_A").unwrap(), "2");
+        assert!(std::env::var("RELOAD_C").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
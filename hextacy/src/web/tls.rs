@@ -0,0 +1,183 @@
+use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod, SslVersion};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The oldest TLS version the server accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+/// Which of Mozilla's [recommended configurations](https://wiki.mozilla.org/Security/Server_Side_TLS)
+/// the cipher suites are taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CipherPolicy {
+    /// Supports TLS 1.2 clients with forward secret AEAD ciphers.
+    #[default]
+    Intermediate,
+
+    /// TLS 1.3 only. Implies a minimum version of TLS 1.3.
+    Modern,
+}
+
+/// Settings for building the server's [SslAcceptor].
+///
+/// ### Example
+///
+/// ```ignore
+/// let tls = TlsConfig::new("certs/cert.pem", "certs/key.pem").min_version(TlsVersion::Tls13);
+///
+/// HttpServer::new(app).bind_openssl("0.0.0.0:443", tls.acceptor()?)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    cert: PathBuf,
+    key: PathBuf,
+    min_version: TlsVersion,
+    ciphers: CipherPolicy,
+}
+
+impl TlsConfig {
+    /// `cert` is a PEM certificate chain and `key` its PEM private key.
+    pub fn new(cert: impl AsRef<Path>, key: impl AsRef<Path>) -> Self {
+        Self {
+            cert: cert.as_ref().to_path_buf(),
+            key: key.as_ref().to_path_buf(),
+            min_version: TlsVersion::default(),
+            ciphers: CipherPolicy::default(),
+        }
+    }
+
+    pub fn min_version(mut self, version: TlsVersion) -> Self {
+        self.min_version = version;
+        self
+    }
+
+    pub fn ciphers(mut self, policy: CipherPolicy) -> Self {
+        self.ciphers = policy;
+        self
+    }
+
+    /// Builds the acceptor with the certificate and key loaded. Fails if either file is missing
+    /// or the key does not belong to the certificate.
+    pub fn acceptor(&self) -> Result<SslAcceptorBuilder, TlsError> {
+        for (kind, path) in [("certificate", &self.cert), ("private key", &self.key)] {
+            if !path.is_file() {
+                return Err(TlsError::MissingFile {
+                    kind,
+                    path: path.clone(),
+                });
+            }
+        }
+
+        let method = SslMethod::tls_server();
+        let mut builder = match self.ciphers {
+            CipherPolicy::Intermediate => SslAcceptor::mozilla_intermediate_v5(method)?,
+            CipherPolicy::Modern => SslAcceptor::mozilla_modern_v5(method)?,
+        };
+
+        let min_version = match (self.min_version, self.ciphers) {
+            (TlsVersion::Tls13, _) | (_, CipherPolicy::Modern) => SslVersion::TLS1_3,
+            (TlsVersion::Tls12, CipherPolicy::Intermediate) => SslVersion::TLS1_2,
+        };
+        builder.set_min_proto_version(Some(min_version))?;
+
+        builder.set_certificate_chain_file(&self.cert)?;
+        builder.set_private_key_file(&self.key, SslFiletype::PEM)?;
+        builder.check_private_key()?;
+
+        Ok(builder)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("TLS {kind} not found at {}", path.display())]
+    MissingFile { kind: &'static str, path: PathBuf },
+    #[error("OpenSSL: {0}")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::PKey,
+        x509::{X509NameBuilder, X509},
+    };
+
+    /// Writes a self signed certificate and its key to a temporary directory.
+    fn self_signed(dir: &str) -> (PathBuf, PathBuf) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = cert.build();
+
+        let dir = std::env::temp_dir().join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::write(&cert_path, cert.to_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        (cert_path, key_path)
+    }
+
+    #[test]
+    fn tls13_minimum() {
+        let (cert, key) = self_signed("hextacy_tls13_minimum");
+
+        let mut acceptor = TlsConfig::new(&cert, &key)
+            .min_version(TlsVersion::Tls13)
+            .acceptor()
+            .unwrap();
+        assert_eq!(acceptor.min_proto_version(), Some(SslVersion::TLS1_3));
+
+        let mut acceptor = TlsConfig::new(&cert, &key).acceptor().unwrap();
+        assert_eq!(acceptor.min_proto_version(), Some(SslVersion::TLS1_2));
+
+        let mut acceptor = TlsConfig::new(&cert, &key)
+            .ciphers(CipherPolicy::Modern)
+            .acceptor()
+            .unwrap();
+        assert_eq!(acceptor.min_proto_version(), Some(SslVersion::TLS1_3));
+    }
+
+    #[test]
+    fn missing_cert() {
+        let (_, key) = self_signed("hextacy_tls_missing_cert");
+
+        let Err(err) = TlsConfig::new("/nonexistent/cert.pem", &key).acceptor() else {
+            panic!("built an acceptor without a certificate");
+        };
+        assert!(matches!(
+            err,
+            TlsError::MissingFile {
+                kind: "certificate",
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "TLS certificate not found at /nonexistent/cert.pem"
+        );
+    }
+}
@@ -1,2 +1,25 @@
+pub mod access_log;
+#[cfg(feature = "crypto")]
+pub mod body_hash;
+pub mod cache_control;
+pub mod concurrency;
+pub mod content_type;
+#[cfg(feature = "crypto")]
+pub mod csrf;
+#[cfg(any(feature = "cache-redis", feature = "cache-inmem"))]
+pub mod in_flight;
+pub mod json_depth;
+pub mod maintenance;
+pub mod normalize_path;
+pub mod pagination;
+pub mod payload;
+pub mod rate_limit;
+pub mod request_id;
 pub mod response;
+#[cfg(feature = "web-schema")]
+pub mod schema;
 pub mod security_headers;
+pub mod sse;
+pub mod static_files;
+pub mod version;
+pub mod ws;
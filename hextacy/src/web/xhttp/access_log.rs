@@ -0,0 +1,207 @@
+use super::request_id::X_REQUEST_ID;
+use http::{header, Method, Request, Response};
+use std::{fmt::Display, time::Instant};
+use tracing::info;
+
+/// The target access log events are emitted with, for filtering them into their own sink.
+pub const ACCESS_LOG_TARGET: &str = "access_log";
+
+/// A structured access log record, emitted as a single `tracing` event with the `access_log`
+/// target once the response is known.
+///
+/// Every property is a separate field so the records can be queried. Pair with a JSON
+/// formatting subscriber, such as `tracing_subscriber::fmt().json()`, to get one JSON object per request.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn access_log<B>(req: Request<B>, next: Next<B>) -> Response {
+///     let mut log = AccessLog::begin(&req);
+///     if let Some(path) = req.extensions().get::<MatchedPath>() {
+///         log = log.path_template(path.as_str());
+///     }
+///     if let Some(session) = req.extensions().get::<Session>() {
+///         log = log.user_id(session.user_id);
+///     }
+///     let res = next.run(req).await;
+///     log.finish(&res);
+///     res
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    method: Method,
+    path: String,
+    request_id: Option<String>,
+    user_id: Option<String>,
+    started: Instant,
+}
+
+impl AccessLog {
+    /// Starts timing the request. The path defaults to the request's path and should be replaced
+    /// with the matched route template when available, so requests to the same route group together.
+    pub fn begin<B>(req: &Request<B>) -> Self {
+        Self {
+            method: req.method().clone(),
+            path: req.uri().path().to_string(),
+            request_id: req
+                .headers()
+                .get(X_REQUEST_ID)
+                .and_then(|id| id.to_str().ok())
+                .map(String::from),
+            user_id: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn path_template(mut self, template: impl Into<String>) -> Self {
+        self.path = template.into();
+        self
+    }
+
+    /// Set for authenticated requests.
+    pub fn user_id(mut self, id: impl Display) -> Self {
+        self.user_id = Some(id.to_string());
+        self
+    }
+
+    /// Emits the record. The response size is read from its `Content-Length` and left out when it has none.
+    pub fn finish<B>(self, res: &Response<B>) {
+        let bytes = res
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<u64>().ok());
+
+        info!(
+            target: ACCESS_LOG_TARGET,
+            method = self.method.as_str(),
+            path = self.path,
+            status = res.status().as_u16(),
+            duration_ms = self.started.elapsed().as_millis() as u64,
+            bytes,
+            request_id = self.request_id,
+            user_id = self.user_id,
+            "{} {} {}",
+            self.method,
+            self.path,
+            res.status().as_u16()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Fields = Vec<(String, String)>;
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{value:?}")))
+        }
+
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()))
+        }
+    }
+
+    /// Records the targets and fields of emitted events.
+    #[derive(Debug, Clone, Default)]
+    struct EventRecorder(Arc<Mutex<Vec<(String, Fields)>>>);
+
+    impl tracing::Subscriber for EventRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = vec![];
+            event.record(&mut FieldVisitor(&mut fields));
+            self.0
+                .lock()
+                .unwrap()
+                .push((event.metadata().target().to_string(), fields));
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn emits_structured_record() {
+        let recorder = EventRecorder::default();
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri("/users/42/sessions?all=true")
+                .header(X_REQUEST_ID, "abc123")
+                .body(())
+                .unwrap();
+            let log = AccessLog::begin(&req)
+                .path_template("/users/:id/sessions")
+                .user_id(42);
+
+            let res = Response::builder()
+                .status(201)
+                .header(header::CONTENT_LENGTH, 17)
+                .body(())
+                .unwrap();
+            log.finish(&res);
+        });
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let (target, fields) = &events[0];
+        assert_eq!(target, ACCESS_LOG_TARGET);
+
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(field("method"), Some("POST"));
+        assert_eq!(field("path"), Some("/users/:id/sessions"));
+        assert_eq!(field("status"), Some("201"));
+        assert_eq!(field("bytes"), Some("17"));
+        assert_eq!(field("request_id"), Some("abc123"));
+        assert_eq!(field("user_id"), Some("42"));
+        assert!(field("duration_ms").is_some());
+    }
+
+    #[test]
+    fn leaves_out_unknown_fields() {
+        let recorder = EventRecorder::default();
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let req = Request::get("/health").body(()).unwrap();
+            AccessLog::begin(&req).finish(&Response::new(()));
+        });
+
+        let events = recorder.0.lock().unwrap();
+        let names = events[0]
+            .1
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"path"));
+        for absent in ["bytes", "request_id", "user_id"] {
+            assert!(!names.contains(&absent), "{absent}");
+        }
+    }
+}
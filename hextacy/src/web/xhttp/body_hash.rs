@@ -0,0 +1,117 @@
+use data_encoding::HEXLOWER;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Hex encoded SHA-256 of the raw request body. Headers are not part of the hash, so it only
+/// depends on the bytes sent.
+///
+/// Intended for idempotency layers, which store the hash alongside the idempotency key
+/// and reject a reuse of the key with a different body.
+///
+/// ### Example
+///
+/// ```ignore
+/// let hash = body_hash(&bytes);
+/// match store.get(&idempotency_key).await? {
+///     Some(stored) if !stored.matches(&hash) => return Err(Error::IdempotencyKeyReused),
+///     Some(stored) => return Ok(stored.response),
+///     None => {}
+/// }
+/// ```
+pub fn body_hash(body: &[u8]) -> String {
+    HEXLOWER.encode(&Sha256::digest(body))
+}
+
+/// Like [body_hash], but the body is first normalized as JSON so that whitespace and
+/// the order of object keys do not affect the hash.
+pub fn json_body_hash(body: &[u8]) -> Result<String, serde_json::Error> {
+    let value: Value = serde_json::from_slice(body)?;
+    let mut canonical = String::with_capacity(body.len());
+    write_canonical(&value, &mut canonical)?;
+    Ok(body_hash(canonical.as_bytes()))
+}
+
+/// Whether the body hashes to `expected`, as obtained from [body_hash].
+pub fn verify_body_hash(body: &[u8], expected: &str) -> bool {
+    constant_time_eq(body_hash(body).as_bytes(), expected.as_bytes())
+}
+
+/// Writes the value without whitespace and with object keys sorted.
+fn write_canonical(value: &Value, out: &mut String) -> Result<(), serde_json::Error> {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key)?);
+                out.push(':');
+                write_canonical(value, out)?;
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(value, out)?;
+            }
+            out.push(']');
+        }
+        value => out.push_str(&serde_json::to_string(value)?),
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_bodies() {
+        let hash = body_hash(b"{\"amount\":10}");
+        assert_eq!(hash, body_hash(b"{\"amount\":10}"));
+        assert_ne!(hash, body_hash(b"{\"amount\":11}"));
+        assert_ne!(hash, body_hash(b"{\"amount\": 10}"));
+        assert_eq!(
+            body_hash(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        assert!(verify_body_hash(b"{\"amount\":10}", &hash));
+        assert!(!verify_body_hash(b"{\"amount\":11}", &hash));
+    }
+
+    #[test]
+    fn normalized_json_bodies() {
+        let hash = json_body_hash(br#"{"amount":10,"to":{"id":1,"name":"bob"}}"#).unwrap();
+        let reformatted = json_body_hash(
+            br#"{
+                "to": { "name": "bob", "id": 1 },
+                "amount": 10
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(hash, reformatted);
+
+        let different = json_body_hash(br#"{"amount":10,"to":{"id":2,"name":"bob"}}"#).unwrap();
+        assert_ne!(hash, different);
+
+        // Array order is significant
+        assert_ne!(
+            json_body_hash(b"[1,2]").unwrap(),
+            json_body_hash(b"[2,1]").unwrap()
+        );
+        assert!(json_body_hash(b"{not json").is_err());
+    }
+}
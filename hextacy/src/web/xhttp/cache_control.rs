@@ -0,0 +1,262 @@
+use http::header::{HeaderName, HeaderValue, CACHE_CONTROL};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CacheControlError {
+    #[error("Contradictory cache directives: {0} and {1}")]
+    Contradiction(&'static str, &'static str),
+}
+
+/// Builder for `Cache-Control` response directives.
+///
+/// [build][CacheControl::build] returns a header pair usable with
+/// [with_headers][super::response::ResponseBuilder::with_headers], or an error if the directives contradict each other.
+///
+/// ### Example
+///
+/// ```ignore
+/// let cache_control = CacheControl::new()
+///     .public()
+///     .max_age(Duration::from_secs(3600))
+///     .stale_while_revalidate(Duration::from_secs(60))
+///     .build()?;
+///
+/// MessageResponse::new("Hello")
+///     .into_response(StatusCode::OK)
+///     .with_headers([cache_control])
+///     .json()
+/// ```
+///
+/// See <https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Cache-Control>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    public: bool,
+    private: bool,
+    no_cache: bool,
+    no_store: bool,
+    must_revalidate: bool,
+    immutable: bool,
+    max_age: Option<Duration>,
+    s_maxage: Option<Duration>,
+    stale_while_revalidate: Option<Duration>,
+}
+
+impl CacheControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The response can be stored in shared caches.
+    pub fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+
+    /// The response can only be stored in the client's cache.
+    pub fn private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
+    /// The response can be stored, but must be validated with the server before each reuse.
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+        self
+    }
+
+    /// The response must not be stored in any cache.
+    pub fn no_store(mut self) -> Self {
+        self.no_store = true;
+        self
+    }
+
+    pub fn must_revalidate(mut self) -> Self {
+        self.must_revalidate = true;
+        self
+    }
+
+    /// The response will not change while fresh.
+    pub fn immutable(mut self) -> Self {
+        self.immutable = true;
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Overrides `max-age` for shared caches.
+    pub fn s_maxage(mut self, s_maxage: Duration) -> Self {
+        self.s_maxage = Some(s_maxage);
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, stale: Duration) -> Self {
+        self.stale_while_revalidate = Some(stale);
+        self
+    }
+
+    /// Validate the directives and render them as a header value.
+    pub fn to_header_value(&self) -> Result<HeaderValue, CacheControlError> {
+        self.validate()?;
+
+        let mut value = String::new();
+        let mut push = |directive: &str| {
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+            value.push_str(directive);
+        };
+
+        let flags = [
+            (self.public, "public"),
+            (self.private, "private"),
+            (self.no_cache, "no-cache"),
+            (self.no_store, "no-store"),
+            (self.must_revalidate, "must-revalidate"),
+            (self.immutable, "immutable"),
+        ];
+        for (_, directive) in flags.into_iter().filter(|(set, _)| *set) {
+            push(directive);
+        }
+
+        let durations = [
+            (self.max_age, "max-age"),
+            (self.s_maxage, "s-maxage"),
+            (self.stale_while_revalidate, "stale-while-revalidate"),
+        ];
+        for (duration, directive) in durations {
+            if let Some(duration) = duration {
+                push(&format!("{directive}={}", duration.as_secs()));
+            }
+        }
+
+        Ok(HeaderValue::from_str(&value).expect("directives are valid header characters"))
+    }
+
+    /// Validate the directives and return them as a `Cache-Control` header pair.
+    pub fn build(&self) -> Result<(HeaderName, HeaderValue), CacheControlError> {
+        Ok((CACHE_CONTROL, self.to_header_value()?))
+    }
+
+    fn validate(&self) -> Result<(), CacheControlError> {
+        use CacheControlError::Contradiction;
+
+        if self.public && self.private {
+            return Err(Contradiction("public", "private"));
+        }
+
+        if self.no_store {
+            let storing = [
+                (self.public, "public"),
+                (self.immutable, "immutable"),
+                (self.max_age.is_some(), "max-age"),
+                (self.s_maxage.is_some(), "s-maxage"),
+                (
+                    self.stale_while_revalidate.is_some(),
+                    "stale-while-revalidate",
+                ),
+            ];
+            if let Some((_, directive)) = storing.into_iter().find(|(set, _)| *set) {
+                return Err(Contradiction("no-store", directive));
+            }
+        }
+
+        if self.private && self.s_maxage.is_some() {
+            return Err(Contradiction("private", "s-maxage"));
+        }
+
+        if self.no_cache && self.immutable {
+            return Err(Contradiction("no-cache", "immutable"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::xhttp::response::RestResponse;
+    use http::StatusCode;
+    use serde::Serialize;
+
+    #[test]
+    fn combinations() {
+        let value = CacheControl::new()
+            .public()
+            .max_age(Duration::from_secs(3600))
+            .stale_while_revalidate(Duration::from_secs(60))
+            .to_header_value()
+            .unwrap();
+        assert_eq!(value, "public, max-age=3600, stale-while-revalidate=60");
+
+        let value = CacheControl::new()
+            .private()
+            .no_cache()
+            .must_revalidate()
+            .to_header_value()
+            .unwrap();
+        assert_eq!(value, "private, no-cache, must-revalidate");
+
+        let value = CacheControl::new()
+            .public()
+            .immutable()
+            .max_age(Duration::from_secs(31536000))
+            .s_maxage(Duration::from_secs(600))
+            .to_header_value()
+            .unwrap();
+        assert_eq!(value, "public, immutable, max-age=31536000, s-maxage=600");
+
+        let value = CacheControl::new().no_store().to_header_value().unwrap();
+        assert_eq!(value, "no-store");
+    }
+
+    #[test]
+    fn contradictions() {
+        use CacheControlError::Contradiction;
+
+        let err = CacheControl::new()
+            .no_store()
+            .max_age(Duration::from_secs(60))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, Contradiction("no-store", "max-age"));
+
+        let err = CacheControl::new().public().private().build().unwrap_err();
+        assert_eq!(err, Contradiction("public", "private"));
+
+        let err = CacheControl::new()
+            .private()
+            .s_maxage(Duration::from_secs(60))
+            .build()
+            .unwrap_err();
+        assert_eq!(err, Contradiction("private", "s-maxage"));
+    }
+
+    #[derive(Serialize)]
+    struct Message {
+        message: &'static str,
+    }
+
+    impl RestResponse<'_> for Message {}
+
+    #[test]
+    fn with_response_builder() {
+        let header = CacheControl::new()
+            .private()
+            .max_age(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        let response = Message { message: "hello" }
+            .into_response(StatusCode::OK)
+            .with_headers([header])
+            .json()
+            .unwrap();
+
+        assert_eq!(response.headers()[CACHE_CONTROL], "private, max-age=10");
+    }
+}
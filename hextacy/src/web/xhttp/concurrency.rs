@@ -0,0 +1,130 @@
+use super::payload::Problem;
+use http::{Response, StatusCode};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// What happens to requests arriving while all slots are taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Saturation {
+    /// Reject them immediately.
+    #[default]
+    Reject,
+
+    /// Wait for a free slot for at most the given duration, then reject them.
+    Queue(Duration),
+}
+
+/// Caps the amount of simultaneous executions of a handler, e.g. an expensive report generation.
+///
+/// Each route that needs a limit should get its own instance. Clones share slots.
+///
+/// ### Example
+///
+/// ```ignore
+/// let reports = Concurrency::new(4, Saturation::Queue(Duration::from_secs(5)));
+///
+/// router.route("/reports", post(generate_report).layer(middleware::from_fn(move |req, next: Next<_>| {
+///     let reports = reports.clone();
+///     async move {
+///         match reports.acquire().await {
+///             // Held until the handler finishes
+///             Ok(_permit) => next.run(req).await,
+///             Err(saturated) => saturated.into_response().into_response(),
+///         }
+///     }
+/// })));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Concurrency {
+    slots: Arc<Semaphore>,
+    saturation: Saturation,
+}
+
+/// Returned when no slot could be acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Saturated;
+
+impl Saturated {
+    /// A `503 Service Unavailable` problem+json response.
+    pub fn into_response(self) -> Response<String> {
+        Problem::response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many concurrent requests, try again later".to_string(),
+        )
+    }
+}
+
+impl Concurrency {
+    pub fn new(limit: usize, saturation: Saturation) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(limit)),
+            saturation,
+        }
+    }
+
+    /// Take a slot. The slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, Saturated> {
+        match self.saturation {
+            Saturation::Reject => self
+                .slots
+                .clone()
+                .try_acquire_owned()
+                .map_err(|_| Saturated),
+            Saturation::Queue(timeout) => {
+                match tokio::time::timeout(timeout, self.slots.clone().acquire_owned()).await {
+                    Ok(Ok(permit)) => Ok(permit),
+                    _ => Err(Saturated),
+                }
+            }
+        }
+    }
+
+    /// The amount of slots currently free.
+    pub fn available(&self) -> usize {
+        self.slots.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_over_limit() {
+        let limit = Concurrency::new(2, Saturation::Reject);
+
+        let first = limit.acquire().await.unwrap();
+        let _second = limit.acquire().await.unwrap();
+        assert_eq!(limit.acquire().await.unwrap_err(), Saturated);
+
+        drop(first);
+        assert!(limit.acquire().await.is_ok());
+
+        let res = Saturated.into_response();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn queues_over_limit() {
+        let limit = Concurrency::new(2, Saturation::Queue(Duration::from_millis(200)));
+
+        let first = limit.acquire().await.unwrap();
+        let _second = limit.acquire().await.unwrap();
+
+        let queued = tokio::spawn({
+            let limit = limit.clone();
+            async move { limit.acquire().await.map(|_| ()) }
+        });
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!queued.is_finished());
+        drop(first);
+        assert_eq!(queued.await.unwrap(), Ok(()));
+
+        // Times out while both slots are held
+        let _third = limit.acquire().await.unwrap();
+        let start = std::time::Instant::now();
+        assert_eq!(limit.acquire().await.unwrap_err(), Saturated);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
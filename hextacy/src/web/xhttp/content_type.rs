@@ -0,0 +1,127 @@
+use super::payload::Problem;
+use http::{header, Request, Response, StatusCode};
+use mime::Mime;
+
+/// Checks requests declare the media type an endpoint extracts before their body is read, rejecting
+/// others with a `415 Unsupported Media Type` `application/problem+json` response.
+///
+/// Only the essence of the `Content-Type` is compared, so parameters such as `charset` are ignored.
+/// Structured syntax suffixes are accepted, e.g. `application/vnd.api+json` for `application/json`.
+///
+/// ### Example
+///
+/// ```ignore
+/// const JSON: RequireContentType = RequireContentType::json();
+///
+/// async fn require_json<B>(req: Request<B>, next: Next<B>) -> Response {
+///     match JSON.check(&req) {
+///         Some(res) => res.into_response(),
+///         None => next.run(req).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequireContentType {
+    kind: &'static str,
+    subtype: &'static str,
+}
+
+impl RequireContentType {
+    /// `kind` and `subtype` are the two parts of the media type, e.g. `application` and `json`.
+    pub const fn new(kind: &'static str, subtype: &'static str) -> Self {
+        Self { kind, subtype }
+    }
+
+    pub const fn json() -> Self {
+        Self::new("application", "json")
+    }
+
+    /// Whether the media type is acceptable.
+    pub fn matches(&self, content_type: &Mime) -> bool {
+        content_type.type_() == self.kind
+            && (content_type.subtype() == self.subtype
+                || content_type.suffix().is_some_and(|s| s == self.subtype))
+    }
+
+    /// Returns the 415 response if the request's `Content-Type` is missing or doesn't match,
+    /// in which case it should be returned to the client immediately.
+    pub fn check<B>(&self, req: &Request<B>) -> Option<Response<String>> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+
+        let detail = match content_type {
+            Some(ct) => match ct.parse::<Mime>() {
+                Ok(mime) if self.matches(&mime) => return None,
+                _ => format!(
+                    "Expected Content-Type {}/{}, got {ct}",
+                    self.kind, self.subtype
+                ),
+            },
+            None => format!("Expected Content-Type {}/{}", self.kind, self.subtype),
+        };
+
+        Some(Problem::response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            detail,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content_type: Option<&str>) -> Request<()> {
+        let mut req = Request::post("/auth/login");
+        if let Some(ct) = content_type {
+            req = req.header(header::CONTENT_TYPE, ct);
+        }
+        req.body(()).unwrap()
+    }
+
+    #[test]
+    fn accepts_json() {
+        let json = RequireContentType::json();
+        assert!(json.check(&request(Some("application/json"))).is_none());
+        assert!(json
+            .check(&request(Some("application/vnd.api+json")))
+            .is_none());
+    }
+
+    #[test]
+    fn tolerates_charset() {
+        let json = RequireContentType::json();
+        assert!(json
+            .check(&request(Some("application/json; charset=utf-8")))
+            .is_none());
+        assert!(json
+            .check(&request(Some("Application/JSON;charset=UTF-8")))
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_other_types() {
+        let json = RequireContentType::json();
+
+        let res = json
+            .check(&request(Some("application/x-www-form-urlencoded")))
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body: serde_json::Value = serde_json::from_str(res.body()).unwrap();
+        assert_eq!(
+            body["detail"],
+            "Expected Content-Type application/json, got application/x-www-form-urlencoded"
+        );
+
+        for ct in [None, Some("text/json"), Some("not a mime")] {
+            let res = json.check(&request(ct)).unwrap();
+            assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE, "{ct:?}");
+        }
+    }
+}
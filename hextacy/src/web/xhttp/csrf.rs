@@ -0,0 +1,227 @@
+use super::payload::Problem;
+use crate::crypto::token;
+use cookie::{Cookie, CookieBuilder, SameSite};
+use data_encoding::BASE64URL_NOPAD;
+use http::{header, Request, Response, StatusCode};
+use thiserror::Error;
+
+/// The name of the hidden input carrying the token.
+pub const CSRF_FIELD: &str = "csrf_token";
+
+/// The name of the cookie carrying the token.
+pub const CSRF_COOKIE: &str = "X_FORM_CSRF";
+
+/// Amount of random bytes in a token.
+const TOKEN_LENGTH: usize = 32;
+
+/// CSRF protection for server-rendered HTML forms using the double submit pattern.
+///
+/// The token is sent to the client both as a cookie and as a hidden input of every POST form on the page.
+/// Other sites can make the browser submit a form with the cookie, but cannot read it to fill in the input,
+/// so submissions are only accepted if the two [match][verify_form].
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn reset_password_page() -> Response {
+///     let csrf = CsrfForm::new();
+///     Response::builder()
+///         .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+///         .header(header::SET_COOKIE, csrf.cookie().to_string())
+///         .body(csrf.inject(RESET_PASSWORD_HTML))
+/// }
+///
+/// async fn reset_password(req: Request<Body>) -> Response {
+///     let (parts, body) = req.into_parts();
+///     let body = hyper::body::to_bytes(body).await?;
+///     if let Err(e) = verify_form(&Request::from_parts(parts, ()), &body) {
+///         return e.into_response().into_response();
+///     }
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfForm {
+    token: String,
+}
+
+impl Default for CsrfForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsrfForm {
+    /// Generates a new random token.
+    pub fn new() -> Self {
+        Self {
+            token: token(BASE64URL_NOPAD, TOKEN_LENGTH),
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The hidden input carrying the token. The token is URL safe base64, so it needs no escaping.
+    pub fn hidden_input(&self) -> String {
+        format!(
+            r#"<input type="hidden" name="{CSRF_FIELD}" value="{}">"#,
+            self.token
+        )
+    }
+
+    /// Inserts the [hidden input][CsrfForm::hidden_input] at the start of every POST form in the HTML.
+    /// GET forms are left alone, since their inputs end up in URLs and would leak the token.
+    pub fn inject(&self, html: &str) -> String {
+        let input = self.hidden_input();
+        let lowercase = html.to_ascii_lowercase();
+        let mut out = String::with_capacity(html.len() + input.len());
+        let mut copied = 0;
+
+        for (start, _) in lowercase.match_indices("<form") {
+            let Some(end) = lowercase[start..].find('>').map(|end| start + end + 1) else {
+                break;
+            };
+            if !is_post(&lowercase[start..end]) {
+                continue;
+            }
+            out.push_str(&html[copied..end]);
+            out.push_str(&input);
+            copied = end;
+        }
+
+        out.push_str(&html[copied..]);
+        out
+    }
+
+    /// The cookie carrying the token, to set on the response rendering the form. It is not needed by scripts,
+    /// so it is HTTP only.
+    pub fn cookie(&self) -> Cookie<'static> {
+        CookieBuilder::new(CSRF_COOKIE, self.token.clone())
+            .path("/")
+            .same_site(SameSite::Strict)
+            .http_only(true)
+            .secure(true)
+            .finish()
+    }
+}
+
+/// Whether the opening form tag submits with POST, from its `method` attribute.
+fn is_post(tag: &str) -> bool {
+    tag.split_once("method")
+        .and_then(|(_, rest)| rest.trim_start().strip_prefix('='))
+        .map(|value| value.trim_start().trim_start_matches(['"', '\'']))
+        .is_some_and(|value| value.starts_with("post"))
+}
+
+/// Checks the token of a submitted `application/x-www-form-urlencoded` body matches the one in the
+/// request's [cookie][CsrfForm::cookie].
+pub fn verify_form<B>(req: &Request<B>, body: &[u8]) -> Result<(), CsrfError> {
+    let cookie = req
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(Result::ok)
+        .find(|cookie| cookie.name() == CSRF_COOKIE)
+        .ok_or(CsrfError::MissingCookie)?;
+
+    let submitted = String::from_utf8_lossy(body);
+    let field = submitted
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == CSRF_FIELD).then_some(value))
+        .ok_or(CsrfError::MissingToken)?;
+
+    if cookie.value().is_empty() || !constant_time_eq(cookie.value().as_bytes(), field.as_bytes()) {
+        return Err(CsrfError::Mismatch);
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CsrfError {
+    #[error("Missing CSRF cookie")]
+    MissingCookie,
+    #[error("Missing CSRF token")]
+    MissingToken,
+    #[error("Invalid CSRF token")]
+    Mismatch,
+}
+
+impl CsrfError {
+    /// A `403 Forbidden` problem+json response.
+    pub fn into_response(self) -> Response<String> {
+        Problem::response(StatusCode::FORBIDDEN, self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<h1>Reset password</h1>
+<FORM action="/auth/reset-password" METHOD="POST"><input name="password"></FORM>
+<form action="/search" method="get"><input name="q"></form>"#;
+
+    fn submit(cookie: Option<&str>, body: &str) -> Result<(), CsrfError> {
+        let mut req = Request::post("/auth/reset-password");
+        if let Some(cookie) = cookie {
+            req = req.header(header::COOKIE, cookie);
+        }
+        verify_form(&req.body(()).unwrap(), body.as_bytes())
+    }
+
+    #[test]
+    fn form_round_trip() {
+        let csrf = CsrfForm::new();
+        let html = csrf.inject(PAGE);
+
+        // Only the POST form gets the token
+        assert_eq!(html.matches(csrf.token()).count(), 1);
+        assert!(html.contains(&format!(
+            r#"METHOD="POST">{}<input name="password">"#,
+            csrf.hidden_input()
+        )));
+        assert!(html.ends_with(r#"<form action="/search" method="get"><input name="q"></form>"#));
+
+        let cookie = csrf.cookie();
+        assert!(cookie.http_only().unwrap());
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+
+        let header = format!("theme=dark; {}", cookie.stripped());
+        let body = format!("password=hunter2&{CSRF_FIELD}={}", csrf.token());
+        assert_eq!(submit(Some(&header), &body), Ok(()));
+    }
+
+    #[test]
+    fn rejects_missing_and_invalid_tokens() {
+        let csrf = CsrfForm::new();
+        let header = csrf.cookie().stripped().to_string();
+
+        assert_eq!(
+            submit(Some(&header), "password=hunter2"),
+            Err(CsrfError::MissingToken)
+        );
+        assert_eq!(
+            submit(None, &format!("{CSRF_FIELD}={}", csrf.token())),
+            Err(CsrfError::MissingCookie)
+        );
+
+        let other = CsrfForm::new();
+        let err = submit(Some(&header), &format!("{CSRF_FIELD}={}", other.token())).unwrap_err();
+        assert_eq!(err, CsrfError::Mismatch);
+        assert_eq!(err.into_response().status(), StatusCode::FORBIDDEN);
+
+        assert_eq!(
+            submit(Some(&format!("{CSRF_COOKIE}=")), &format!("{CSRF_FIELD}=")),
+            Err(CsrfError::Mismatch)
+        );
+    }
+}
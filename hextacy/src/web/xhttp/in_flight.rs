@@ -0,0 +1,164 @@
+use super::payload::Problem;
+use crate::adapters::cache::{
+    lock::{acquire_lock, release_lock},
+    CacheError, SimpleCacheAccess,
+};
+use http::{Response, StatusCode};
+use std::{future::Future, time::Duration};
+use thiserror::Error;
+use tracing::warn;
+
+/// Rejects duplicate requests, e.g. two concurrent "create order" clicks, by running a user's request
+/// to an endpoint only when no other request of theirs to the same endpoint is being processed.
+///
+/// The in-flight lock is held in the cache, so duplicates are rejected across instances. It is released
+/// once the handler completes, or after the `ttl` if the instance processing it dies before that.
+///
+/// ### Example
+///
+/// ```ignore
+/// const ORDERS: InFlight = InFlight::new(Duration::from_secs(30));
+///
+/// async fn create_order(State(state): State<AppState>, session: Session, Json(order): Json<NewOrder>) -> Response {
+///     let mut cache = state.cache.connect().await?;
+///     match ORDERS.run(&mut cache, "orders:create", &session.user_id, state.orders.create(order)).await {
+///         Ok(res) => res.into_response(),
+///         Err(e) => e.into_response().into_response(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InFlight {
+    ttl: Duration,
+}
+
+impl InFlight {
+    /// `ttl` should comfortably exceed the time the endpoint takes to respond.
+    pub const fn new(ttl: Duration) -> Self {
+        Self { ttl }
+    }
+
+    /// The cache key of the in-flight lock for the user's requests to the endpoint.
+    pub fn key(endpoint: &str, user: &str) -> String {
+        format!("in_flight:{endpoint}:{user}")
+    }
+
+    /// Run `handler` if the user has no other request to the endpoint in flight,
+    /// otherwise return [InFlightError::Duplicate] without running it.
+    pub async fn run<C, F>(
+        &self,
+        cache: &mut C,
+        endpoint: &str,
+        user: &str,
+        handler: F,
+    ) -> Result<F::Output, InFlightError>
+    where
+        C: SimpleCacheAccess,
+        F: Future,
+    {
+        let key = Self::key(endpoint, user);
+        let Some(token) = acquire_lock(cache, &key, self.ttl).await? else {
+            return Err(InFlightError::Duplicate);
+        };
+
+        let output = handler.await;
+
+        // The handler already ran, so its output is returned regardless and the lock expires on its own
+        if let Err(e) = release_lock(cache, &key, &token).await {
+            warn!("Failed to release in-flight lock {key}: {e}");
+        }
+
+        Ok(output)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InFlightError {
+    #[error("A duplicate request is already being processed")]
+    Duplicate,
+    #[error("Cache: {0}")]
+    Cache(#[from] CacheError),
+}
+
+impl InFlightError {
+    /// A `409 Conflict` problem+json response for duplicates and a `500 Internal Server Error` one otherwise.
+    pub fn into_response(self) -> Response<String> {
+        match self {
+            Self::Duplicate => Problem::response(StatusCode::CONFLICT, self.to_string()),
+            Self::Cache(_) => Problem::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::cache::tests::MapCache;
+    use tokio::sync::oneshot;
+
+    const ORDERS: InFlight = InFlight::new(Duration::from_secs(30));
+
+    #[tokio::test]
+    async fn concurrent_duplicate_conflicts() {
+        let cache = MapCache::default();
+        let (started_tx, started_rx) = oneshot::channel();
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+
+        let first = tokio::spawn({
+            let mut cache = cache.clone();
+            async move {
+                ORDERS
+                    .run(&mut cache, "orders:create", "alice", async move {
+                        started_tx.send(()).unwrap();
+                        finish_rx.await.unwrap();
+                        "created"
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        let err = ORDERS
+            .run(&mut cache.clone(), "orders:create", "alice", async {
+                unreachable!("duplicates never run")
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InFlightError::Duplicate));
+        assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+
+        // Other users and endpoints are unaffected
+        let other = ORDERS
+            .run(&mut cache.clone(), "orders:create", "bob", async {
+                "created"
+            })
+            .await;
+        assert_eq!(other.unwrap(), "created");
+        let other = ORDERS
+            .run(&mut cache.clone(), "orders:cancel", "alice", async {
+                "cancelled"
+            })
+            .await;
+        assert_eq!(other.unwrap(), "cancelled");
+
+        finish_tx.send(()).unwrap();
+        assert_eq!(first.await.unwrap().unwrap(), "created");
+    }
+
+    #[tokio::test]
+    async fn releases_on_completion() {
+        let mut cache = MapCache::default();
+
+        for _ in 0..2 {
+            let res = ORDERS
+                .run(&mut cache, "orders:create", "alice", async { "created" })
+                .await;
+            assert_eq!(res.unwrap(), "created");
+        }
+
+        assert!(cache.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use super::payload::Problem;
+use http::{Response, StatusCode};
+
+/// The default maximum nesting depth, well below `serde_json`'s recursion limit.
+pub const DEFAULT_MAX_JSON_DEPTH: usize = 32;
+
+/// Rejects JSON bodies nested deeper than the configured maximum with a `400 Bad Request`
+/// `application/problem+json` response, before they are deserialized.
+///
+/// The body is only scanned for brackets outside of strings, so it is cheap compared to parsing and
+/// stops at the first bracket exceeding the limit. Malformed JSON is left for the extractor to reject.
+///
+/// ### Example
+///
+/// ```ignore
+/// const DEPTH: JsonDepth = JsonDepth::new(16);
+///
+/// async fn limit_json_depth(req: Request<Body>, next: Next<Body>) -> Response {
+///     let (parts, body) = req.into_parts();
+///     let bytes = hyper::body::to_bytes(body).await.unwrap();
+///     match DEPTH.check(&bytes) {
+///         Some(res) => res.into_response(),
+///         None => next.run(Request::from_parts(parts, Body::from(bytes))).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonDepth {
+    max: usize,
+}
+
+impl Default for JsonDepth {
+    fn default() -> Self {
+        Self {
+            max: DEFAULT_MAX_JSON_DEPTH,
+        }
+    }
+}
+
+impl JsonDepth {
+    pub const fn new(max: usize) -> Self {
+        Self { max }
+    }
+
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Whether the body has more than `max` nested objects or arrays.
+    pub fn exceeds(&self, body: &[u8]) -> bool {
+        let mut depth = 0_usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        for byte in body {
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => {
+                    depth += 1;
+                    if depth > self.max {
+                        return true;
+                    }
+                }
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Returns the 400 response if the body is nested too deeply, in which case it should be returned
+    /// to the client immediately.
+    pub fn check(&self, body: &[u8]) -> Option<Response<String>> {
+        self.exceeds(body).then(|| {
+            Problem::response(
+                StatusCode::BAD_REQUEST,
+                format!("JSON exceeds the maximum nesting depth of {}", self.max),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header;
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn within_limit() {
+        let depth = JsonDepth::new(4);
+        assert!(depth.check(nested(4).as_bytes()).is_none());
+        assert!(depth
+            .check(br#"{"user": {"name": "alice", "roles": [{"id": 1}, {"id": 2}]}}"#)
+            .is_none());
+        assert!(depth.check(b"").is_none());
+
+        // Brackets in strings are not nesting
+        assert!(depth
+            .check(br#"{"bio": "[[[[[{{{{{ \"[[[[[\" "}"#)
+            .is_none());
+    }
+
+    #[test]
+    fn exceeding_limit() {
+        let depth = JsonDepth::new(4);
+        let res = depth.check(nested(5).as_bytes()).unwrap();
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body: serde_json::Value = serde_json::from_str(res.body()).unwrap();
+        assert_eq!(
+            body["detail"],
+            "JSON exceeds the maximum nesting depth of 4"
+        );
+
+        // Rejected without parsing the rest of the body
+        let mut truncated = nested(100);
+        truncated.truncate(60);
+        assert!(JsonDepth::default().exceeds(truncated.as_bytes()));
+    }
+}
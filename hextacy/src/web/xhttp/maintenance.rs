@@ -0,0 +1,140 @@
+use super::payload::Problem;
+use http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Puts the API into read-only mode, e.g. during deploys. While enabled, requests with methods other
+/// than the allowed ones (`GET`, `HEAD` and `OPTIONS` by default) are answered with a
+/// `503 Service Unavailable` and a `Retry-After` header.
+///
+/// The flag can be initialised from the environment and toggled at runtime. Clones share the flag,
+/// so a clone can be handed to an admin endpoint.
+///
+/// ### Example
+///
+/// ```ignore
+/// let maintenance = MaintenanceMode::from_env("MAINTENANCE_MODE", Duration::from_secs(120));
+///
+/// router
+///     .route("/admin/maintenance", put({
+///         let maintenance = maintenance.clone();
+///         move |Json(enabled): Json<bool>| async move { maintenance.set(enabled) }
+///     }))
+///     .layer(middleware::from_fn(move |req: Request<Body>, next: Next<Body>| {
+///         let maintenance = maintenance.clone();
+///         async move {
+///             match maintenance.check(&req) {
+///                 Some(res) => res.into_response(),
+///                 None => next.run(req).await,
+///             }
+///         }
+///     }));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    enabled: Arc<AtomicBool>,
+    retry_after: Duration,
+    allowed: Vec<Method>,
+}
+
+impl MaintenanceMode {
+    pub fn new(enabled: bool, retry_after: Duration) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+            retry_after,
+            allowed: vec![Method::GET, Method::HEAD, Method::OPTIONS],
+        }
+    }
+
+    /// Enabled if the variable is set to `true` or `1`.
+    pub fn from_env(key: &str, retry_after: Duration) -> Self {
+        let enabled = crate::env::get(key).is_ok_and(|value| {
+            let value = value.trim();
+            value == "1" || value.eq_ignore_ascii_case("true")
+        });
+        Self::new(enabled, retry_after)
+    }
+
+    /// Set the methods that are let through while in maintenance.
+    pub fn allow(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.allowed = methods.into_iter().collect();
+        self
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns a `503 Service Unavailable` response if the request is not allowed through,
+    /// in which case it should be returned to the client immediately.
+    pub fn check<B>(&self, req: &Request<B>) -> Option<Response<String>> {
+        if !self.is_enabled() || self.allowed.contains(req.method()) {
+            return None;
+        }
+
+        let mut res = Problem::response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The service is under maintenance, try again later".to_string(),
+        );
+        res.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(self.retry_after.as_secs()),
+        );
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: Method) -> Request<()> {
+        Request::builder()
+            .method(method)
+            .uri("/users")
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn toggling_blocks_mutations() {
+        let maintenance = MaintenanceMode::new(false, Duration::from_secs(120));
+        let admin = maintenance.clone();
+
+        assert!(maintenance.check(&request(Method::POST)).is_none());
+
+        admin.set(true);
+        let res = maintenance.check(&request(Method::POST)).unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "120");
+        assert!(maintenance.check(&request(Method::DELETE)).is_some());
+        assert!(maintenance.check(&request(Method::GET)).is_none());
+
+        admin.set(false);
+        assert!(maintenance.check(&request(Method::POST)).is_none());
+    }
+
+    #[test]
+    fn allowed_methods_and_env() {
+        std::env::set_var("MAINTENANCE_MODE_TEST", "true");
+        let maintenance =
+            MaintenanceMode::from_env("MAINTENANCE_MODE_TEST", Duration::from_secs(5))
+                .allow([Method::OPTIONS]);
+        assert!(maintenance.is_enabled());
+        assert!(maintenance.check(&request(Method::GET)).is_some());
+        assert!(maintenance.check(&request(Method::OPTIONS)).is_none());
+
+        let maintenance =
+            MaintenanceMode::from_env("MAINTENANCE_MODE_UNSET", Duration::from_secs(5));
+        assert!(!maintenance.is_enabled());
+    }
+}
@@ -0,0 +1,162 @@
+use http::{header, uri::PathAndQuery, Request, Response, StatusCode, Uri};
+
+/// Determines how [NormalizePath] treats request paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathMode {
+    /// Merge consecutive slashes, leaving the trailing slash as is, i.e. `//users//` becomes `/users/`.
+    Merge,
+
+    /// Merge consecutive slashes and remove the trailing slash, i.e. `//users//` becomes `/users`.
+    #[default]
+    Trim,
+
+    /// Do not touch the request. If its path is not in the trimmed form, respond with a
+    /// `308 Permanent Redirect` to it instead.
+    Redirect,
+}
+
+/// Normalizes request paths before they reach the router so that `/users/` and `/users` end up
+/// at the same handler.
+///
+/// This is framework agnostic and intended to be called from whatever the framework uses
+/// for middleware, before routing takes place.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn normalize<B>(mut req: Request<B>, next: Next<B>) -> Response {
+///     match NormalizePath::new(PathMode::Trim).normalize(&mut req) {
+///         Some(redirect) => redirect.into_response(),
+///         None => next.run(req).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalizePath {
+    mode: PathMode,
+}
+
+impl NormalizePath {
+    pub fn new(mode: PathMode) -> Self {
+        Self { mode }
+    }
+
+    /// Normalize the request's path according to the configured mode. Returns `Some` with a redirect
+    /// response only in [PathMode::Redirect] when the path is not already normalized, in which case the
+    /// response should be returned to the client immediately.
+    pub fn normalize<B>(&self, req: &mut Request<B>) -> Option<Response<()>> {
+        let path = req.uri().path();
+        let normalized = normalize_path(path, self.mode != PathMode::Merge);
+
+        if normalized == path {
+            return None;
+        }
+
+        let target = match req.uri().query() {
+            Some(query) => format!("{normalized}?{query}"),
+            None => normalized,
+        };
+
+        if self.mode == PathMode::Redirect {
+            return Response::builder()
+                .status(StatusCode::PERMANENT_REDIRECT)
+                .header(header::LOCATION, target)
+                .body(())
+                .ok();
+        }
+
+        let Ok(path_and_query) = PathAndQuery::try_from(target) else {
+            return None;
+        };
+
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = Some(path_and_query);
+
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *req.uri_mut() = uri;
+        }
+
+        None
+    }
+}
+
+/// Merges consecutive slashes in the path and optionally removes the trailing one.
+/// The root path is always kept as `/`.
+pub fn normalize_path(path: &str, trim: bool) -> String {
+    let mut normalized = String::with_capacity(path.len() + 1);
+
+    if !path.starts_with('/') {
+        normalized.push('/');
+    }
+
+    for c in path.chars() {
+        if c == '/' && normalized.ends_with('/') {
+            continue;
+        }
+        normalized.push(c);
+    }
+
+    if trim && normalized.len() > 1 && normalized.ends_with('/') {
+        normalized.pop();
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str) -> Request<()> {
+        Request::builder().uri(uri).body(()).unwrap()
+    }
+
+    #[test]
+    fn trim() {
+        let normalize = NormalizePath::new(PathMode::Trim);
+
+        let mut req = request("/users/");
+        assert!(normalize.normalize(&mut req).is_none());
+        assert_eq!(req.uri().path(), "/users");
+
+        let mut req = request("http://localhost:3000//users//?page=1");
+        assert!(normalize.normalize(&mut req).is_none());
+        assert_eq!(req.uri().path(), "/users");
+        assert_eq!(req.uri().query(), Some("page=1"));
+        assert_eq!(req.uri().host(), Some("localhost"));
+
+        let mut req = request("/");
+        assert!(normalize.normalize(&mut req).is_none());
+        assert_eq!(req.uri().path(), "/");
+    }
+
+    #[test]
+    fn merge() {
+        let normalize = NormalizePath::new(PathMode::Merge);
+
+        let mut req = request("//users///");
+        assert!(normalize.normalize(&mut req).is_none());
+        assert_eq!(req.uri().path(), "/users/");
+
+        let mut req = request("/users");
+        assert!(normalize.normalize(&mut req).is_none());
+        assert_eq!(req.uri().path(), "/users");
+    }
+
+    #[test]
+    fn redirect() {
+        let normalize = NormalizePath::new(PathMode::Redirect);
+
+        let mut req = request("/users/?page=1");
+        let res = normalize.normalize(&mut req).unwrap();
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "/users?page=1"
+        );
+        assert_eq!(req.uri().path(), "/users/");
+
+        let mut req = request("/users");
+        assert!(normalize.normalize(&mut req).is_none());
+    }
+}
@@ -0,0 +1,504 @@
+use super::payload::Problem;
+use http::{Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize, Serializer};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// What happens to pagination parameters that are numbers, but out of bounds, i.e. a `page` of `0`
+/// or a `per_page` above the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfBounds {
+    /// Respond with a `400 Bad Request`.
+    #[default]
+    Reject,
+
+    /// Clamp them to the nearest valid value.
+    Coerce,
+}
+
+/// Bounds for pagination parameters coming from clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaginationPolicy {
+    pub default_per_page: u32,
+    pub max_per_page: u32,
+    pub out_of_bounds: OutOfBounds,
+}
+
+impl Default for PaginationPolicy {
+    fn default() -> Self {
+        Self {
+            default_per_page: 25,
+            max_per_page: 100,
+            out_of_bounds: OutOfBounds::Reject,
+        }
+    }
+}
+
+/// Validated `page` and `per_page` parameters. Pages start at 1.
+///
+/// Can only be obtained through the constructors, so both parameters are always at least 1.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn list_users(RawQuery(query): RawQuery) -> Response {
+///     let paginator = match Paginator::from_query(query.as_deref(), &PaginationPolicy::default()) {
+///         Ok(paginator) => paginator,
+///         Err(e) => return e.into_response().into_response(),
+///     };
+///     let users = repo.list(paginator.offset(), paginator.limit()).await?;
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paginator {
+    page: u32,
+    per_page: u32,
+}
+
+impl Paginator {
+    /// Reads `page` and `per_page` from the query string, using the first page and the policy's
+    /// default page size when they are absent. Keys and values are percent-decoded. Values that are not
+    /// non-negative integers, such as `-1`, are always rejected. Out of bound values are handled according to the policy.
+    pub fn from_query(
+        query: Option<&str>,
+        policy: &PaginationPolicy,
+    ) -> Result<Self, PaginationError> {
+        let mut page = None;
+        let mut per_page = None;
+
+        for (key, value) in query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+        {
+            let value = percent_decode_str(value).decode_utf8_lossy();
+            match percent_decode_str(key).decode_utf8_lossy().as_ref() {
+                "page" => page = Some(parse("page", &value)?),
+                "per_page" => per_page = Some(parse("per_page", &value)?),
+                _ => {}
+            }
+        }
+
+        Self::new(
+            page.unwrap_or(1),
+            per_page.unwrap_or(policy.default_per_page),
+            policy,
+        )
+    }
+
+    /// Checks the bounds of already parsed parameters.
+    pub fn new(
+        page: u32,
+        per_page: u32,
+        policy: &PaginationPolicy,
+    ) -> Result<Self, PaginationError> {
+        let max = policy.max_per_page.max(1);
+
+        match policy.out_of_bounds {
+            OutOfBounds::Reject => {
+                if page == 0 {
+                    return Err(PaginationError::ZeroPage);
+                }
+                if per_page == 0 || per_page > max {
+                    return Err(PaginationError::PerPageOutOfBounds { max });
+                }
+                Ok(Self { page, per_page })
+            }
+            OutOfBounds::Coerce => Ok(Self {
+                page: page.max(1),
+                per_page: per_page.clamp(1, max),
+            }),
+        }
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+    }
+
+    /// The amount of rows to skip.
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+
+    /// The amount of rows to take.
+    pub fn limit(&self) -> u64 {
+        u64::from(self.per_page)
+    }
+}
+
+/// Raw `page` and `per_page` query parameters, for frameworks which deserialize the query string
+/// themselves, i.e. axum's `Query` extractor. Malformed values are rejected by the extractor.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn list_users(Query(query): Query<PaginationQuery>) -> Response {
+///     let paginator = match query.paginate(&POLICY) {
+///         Ok(paginator) => paginator,
+///         Err(e) => return e.into_response().into_response(),
+///     };
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub struct PaginationQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl PaginationQuery {
+    /// Applies the defaults and bounds of the policy, like [Paginator::from_query].
+    pub fn paginate(&self, policy: &PaginationPolicy) -> Result<Paginator, PaginationError> {
+        Paginator::new(
+            self.page.unwrap_or(1),
+            self.per_page.unwrap_or(policy.default_per_page),
+            policy,
+        )
+    }
+}
+
+fn parse(param: &'static str, value: &str) -> Result<u32, PaginationError> {
+    value.parse().map_err(|_| PaginationError::Invalid {
+        param,
+        value: value.to_string(),
+    })
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaginationError {
+    #[error("Invalid {param}: {value:?} is not a non-negative integer")]
+    Invalid { param: &'static str, value: String },
+    #[error("Pages start at 1")]
+    ZeroPage,
+    #[error("per_page must be between 1 and {max}")]
+    PerPageOutOfBounds { max: u32 },
+}
+
+impl PaginationError {
+    /// A `400 Bad Request` problem+json response.
+    pub fn into_response(self) -> Response<String> {
+        Problem::response(StatusCode::BAD_REQUEST, self.to_string())
+    }
+}
+
+/// A page of results along with the total amount of them. Serializes into the shape given by `F`,
+/// so the same repository output can be served to clients expecting different shapes.
+///
+/// ### Example
+///
+/// ```ignore
+/// let page = Paginated::<_>::new(users, total, paginator);
+/// let body = match format {
+///     Format::Offset => serde_json::to_string(&page)?,
+///     Format::Relay => serde_json::to_string(&page.format::<RelayFormat>())?,
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paginated<T, F = OffsetFormat> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub paginator: Paginator,
+    format: PhantomData<F>,
+}
+
+impl<T, F> Paginated<T, F> {
+    pub fn new(items: Vec<T>, total: u64, paginator: Paginator) -> Self {
+        Self {
+            items,
+            total,
+            paginator,
+            format: PhantomData,
+        }
+    }
+
+    /// Serialize through a different format.
+    pub fn format<G>(self) -> Paginated<T, G> {
+        Paginated::new(self.items, self.total, self.paginator)
+    }
+
+    /// Whether there are items past this page.
+    pub fn has_next(&self) -> bool {
+        self.paginator.offset() + (self.items.len() as u64) < self.total
+    }
+
+    /// Whether there are items before this page.
+    pub fn has_previous(&self) -> bool {
+        self.paginator.offset() > 0
+    }
+
+    /// The amount of pages needed for all the items at the current page size.
+    pub fn total_pages(&self) -> u64 {
+        self.total.div_ceil(self.paginator.limit().max(1))
+    }
+}
+
+impl<T, F> Serialize for Paginated<T, F>
+where
+    T: Serialize,
+    F: PaginationFormat,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        F::serialize(self, serializer)
+    }
+}
+
+/// The shape a [Paginated] response is serialized into.
+pub trait PaginationFormat: Sized {
+    fn serialize<T, S>(page: &Paginated<T, Self>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer;
+}
+
+/// `{ "items": [..], "page": 2, "per_page": 10, "total": 42, "total_pages": 5 }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OffsetFormat;
+
+impl PaginationFormat for OffsetFormat {
+    fn serialize<T, S>(page: &Paginated<T, Self>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Offset<'a, T> {
+            items: &'a [T],
+            page: u32,
+            per_page: u32,
+            total: u64,
+            total_pages: u64,
+        }
+
+        Offset {
+            items: &page.items,
+            page: page.paginator.page(),
+            per_page: page.paginator.per_page(),
+            total: page.total,
+            total_pages: page.total_pages(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Relay style connection, `{ "data": [..], "page_info": { .. } }`.
+///
+/// Cursors are the offsets of the first and last item, so clients can page from either end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RelayFormat;
+
+impl PaginationFormat for RelayFormat {
+    fn serialize<T, S>(page: &Paginated<T, Self>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Relay<'a, T> {
+            data: &'a [T],
+            page_info: PageInfo,
+        }
+
+        #[derive(Serialize)]
+        struct PageInfo {
+            has_next_page: bool,
+            has_previous_page: bool,
+            start_cursor: Option<String>,
+            end_cursor: Option<String>,
+            total_count: u64,
+        }
+
+        let offset = page.paginator.offset();
+        let last = (page.items.len() as u64).checked_sub(1);
+
+        Relay {
+            data: &page.items,
+            page_info: PageInfo {
+                has_next_page: page.has_next(),
+                has_previous_page: page.has_previous(),
+                start_cursor: last.map(|_| offset.to_string()),
+                end_cursor: last.map(|last| (offset + last).to_string()),
+                total_count: page.total,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COERCE: PaginationPolicy = PaginationPolicy {
+        default_per_page: 25,
+        max_per_page: 100,
+        out_of_bounds: OutOfBounds::Coerce,
+    };
+
+    #[test]
+    fn valid_input() {
+        let policy = PaginationPolicy::default();
+
+        let paginator =
+            Paginator::from_query(Some("page=3&per_page=10&sort=name"), &policy).unwrap();
+        assert_eq!(
+            paginator,
+            Paginator {
+                page: 3,
+                per_page: 10
+            }
+        );
+        assert_eq!(paginator.offset(), 20);
+        assert_eq!(paginator.limit(), 10);
+
+        let paginator = Paginator::from_query(None, &policy).unwrap();
+        assert_eq!(
+            paginator,
+            Paginator {
+                page: 1,
+                per_page: 25
+            }
+        );
+        assert_eq!(paginator.offset(), 0);
+    }
+
+    #[test]
+    fn percent_encoded_query() {
+        let paginator = Paginator::from_query(
+            Some("page=%33&per%5Fpage=1%30"),
+            &PaginationPolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            paginator,
+            Paginator {
+                page: 3,
+                per_page: 10
+            }
+        );
+
+        let err =
+            Paginator::from_query(Some("page=%2D1"), &PaginationPolicy::default()).unwrap_err();
+        assert_eq!(
+            err,
+            PaginationError::Invalid {
+                param: "page",
+                value: "-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn zero_page() {
+        let err = Paginator::from_query(Some("page=0"), &PaginationPolicy::default()).unwrap_err();
+        assert_eq!(err, PaginationError::ZeroPage);
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let paginator = Paginator::from_query(Some("page=0"), &COERCE).unwrap();
+        assert_eq!(paginator.page, 1);
+    }
+
+    #[test]
+    fn overlarge_per_page() {
+        let err = Paginator::from_query(Some("per_page=100000"), &PaginationPolicy::default())
+            .unwrap_err();
+        assert_eq!(err, PaginationError::PerPageOutOfBounds { max: 100 });
+
+        let paginator = Paginator::from_query(Some("per_page=100000"), &COERCE).unwrap();
+        assert_eq!(paginator.per_page, 100);
+        let paginator = Paginator::from_query(Some("per_page=0"), &COERCE).unwrap();
+        assert_eq!(paginator.per_page, 1);
+    }
+
+    #[test]
+    fn deserialized_query_clamps_per_page() {
+        let query: PaginationQuery = serde_json::from_str(r#"{ "per_page": 500 }"#).unwrap();
+        let paginator = query.paginate(&COERCE).unwrap();
+        assert_eq!(
+            paginator,
+            Paginator {
+                page: 1,
+                per_page: 100
+            }
+        );
+
+        assert_eq!(
+            query.paginate(&PaginationPolicy::default()),
+            Err(PaginationError::PerPageOutOfBounds { max: 100 })
+        );
+
+        let paginator = PaginationQuery::default().paginate(&COERCE).unwrap();
+        assert_eq!(paginator.per_page, 25);
+    }
+
+    #[test]
+    fn malformed_values_are_always_rejected() {
+        for query in ["page=-1", "per_page=-5", "page=abc", "page=99999999999"] {
+            let err = Paginator::from_query(Some(query), &COERCE).unwrap_err();
+            assert!(matches!(err, PaginationError::Invalid { .. }), "{query}");
+        }
+    }
+
+    fn page() -> Paginated<&'static str> {
+        let paginator = Paginator {
+            page: 2,
+            per_page: 2,
+        };
+        Paginated::new(vec!["c", "d"], 5, paginator)
+    }
+
+    #[test]
+    fn offset_format() {
+        let json = serde_json::to_value(page()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "items": ["c", "d"],
+                "page": 2,
+                "per_page": 2,
+                "total": 5,
+                "total_pages": 3
+            })
+        );
+    }
+
+    #[test]
+    fn relay_format() {
+        let json = serde_json::to_value(page().format::<RelayFormat>()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "data": ["c", "d"],
+                "page_info": {
+                    "has_next_page": true,
+                    "has_previous_page": true,
+                    "start_cursor": "2",
+                    "end_cursor": "3",
+                    "total_count": 5
+                }
+            })
+        );
+
+        let first = Paginator {
+            page: 1,
+            per_page: 10,
+        };
+        let empty = Paginated::<u8, RelayFormat>::new(vec![], 0, first);
+        let json = serde_json::to_value(empty).unwrap();
+        assert_eq!(
+            json["page_info"],
+            serde_json::json!({
+                "has_next_page": false,
+                "has_previous_page": false,
+                "start_cursor": null,
+                "end_cursor": null,
+                "total_count": 0
+            })
+        );
+    }
+}
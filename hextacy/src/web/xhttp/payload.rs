@@ -0,0 +1,141 @@
+use http::{header, HeaderValue, Request, Response, StatusCode};
+use serde::Serialize;
+
+/// 2 MiB
+pub const DEFAULT_MAX_PAYLOAD: usize = 2 * 1024 * 1024;
+
+/// A global cap on request body sizes that rejects oversized requests with a consistent
+/// `413 Payload Too Large` `application/problem+json` response.
+///
+/// [check][PayloadConfig::check] only inspects the `Content-Length` header, so [max][PayloadConfig::max] should also
+/// be passed to whatever the framework uses to limit reading streamed bodies.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn limit_payload<B>(req: Request<B>, next: Next<B>) -> Response {
+///     match PAYLOAD.check(&req) {
+///         Some(res) => res.into_response(),
+///         None => next.run(req).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadConfig {
+    max: usize,
+}
+
+impl Default for PayloadConfig {
+    fn default() -> Self {
+        Self {
+            max: DEFAULT_MAX_PAYLOAD,
+        }
+    }
+}
+
+impl PayloadConfig {
+    /// `max` is in bytes.
+    pub const fn new(max: usize) -> Self {
+        Self { max }
+    }
+
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns the 413 response if the request declares a body larger than the configured max,
+    /// in which case it should be returned to the client immediately.
+    pub fn check<B>(&self, req: &Request<B>) -> Option<Response<String>> {
+        let length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+
+        match length {
+            Some(length) if length > self.max => Some(self.too_large()),
+            _ => None,
+        }
+    }
+
+    /// The response used when a payload exceeds the limit. Also usable when the limit is hit while reading the body.
+    pub fn too_large(&self) -> Response<String> {
+        Problem::response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Request body exceeds the limit of {} bytes", self.max),
+        )
+    }
+}
+
+/// See <https://www.rfc-editor.org/rfc/rfc7807>
+#[derive(Debug, Serialize)]
+pub(super) struct Problem {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+}
+
+impl Problem {
+    /// An `application/problem+json` response titled with the status' reason.
+    pub(super) fn response(status: StatusCode, detail: String) -> Response<String> {
+        let problem = Problem {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+        };
+
+        let mut res =
+            Response::new(serde_json::to_string(&problem).expect("problem is always serializable"));
+        *res.status_mut() = status;
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(length: usize) -> Request<()> {
+        Request::builder()
+            .header(header::CONTENT_LENGTH, length)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn under_limit() {
+        let config = PayloadConfig::new(1024);
+        assert!(config.check(&request(1024)).is_none());
+        assert!(config.check(&Request::new(())).is_none());
+    }
+
+    #[test]
+    fn over_limit() {
+        let config = PayloadConfig::new(1024);
+        let res = config.check(&request(1025)).unwrap();
+
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+
+        let body: serde_json::Value = serde_json::from_str(res.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Payload Too Large",
+                "status": 413,
+                "detail": "Request body exceeds the limit of 1024 bytes"
+            })
+        );
+    }
+}
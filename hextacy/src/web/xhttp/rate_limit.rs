@@ -0,0 +1,243 @@
+use super::payload::Problem;
+use crate::retry::jittered;
+use http::{header, HeaderValue, Response, StatusCode};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Allows `rate` requests per `window` on average, with up to `burst` requests at once.
+///
+/// Implemented as a token bucket holding at most `burst` tokens which refills at `rate` tokens per `window`.
+/// Every request takes a token and is limited if there are none left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    pub rate: u32,
+    pub burst: u32,
+    pub window: Duration,
+}
+
+impl RateLimitPolicy {
+    pub const fn new(rate: u32, burst: u32, window: Duration) -> Self {
+        Self {
+            rate,
+            burst,
+            window,
+        }
+    }
+
+    /// How long it takes to refill a single token.
+    fn refill_interval(&self) -> Duration {
+        if self.rate == 0 {
+            return Duration::MAX;
+        }
+        self.window / self.rate
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// In memory rate limiter applying [policies][RateLimitPolicy] per scope, e.g. a tight one for search
+/// endpoints and a relaxed default for everything else. Each scope keeps a separate bucket per client key,
+/// such as the client's IP or user ID.
+///
+/// Clones share buckets. Limits are not shared between processes.
+///
+/// With [jitter][RateLimit::jitter] the `Retry-After` of limited responses varies randomly, so clients
+/// cannot use it to time their attempts to the moment a token is refilled.
+///
+/// ### Example
+///
+/// ```ignore
+/// static LIMIT: Lazy<RateLimit> = Lazy::new(|| {
+///     RateLimit::new(RateLimitPolicy::new(100, 20, Duration::from_secs(60)))
+///         .scope("search", RateLimitPolicy::new(10, 3, Duration::from_secs(60)))
+/// });
+///
+/// async fn limit_search<B>(ConnectInfo(addr): ConnectInfo<SocketAddr>, req: Request<B>, next: Next<B>) -> Response {
+///     match LIMIT.check("search", &addr.ip().to_string()) {
+///         Some(res) => res.into_response(),
+///         None => next.run(req).await,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    default: RateLimitPolicy,
+    scopes: HashMap<&'static str, RateLimitPolicy>,
+    buckets: Arc<Mutex<HashMap<(String, String), Bucket>>>,
+    jitter: Duration,
+}
+
+impl RateLimit {
+    /// `default` applies to scopes without a policy of their own.
+    pub fn new(default: RateLimitPolicy) -> Self {
+        Self {
+            default,
+            scopes: HashMap::new(),
+            buckets: Arc::default(),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Randomly move the `Retry-After` of limited responses by up to `jitter` in either direction.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn scope(mut self, scope: &'static str, policy: RateLimitPolicy) -> Self {
+        self.scopes.insert(scope, policy);
+        self
+    }
+
+    pub fn policy(&self, scope: &str) -> RateLimitPolicy {
+        self.scopes.get(scope).copied().unwrap_or(self.default)
+    }
+
+    /// Take a token from the key's bucket in the scope. If there are none left,
+    /// returns how long the client should wait before retrying.
+    pub fn acquire(&self, scope: &str, key: &str) -> Result<(), Duration> {
+        self.acquire_at(scope, key, Instant::now())
+    }
+
+    /// Returns a `429 Too Many Requests` response with a `Retry-After` header if the request is limited,
+    /// in which case it should be returned to the client immediately.
+    pub fn check(&self, scope: &str, key: &str) -> Option<Response<String>> {
+        let retry_after = jittered(self.acquire(scope, key).err()?, self.jitter);
+        // Round up so clients never retry too early, unless jittered to do so
+        let secs = (retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1);
+
+        let mut res = Problem::response(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Rate limit exceeded, retry in {secs} seconds"),
+        );
+        res.headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        Some(res)
+    }
+
+    fn acquire_at(&self, scope: &str, key: &str, now: Instant) -> Result<(), Duration> {
+        let policy = self.policy(scope);
+        let burst = f64::from(policy.burst);
+
+        let mut buckets = self.buckets.lock().expect("rate limit lock poisoned");
+        let bucket = buckets
+            .entry((scope.to_string(), key.to_string()))
+            .or_insert(Bucket {
+                tokens: burst,
+                updated: now,
+            });
+
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        let refilled = if policy.rate == 0 {
+            0.
+        } else {
+            elapsed.as_secs_f64() / policy.refill_interval().as_secs_f64()
+        };
+        bucket.tokens = (bucket.tokens + refilled).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1. {
+            bucket.tokens -= 1.;
+            return Ok(());
+        }
+
+        if policy.rate == 0 || policy.burst == 0 {
+            return Err(policy.window);
+        }
+
+        Err(policy.refill_interval().mul_f64(1. - bucket.tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn burst_then_limited_until_refill() {
+        // 1 token every 10 seconds, 3 at once
+        let limit = RateLimit::new(RateLimitPolicy::new(6, 3, WINDOW));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limit.acquire_at("api", "client", start).is_ok());
+        }
+
+        let wait = limit.acquire_at("api", "client", start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(10));
+
+        let later = start + Duration::from_secs(5);
+        let wait = limit.acquire_at("api", "client", later).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(5));
+
+        let refilled = start + Duration::from_secs(10);
+        assert!(limit.acquire_at("api", "client", refilled).is_ok());
+        assert!(limit.acquire_at("api", "client", refilled).is_err());
+
+        // Never refills past the burst
+        let idle = start + Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert!(limit.acquire_at("api", "client", idle).is_ok());
+        }
+        assert!(limit.acquire_at("api", "client", idle).is_err());
+    }
+
+    #[test]
+    fn scopes_and_keys_are_independent() {
+        let limit = RateLimit::new(RateLimitPolicy::new(100, 100, WINDOW))
+            .scope("search", RateLimitPolicy::new(1, 1, WINDOW));
+        let now = Instant::now();
+
+        assert!(limit.acquire_at("search", "a", now).is_ok());
+        assert!(limit.acquire_at("search", "a", now).is_err());
+
+        assert!(limit.acquire_at("search", "b", now).is_ok());
+        assert!(limit.acquire_at("users", "a", now).is_ok());
+    }
+
+    #[test]
+    fn limited_response() {
+        let limit = RateLimit::new(RateLimitPolicy::new(1, 1, WINDOW));
+
+        assert!(limit.check("api", "client").is_none());
+
+        let res = limit.check("api", "client").unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "60");
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+    }
+
+    #[test]
+    fn jittered_retry_after() {
+        let jitter = Duration::from_secs(10);
+        let limit = RateLimit::new(RateLimitPolicy::new(1, 1, WINDOW)).jitter(jitter);
+
+        let mut seen = std::collections::HashSet::new();
+        for i in 0..50 {
+            let client = i.to_string();
+            assert!(limit.check("api", &client).is_none());
+
+            let res = limit.check("api", &client).unwrap();
+            let secs: u64 = res.headers()[header::RETRY_AFTER]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((50..=70).contains(&secs), "{secs}");
+            seen.insert(secs);
+        }
+        assert!(seen.len() > 1);
+    }
+}
@@ -0,0 +1,80 @@
+use http::{HeaderName, HeaderValue, Request};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Returns the `x-request-id` of the request, generating one and setting it on the request if it
+/// has none, so that downstream handlers observe the same ID.
+///
+/// Pair with [with_correlation_id][crate::queue::with_correlation_id] to propagate the ID to
+/// any messages published while handling the request.
+///
+/// ### Example
+///
+/// ```ignore
+/// async fn correlate<B>(mut req: Request<B>, next: Next<B>) -> Response {
+///     let id = request_id(&mut req);
+///     with_correlation_id(id, next.run(req)).await
+/// }
+/// ```
+pub fn request_id<B>(req: &mut Request<B>) -> String {
+    if let Some(id) = req
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+    {
+        return id.to_string();
+    }
+
+    let id = generate();
+    req.headers_mut().insert(
+        X_REQUEST_ID,
+        HeaderValue::from_str(&id).expect("hex is valid"),
+    );
+    id
+}
+
+/// Generates an ID unique to this process and call.
+fn generate() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(nanos);
+    hasher.write_u32(std::process::id());
+
+    format!(
+        "{:016x}{:x}",
+        hasher.finish(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_or_generates_id() {
+        let mut req = Request::builder()
+            .header(X_REQUEST_ID, "abc")
+            .body(())
+            .unwrap();
+        assert_eq!(request_id(&mut req), "abc");
+
+        let mut req = Request::new(());
+        let id = request_id(&mut req);
+        assert!(!id.is_empty());
+        assert_eq!(req.headers()[X_REQUEST_ID], id.as_str());
+        assert_eq!(request_id(&mut req), id);
+    }
+}
@@ -1,3 +1,5 @@
+mod nulls;
+
 use cookie::Cookie;
 use http::header;
 use http::{
@@ -5,7 +7,10 @@ use http::{
     response::Builder,
     Response, StatusCode,
 };
-use serde::Serialize;
+use nulls::{SkipNulls, EXPLICIT_NULL};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -18,12 +23,80 @@ pub enum ResponseError {
     Serde(#[from] serde_json::Error),
 }
 
+static ENVELOPE: AtomicBool = AtomicBool::new(false);
+
+/// Set whether JSON responses are wrapped in an envelope by default, i.e. `{ "data": ..., "meta": ... }`
+/// for successful responses and `{ "error": ..., "meta": ... }` for 4xx and 5xx ones.
+/// Individual responses can override this with [ResponseBuilder::envelope]. Disabled by default.
+pub fn set_envelope(enabled: bool) {
+    ENVELOPE.store(enabled, Ordering::Relaxed);
+}
+
+static SKIP_NULLS: AtomicBool = AtomicBool::new(false);
+
+/// Set whether fields and map entries serialized as `null` are omitted from JSON responses by default.
+/// Individual responses can override this with [ResponseBuilder::skip_nulls]. Disabled by default.
+///
+/// Fields that need to signal a value was cleared can use [Explicit], which is always serialized.
+pub fn set_skip_nulls(enabled: bool) {
+    SKIP_NULLS.store(enabled, Ordering::Relaxed);
+}
+
+/// An optional value serialized as `null` when absent, even when [skipping nulls][set_skip_nulls],
+/// e.g. to tell clients a field was cleared.
+///
+/// ### Example
+///
+/// ```ignore
+/// #[derive(Serialize)]
+/// struct UserUpdated {
+///     id: Uuid,
+///     nickname: Option<String>,      // Omitted when not set
+///     avatar_url: Explicit<String>,  // `null` when the avatar was removed
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Explicit<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for Explicit<T> {
+    fn from(value: Option<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Serialize for Explicit<T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Some(ref value) => value.serialize(serializer),
+            None => serializer.serialize_unit_struct(EXPLICIT_NULL),
+        }
+    }
+}
+
 pub struct ResponseBuilder<T> {
     builder: Builder,
+    status: StatusCode,
     body: T,
+    envelope: Option<bool>,
+    skip_nulls: Option<bool>,
+    meta: Map<String, Value>,
 }
 
 impl<T> ResponseBuilder<T> {
+    fn new(status: StatusCode, body: T) -> Self {
+        Self {
+            builder: Builder::new().status(status),
+            status,
+            body,
+            envelope: None,
+            skip_nulls: None,
+            meta: Map::new(),
+        }
+    }
+
     pub fn with_cookies(
         mut self,
         cookies: &[Cookie<'_>],
@@ -52,11 +125,48 @@ impl<T> ResponseBuilder<T> {
         self
     }
 
+    /// Whether to wrap the JSON body in an envelope, overriding the [crate wide setting][set_envelope].
+    pub fn envelope(mut self, enabled: bool) -> ResponseBuilder<T> {
+        self.envelope = Some(enabled);
+        self
+    }
+
+    /// Whether to omit `null` fields from the JSON body, overriding the [crate wide setting][set_skip_nulls].
+    pub fn skip_nulls(mut self, enabled: bool) -> ResponseBuilder<T> {
+        self.skip_nulls = Some(enabled);
+        self
+    }
+
+    /// Add an entry to the envelope's `meta` object, e.g. the request ID or timing.
+    /// Ignored if the response is not enveloped.
+    pub fn with_meta(
+        mut self,
+        key: &str,
+        value: impl Serialize,
+    ) -> Result<ResponseBuilder<T>, ResponseError> {
+        self.meta
+            .insert(key.to_string(), serde_json::to_value(value)?);
+        Ok(self)
+    }
+
     pub fn finish(self) -> Result<Response<T>, ResponseError> {
         Ok(self.builder.body(self.body)?)
     }
 }
 
+impl ResponseBuilder<String> {
+    /// A `201 Created` with a `Location` header pointing to the created resource and an empty body.
+    /// Use [RestResponse::into_created] to include the resource in the body.
+    pub fn created(location: &str) -> Self {
+        Self::new(StatusCode::CREATED, String::new()).with_headers([(header::LOCATION, location)])
+    }
+
+    /// A `204 No Content` with an empty body.
+    pub fn no_content() -> Self {
+        Self::new(StatusCode::NO_CONTENT, String::new())
+    }
+}
+
 impl<T> ResponseBuilder<T>
 where
     T: Serialize,
@@ -71,12 +181,43 @@ where
             }
         }
 
-        let json = serde_json::to_string(&self.body)?;
+        let body = SkipNulls::new(
+            &self.body,
+            self.skip_nulls
+                .unwrap_or_else(|| SKIP_NULLS.load(Ordering::Relaxed)),
+        );
+
+        let json = if self
+            .envelope
+            .unwrap_or_else(|| ENVELOPE.load(Ordering::Relaxed))
+        {
+            let meta = &self.meta;
+            if self.status.is_client_error() || self.status.is_server_error() {
+                serde_json::to_string(&ErrorEnvelope { error: &body, meta })?
+            } else {
+                serde_json::to_string(&Envelope { data: &body, meta })?
+            }
+        } else {
+            serde_json::to_string(&body)?
+        };
 
         self.builder.body(json).map_err(ResponseError::Http)
     }
 }
 
+/// Serialized directly instead of through a [Value] so the body keeps its field order.
+#[derive(Serialize)]
+struct Envelope<'a, B> {
+    data: &'a B,
+    meta: &'a Map<String, Value>,
+}
+
+#[derive(Serialize)]
+struct ErrorEnvelope<'a, B> {
+    error: &'a B,
+    meta: &'a Map<String, Value>,
+}
+
 /// Utility containing default methods for quickly converting a struct to an HTTP response.
 pub trait RestResponse<'a>
 where
@@ -84,9 +225,164 @@ where
 {
     /// Enables quickly converting a struct to an http response with a JSON body and the provided cookies and headers.
     fn into_response(self, code: StatusCode) -> ResponseBuilder<Self> {
-        ResponseBuilder {
-            builder: Builder::new().status(code),
-            body: self,
+        ResponseBuilder::new(code, self)
+    }
+
+    /// Like [into_response][RestResponse::into_response] with a `201 Created` and a `Location` header
+    /// pointing to the created resource.
+    fn into_created(self, location: &str) -> ResponseBuilder<Self> {
+        ResponseBuilder::new(StatusCode::CREATED, self).with_headers([(header::LOCATION, location)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct User {
+        id: u32,
+    }
+
+    impl RestResponse<'_> for User {}
+
+    #[test]
+    fn created_and_no_content() {
+        let res = ResponseBuilder::created("/users/1").finish().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[header::LOCATION], "/users/1");
+        assert!(res.body().is_empty());
+
+        let res = User { id: 1 }.into_created("/users/1").json().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[header::LOCATION], "/users/1");
+        assert_eq!(res.body(), r#"{"id":1}"#);
+
+        let res = ResponseBuilder::no_content().finish().unwrap();
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(res.headers().get(header::CONTENT_TYPE).is_none());
+        assert!(res.body().is_empty());
+
+        assert!(ResponseBuilder::created("/users/\n1\r").finish().is_err());
+    }
+
+    #[derive(Serialize)]
+    struct ApiError {
+        message: &'static str,
+    }
+
+    impl RestResponse<'_> for ApiError {}
+
+    #[test]
+    fn envelopes() {
+        let res = User { id: 1 }
+            .into_response(StatusCode::OK)
+            .envelope(true)
+            .with_meta("request_id", "abc")
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(
+            res.body(),
+            r#"{"data":{"id":1},"meta":{"request_id":"abc"}}"#
+        );
+
+        let res = ApiError {
+            message: "not found",
         }
+        .into_response(StatusCode::NOT_FOUND)
+        .envelope(true)
+        .json()
+        .unwrap();
+        assert_eq!(res.body(), r#"{"error":{"message":"not found"},"meta":{}}"#);
+
+        // Opted out per response, meta is ignored
+        let res = User { id: 1 }
+            .into_response(StatusCode::OK)
+            .envelope(false)
+            .with_meta("request_id", "abc")
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(res.body(), r#"{"id":1}"#);
+    }
+
+    #[derive(Serialize)]
+    struct Profile {
+        id: u32,
+        nickname: Option<&'static str>,
+        avatar: Explicit<&'static str>,
+        tags: Vec<Option<&'static str>>,
+        links: std::collections::BTreeMap<&'static str, Option<&'static str>>,
+        settings: Option<Settings>,
+    }
+
+    #[derive(Serialize)]
+    struct Settings {
+        theme: Option<&'static str>,
+    }
+
+    impl RestResponse<'_> for Profile {}
+
+    fn profile() -> Profile {
+        Profile {
+            id: 1,
+            nickname: None,
+            avatar: Explicit(None),
+            tags: vec![Some("a"), None],
+            links: [("blog", None), ("site", Some("x.dev"))].into(),
+            settings: Some(Settings { theme: None }),
+        }
+    }
+
+    #[test]
+    fn skips_nulls() {
+        let res = profile()
+            .into_response(StatusCode::OK)
+            .skip_nulls(true)
+            .json()
+            .unwrap();
+        // Explicit nulls and sequence elements are kept
+        assert_eq!(
+            res.body(),
+            r#"{"id":1,"avatar":null,"tags":["a",null],"links":{"site":"x.dev"},"settings":{}}"#
+        );
+
+        let res = profile()
+            .into_response(StatusCode::OK)
+            .skip_nulls(true)
+            .envelope(true)
+            .json()
+            .unwrap();
+        assert_eq!(
+            res.body(),
+            r#"{"data":{"id":1,"avatar":null,"tags":["a",null],"links":{"site":"x.dev"},"settings":{}},"meta":{}}"#
+        );
+
+        let res = Profile {
+            nickname: Some("al"),
+            avatar: Explicit(Some("al.png")),
+            ..profile()
+        }
+        .into_response(StatusCode::OK)
+        .skip_nulls(true)
+        .json()
+        .unwrap();
+        assert!(res
+            .body()
+            .starts_with(r#"{"id":1,"nickname":"al","avatar":"al.png","#));
+    }
+
+    #[test]
+    fn keeps_nulls_when_disabled() {
+        let res = profile()
+            .into_response(StatusCode::OK)
+            .skip_nulls(false)
+            .json()
+            .unwrap();
+        assert_eq!(
+            res.body(),
+            r#"{"id":1,"nickname":null,"avatar":null,"tags":["a",null],"links":{"blog":null,"site":"x.dev"},"settings":{"theme":null}}"#
+        );
     }
 }
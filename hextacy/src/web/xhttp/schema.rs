@@ -0,0 +1,246 @@
+use http::{header, HeaderValue, Response, StatusCode};
+use jsonschema::JSONSchema;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use thiserror::Error;
+
+/// A JSON Schema compiled once, usually at startup, and used to validate incoming payloads.
+///
+/// ### Example
+///
+/// ```ignore
+/// static SCHEMA: OnceLock<Schema> = OnceLock::new();
+///
+/// let schema = Schema::from_file("schemas/webhook.json").expect("invalid webhook schema");
+/// SCHEMA.set(schema).unwrap();
+///
+/// async fn webhook(body: Bytes) -> Response {
+///     let SchemaValidated(event) = match SchemaValidated::<Event>::from_slice(&body, SCHEMA.get().unwrap()) {
+///         Ok(event) => event,
+///         Err(e) => return e.into_response().into_response(),
+///     };
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Schema {
+    compiled: JSONSchema,
+}
+
+impl Schema {
+    pub fn new(schema: &Value) -> Result<Self, SchemaError> {
+        JSONSchema::compile(schema)
+            .map(|compiled| Self { compiled })
+            .map_err(|e| SchemaError::Compile(e.to_string()))
+    }
+
+    /// Reads and compiles the schema at the given path.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SchemaError> {
+        let schema = std::fs::read(path)?;
+        Self::new(&serde_json::from_slice(&schema)?)
+    }
+
+    /// Returns every violation found in the instance.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+        self.compiled.validate(instance).map_err(|errors| {
+            errors
+                .map(|e| SchemaViolation {
+                    pointer: e.instance_path.to_string(),
+                    message: e.to_string(),
+                })
+                .collect()
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    #[error("IO: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Schema is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid schema: {0}")]
+    Compile(String),
+}
+
+/// A single schema violation in a payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, empty for the whole document.
+    pub pointer: String,
+    pub message: String,
+}
+
+/// A payload which was validated against a [Schema] before being deserialized into `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaValidated<T>(pub T);
+
+impl<T> SchemaValidated<T>
+where
+    T: DeserializeOwned,
+{
+    pub fn from_slice(body: &[u8], schema: &Schema) -> Result<Self, SchemaRejection> {
+        let value: Value = serde_json::from_slice(body).map_err(SchemaRejection::Syntax)?;
+        Self::from_value(value, schema)
+    }
+
+    pub fn from_value(value: Value, schema: &Schema) -> Result<Self, SchemaRejection> {
+        schema
+            .validate(&value)
+            .map_err(SchemaRejection::Violations)?;
+        serde_json::from_value(value)
+            .map(Self)
+            .map_err(SchemaRejection::Deserialize)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SchemaRejection {
+    #[error("Malformed JSON: {0}")]
+    Syntax(serde_json::Error),
+    #[error("Payload violates the schema in {} places", .0.len())]
+    Violations(Vec<SchemaViolation>),
+    #[error("Payload matches the schema but not the expected type: {0}")]
+    Deserialize(serde_json::Error),
+}
+
+impl SchemaRejection {
+    /// A `400 Bad Request` for malformed JSON, otherwise a `422 Unprocessable Entity` listing
+    /// the violations in an `errors` member.
+    pub fn into_response(self) -> Response<String> {
+        let status = match self {
+            Self::Syntax(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+
+        let detail = self.to_string();
+        let errors = match self {
+            Self::Violations(violations) => violations,
+            _ => vec![],
+        };
+
+        let problem = ViolationProblem {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+            errors,
+        };
+
+        let mut res =
+            Response::new(serde_json::to_string(&problem).expect("problem is always serializable"));
+        *res.status_mut() = status;
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        res
+    }
+}
+
+/// A problem with the extension member holding the violations.
+#[derive(Debug, Serialize)]
+struct ViolationProblem {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<SchemaViolation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Event {
+        kind: String,
+        retries: u8,
+        tags: Vec<String>,
+    }
+
+    fn schema() -> Schema {
+        Schema::new(&json!({
+            "type": "object",
+            "required": ["kind", "retries", "tags"],
+            "properties": {
+                "kind": { "enum": ["created", "deleted"] },
+                "retries": { "type": "integer", "minimum": 0, "maximum": 5 },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_body() {
+        let body = br#"{"kind":"created","retries":2,"tags":["a"]}"#;
+        let SchemaValidated(event) = SchemaValidated::<Event>::from_slice(body, &schema()).unwrap();
+        assert_eq!(
+            event,
+            Event {
+                kind: "created".to_string(),
+                retries: 2,
+                tags: vec!["a".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_body_points_at_violations() {
+        let body = br#"{"kind":"updated","retries":9,"tags":["a",1]}"#;
+        let Err(rejection) = SchemaValidated::<Event>::from_slice(body, &schema()) else {
+            panic!("body violates the schema");
+        };
+
+        let SchemaRejection::Violations(ref violations) = rejection else {
+            panic!("expected violations, got {rejection:?}");
+        };
+        let mut pointers = violations
+            .iter()
+            .map(|v| v.pointer.as_str())
+            .collect::<Vec<_>>();
+        pointers.sort_unstable();
+        assert_eq!(pointers, ["/kind", "/retries", "/tags/1"]);
+
+        let res = rejection.into_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = serde_json::from_str(res.body()).unwrap();
+        assert_eq!(body["errors"].as_array().unwrap().len(), 3);
+        assert!(body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|e| e["pointer"] == "/tags/1"));
+    }
+
+    #[test]
+    fn malformed_and_missing() {
+        let rejection = SchemaValidated::<Event>::from_slice(b"{", &schema()).unwrap_err();
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let Err(SchemaRejection::Violations(violations)) =
+            SchemaValidated::<Event>::from_slice(br#"{"kind":"created"}"#, &schema())
+        else {
+            panic!("missing fields violate the schema");
+        };
+        assert!(violations.iter().all(|v| v.pointer.is_empty()));
+    }
+
+    #[test]
+    fn invalid_schema() {
+        assert!(matches!(
+            Schema::new(&json!({ "type": "nope" })),
+            Err(SchemaError::Compile(_))
+        ));
+    }
+}
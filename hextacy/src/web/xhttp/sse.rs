@@ -0,0 +1,182 @@
+use crate::queue::Consumer;
+use futures::{Stream, StreamExt};
+use http::{header, Response, StatusCode};
+use serde::Serialize;
+use std::{fmt::Display, time::Duration};
+use tracing::error;
+
+/// The comment sent to keep idle connections open.
+pub const KEEP_ALIVE: &str = ":\n\n";
+
+/// A single server-sent event. Displays as its `text/event-stream` frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    pub fn data(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create an event with the value serialized to JSON as its data.
+    pub fn json<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        serde_json::to_string(value).map(Self::data)
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the event type, dispatched to `addEventListener(type)` listeners on the client.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set how long the client waits before reconnecting.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Newlines would terminate the field early
+        if let Some(ref id) = self.id {
+            writeln!(f, "id: {}", id.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(ref event) = self.event {
+            writeln!(f, "event: {}", event.replace(['\r', '\n'], ""))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry.as_millis())?;
+        }
+        for line in self.data.lines() {
+            writeln!(f, "data: {line}")?;
+        }
+        if self.data.is_empty() {
+            writeln!(f, "data:")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Turns a stream of events into a stream of `text/event-stream` frames.
+///
+/// A [keep-alive comment][KEEP_ALIVE] is emitted whenever no event was received for `keep_alive`
+/// so proxies do not close idle connections. The stream ends when the event stream does.
+///
+/// Use the result as the body of [sse_response].
+pub fn sse<S>(events: S, keep_alive: Duration) -> impl Stream<Item = String> + Send
+where
+    S: Stream<Item = Event> + Send + 'static,
+{
+    futures::stream::unfold(Box::pin(events), move |mut events| async move {
+        match tokio::time::timeout(keep_alive, events.next()).await {
+            Ok(Some(event)) => Some((event.to_string(), events)),
+            Ok(None) => None,
+            Err(_) => Some((KEEP_ALIVE.to_string(), events)),
+        }
+    })
+}
+
+/// Exposes a queue subscription as a stream of events with the messages serialized to JSON.
+/// Messages that fail to be received or serialized are logged and skipped.
+pub fn consumer_events<C, M>(consumer: C) -> impl Stream<Item = Event> + Send
+where
+    C: Consumer<M>,
+    M: Serialize + Send + 'static,
+{
+    futures::stream::unfold(consumer, |mut consumer| async move {
+        loop {
+            match consumer.poll_queue().await {
+                Ok(Some(message)) => match Event::json(&message) {
+                    Ok(event) => return Some((event, consumer)),
+                    Err(e) => error!("Error occurred while serializing event: {e}"),
+                },
+                Ok(None) => return None,
+                Err(e) => error!("Error occurred while polling queue: {e}"),
+            }
+        }
+    })
+}
+
+/// Wraps the body in a `200` response with the headers required for server-sent events.
+pub fn sse_response<B>(body: B) -> Response<B> {
+    let mut response = Response::new(body);
+    *response.status_mut() = StatusCode::OK;
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("text/event-stream"),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static("no-cache"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::QueueError;
+
+    #[test]
+    fn framing() {
+        let event = Event::data("hello").id("1").event("greeting");
+        assert_eq!(event.to_string(), "id: 1\nevent: greeting\ndata: hello\n\n");
+
+        let event = Event::data("first\nsecond").retry(Duration::from_secs(3));
+        assert_eq!(
+            event.to_string(),
+            "retry: 3000\ndata: first\ndata: second\n\n"
+        );
+
+        let event = Event::json(&serde_json::json!({ "id": 1 })).unwrap();
+        assert_eq!(event.to_string(), "data: {\"id\":1}\n\n");
+    }
+
+    #[tokio::test]
+    async fn stream_frames_and_keep_alives() {
+        let events = futures::stream::iter([Event::data("a"), Event::data("b").event("b")]);
+        let frames = sse(events, Duration::from_secs(10))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(frames, ["data: a\n\n", "event: b\ndata: b\n\n"]);
+
+        let idle = futures::stream::pending::<Event>();
+        let frames = sse(idle, Duration::from_millis(10))
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(frames, [KEEP_ALIVE, KEEP_ALIVE]);
+
+        let res = sse_response(());
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/event-stream");
+    }
+
+    struct Messages(Vec<Result<u8, QueueError>>);
+
+    impl Consumer<u8> for Messages {
+        async fn poll_queue(&mut self) -> Result<Option<u8>, QueueError> {
+            self.0.pop().transpose()
+        }
+    }
+
+    #[tokio::test]
+    async fn consumer_as_events() {
+        let consumer = Messages(vec![Ok(2), Err(QueueError::Full), Ok(1)]);
+        let events = consumer_events(consumer).collect::<Vec<_>>().await;
+        assert_eq!(events, [Event::data("1"), Event::data("2")]);
+    }
+}
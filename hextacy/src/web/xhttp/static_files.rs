@@ -0,0 +1,284 @@
+use http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use std::{
+    fs::Metadata,
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Serves files from `dir` for requests whose path starts with `mount`, setting the given
+/// `Cache-Control` value on every served file.
+///
+/// ### Example
+///
+/// ```ignore
+/// let assets = static_files("/assets", "./public", "public, max-age=3600").spa_fallback("index.html");
+///
+/// async fn serve<B>(req: Request<B>, next: Next<B>) -> Response {
+///     match assets.serve(&req).await {
+///         Some(res) => res.into_response(),
+///         None => next.run(req).await,
+///     }
+/// }
+/// ```
+pub fn static_files(
+    mount: &str,
+    dir: impl Into<PathBuf>,
+    cache_control: &'static str,
+) -> StaticFiles {
+    StaticFiles::new(mount, dir, cache_control)
+}
+
+/// Framework agnostic static file server. Call [serve][StaticFiles::serve] from whatever the framework uses
+/// for middleware or fallback handlers.
+///
+/// Responses carry a weak `ETag` derived from the file's size and modification time. Requests with a
+/// matching `If-None-Match` receive a `304 Not Modified` without a body.
+///
+/// Request paths are percent-decoded before being resolved. Paths containing `..` or other non-normal
+/// components are never resolved, so files outside of `dir` cannot be served.
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    mount: String,
+    dir: PathBuf,
+    cache_control: HeaderValue,
+    fallback: Option<PathBuf>,
+}
+
+impl StaticFiles {
+    pub fn new(mount: &str, dir: impl Into<PathBuf>, cache_control: &'static str) -> Self {
+        Self {
+            mount: mount.trim_end_matches('/').to_string(),
+            dir: dir.into(),
+            cache_control: HeaderValue::from_static(cache_control),
+            fallback: None,
+        }
+    }
+
+    /// Serve the given file, relative to the directory, for any path under the mount that does not
+    /// correspond to a file. Useful for single page apps doing their own routing, where the fallback is usually `index.html`.
+    pub fn spa_fallback(mut self, file: impl Into<PathBuf>) -> Self {
+        self.fallback = Some(file.into());
+        self
+    }
+
+    /// Returns `None` if the request is not a `GET` or `HEAD` under the mount, in which case it should be passed along.
+    /// Otherwise returns the file, a `304` if the client's copy is fresh, or a `404` if neither
+    /// the file nor the fallback exist.
+    pub async fn serve<B>(&self, req: &Request<B>) -> Option<Response<Vec<u8>>> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return None;
+        }
+
+        let path = req.uri().path();
+        let relative = if self.mount.is_empty() {
+            path
+        } else {
+            let rest = path.strip_prefix(&self.mount)?;
+            if !rest.is_empty() && !rest.starts_with('/') {
+                return None;
+            }
+            rest
+        };
+
+        let mut found = None;
+        if let Some(file) = self.resolve(relative) {
+            found = file_metadata(&file).await.map(|meta| (file, meta));
+        }
+        if let (None, Some(fallback)) = (&found, &self.fallback) {
+            let file = self.dir.join(fallback);
+            found = file_metadata(&file).await.map(|meta| (file, meta));
+        }
+
+        let Some((file, meta)) = found else {
+            return Some(status(StatusCode::NOT_FOUND));
+        };
+
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        let etag = format!("W/\"{:x}-{:x}\"", meta.len(), modified);
+
+        let fresh = req
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| {
+                v.split(',')
+                    .any(|tag| tag.trim() == etag || tag.trim() == "*")
+            });
+
+        let builder = Response::builder()
+            .header(header::CACHE_CONTROL, self.cache_control.clone())
+            .header(header::ETAG, &etag);
+
+        if fresh {
+            return builder.status(StatusCode::NOT_MODIFIED).body(vec![]).ok();
+        }
+
+        let Ok(body) = tokio::fs::read(&file).await else {
+            return Some(status(StatusCode::INTERNAL_SERVER_ERROR));
+        };
+
+        let builder = builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type(&file))
+            .header(header::CONTENT_LENGTH, body.len());
+
+        if req.method() == Method::HEAD {
+            return builder.body(vec![]).ok();
+        }
+
+        builder.body(body).ok()
+    }
+
+    /// Decodes the relative path and joins it to the directory, rejecting anything that could escape it.
+    fn resolve(&self, relative: &str) -> Option<PathBuf> {
+        let relative = percent_decode_str(relative).decode_utf8().ok()?;
+        let relative = Path::new(relative.trim_start_matches('/'));
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        Some(self.dir.join(relative))
+    }
+}
+
+async fn file_metadata(path: &Path) -> Option<Metadata> {
+    tokio::fs::metadata(path)
+        .await
+        .ok()
+        .filter(|meta| meta.is_file())
+}
+
+fn status(status: StatusCode) -> Response<Vec<u8>> {
+    let mut res = Response::new(vec![]);
+    *res.status_mut() = status;
+    res
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match ext.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("wasm") => "application/wasm",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("css")).unwrap();
+        std::fs::write(dir.path().join("index.html"), "<html></html>").unwrap();
+        std::fs::write(dir.path().join("css/main.css"), "body {}").unwrap();
+        std::fs::write(dir.path().join("a b.txt"), "spaced").unwrap();
+        dir
+    }
+
+    fn get(uri: &str) -> Request<()> {
+        Request::builder().uri(uri).body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_file_with_cache_headers() {
+        let dir = setup();
+        let files = static_files("/assets", dir.path(), "public, max-age=3600");
+
+        let res = files.serve(&get("/assets/css/main.css")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), b"body {}");
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "public, max-age=3600");
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "text/css; charset=utf-8"
+        );
+
+        let etag = res.headers()[header::ETAG].clone();
+        let req = Request::builder()
+            .uri("/assets/css/main.css")
+            .header(header::IF_NONE_MATCH, etag)
+            .body(())
+            .unwrap();
+        let res = files.serve(&req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert!(res.body().is_empty());
+
+        assert!(files.serve(&get("/api/users")).await.is_none());
+        assert!(files.serve(&get("/assetsfoo")).await.is_none());
+        assert_eq!(
+            files.serve(&get("/assets/nope.js")).await.unwrap().status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            files
+                .serve(&get("/assets/../Cargo.toml"))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn decodes_paths_before_resolving() {
+        let dir = setup();
+        let files = static_files("/assets", dir.path(), "no-cache");
+
+        let res = files.serve(&get("/assets/a%20b.txt")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), b"spaced");
+
+        for uri in [
+            "/assets/%2e%2e/Cargo.toml",
+            "/assets/css%2F..%2F..%2FCargo.toml",
+        ] {
+            let res = files.serve(&get(uri)).await.unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn spa_fallback() {
+        let dir = setup();
+        let files = static_files("/", dir.path(), "no-cache").spa_fallback("index.html");
+
+        for uri in ["/", "/users/42", "/settings/"] {
+            let res = files.serve(&get(uri)).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(res.body(), b"<html></html>");
+            assert_eq!(res.headers()[header::CACHE_CONTROL], "no-cache");
+        }
+
+        let res = files.serve(&get("/css/main.css")).await.unwrap();
+        assert_eq!(res.body(), b"body {}");
+    }
+}
@@ -0,0 +1,82 @@
+use http::{header, HeaderValue, Response, StatusCode};
+use serde::Serialize;
+
+/// Build information of the deployed binary, created with [version_info][crate::version_info].
+///
+/// The git sha and build timestamp are read from the `GIT_SHA` and `BUILD_TIMESTAMP` env variables at compile time
+/// and are `None` if the build script did not set them.
+///
+/// ### Example
+///
+/// In the binary's `build.rs`
+///
+/// ```ignore
+/// let sha = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output().unwrap();
+/// println!("cargo:rustc-env=GIT_SHA={}", String::from_utf8_lossy(&sha.stdout).trim());
+/// println!("cargo:rustc-env=BUILD_TIMESTAMP={}", chrono::Utc::now().to_rfc3339());
+/// println!("cargo:rerun-if-changed=.git/HEAD");
+/// ```
+///
+/// and the handler
+///
+/// ```ignore
+/// async fn version() -> Response {
+///     version_info!().response().into_response()
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VersionInfo {
+    pub version: &'static str,
+    pub git_sha: Option<&'static str>,
+    pub built_at: Option<&'static str>,
+}
+
+impl VersionInfo {
+    /// A `200` JSON response with the build information.
+    pub fn response(&self) -> Response<String> {
+        let mut res = Response::new(
+            serde_json::to_string(self).expect("version info is always serializable"),
+        );
+        *res.status_mut() = StatusCode::OK;
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        res
+    }
+}
+
+/// Creates a [VersionInfo][crate::web::xhttp::version::VersionInfo] from the compile time env of the crate invoking it,
+/// so the version is the application's and not hextacy's.
+#[macro_export]
+macro_rules! version_info {
+    () => {
+        $crate::web::xhttp::version::VersionInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: option_env!("GIT_SHA"),
+            built_at: option_env!("BUILD_TIMESTAMP"),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_build_info() {
+        let info = version_info!();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+
+        let res = info.response();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+
+        let body: serde_json::Value = serde_json::from_str(res.body()).unwrap();
+        let body = body.as_object().unwrap();
+        assert_eq!(body.len(), 3);
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body.contains_key("git_sha"));
+        assert!(body.contains_key("built_at"));
+    }
+}
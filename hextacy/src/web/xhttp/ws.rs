@@ -0,0 +1,216 @@
+use crate::queue::Subscriber;
+
+/// Status codes for closing a websocket connection, as defined in RFC 6455 and the IANA registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseCode {
+    /// 1000, the purpose of the connection was fulfilled.
+    Normal,
+    /// 1001, the server is shutting down or the client navigated away.
+    GoingAway,
+    /// 1002
+    Protocol,
+    /// 1003, the endpoint received a type of data it can't accept.
+    Unsupported,
+    /// 1007, the message data was not consistent with its type.
+    Invalid,
+    /// 1008, a generic code for messages violating the server's policy.
+    Policy,
+    /// 1009
+    Size,
+    /// 1011, the server encountered an unexpected condition.
+    Error,
+    /// 1013, the server is overloaded or the client is being throttled.
+    TryAgainLater,
+    /// Codes in the 4000-4999 range are reserved for applications.
+    Other(u16),
+}
+
+impl From<CloseCode> for u16 {
+    fn from(code: CloseCode) -> u16 {
+        match code {
+            CloseCode::Normal => 1000,
+            CloseCode::GoingAway => 1001,
+            CloseCode::Protocol => 1002,
+            CloseCode::Unsupported => 1003,
+            CloseCode::Invalid => 1007,
+            CloseCode::Policy => 1008,
+            CloseCode::Size => 1009,
+            CloseCode::Error => 1011,
+            CloseCode::TryAgainLater => 1013,
+            CloseCode::Other(code) => code,
+        }
+    }
+}
+
+impl From<u16> for CloseCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1000 => Self::Normal,
+            1001 => Self::GoingAway,
+            1002 => Self::Protocol,
+            1003 => Self::Unsupported,
+            1007 => Self::Invalid,
+            1008 => Self::Policy,
+            1009 => Self::Size,
+            1011 => Self::Error,
+            1013 => Self::TryAgainLater,
+            code => Self::Other(code),
+        }
+    }
+}
+
+/// The maximum size of a close reason, control frame payloads are limited to 125 bytes, 2 of which are the code.
+pub const MAX_CLOSE_REASON: usize = 123;
+
+/// A typed close frame. Convert it to the framework's close message with the code and reason,
+/// or send the raw [payload][CloseFrame::payload].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: CloseCode,
+    pub reason: String,
+}
+
+impl CloseFrame {
+    /// Reasons longer than [MAX_CLOSE_REASON] bytes are truncated on a character boundary.
+    pub fn new(code: CloseCode, reason: &str) -> Self {
+        let mut end = reason.len().min(MAX_CLOSE_REASON);
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            code,
+            reason: reason[..end].to_string(),
+        }
+    }
+
+    /// The frame payload, the code in network byte order followed by the UTF-8 reason.
+    pub fn payload(&self) -> Vec<u8> {
+        let mut payload = u16::from(self.code).to_be_bytes().to_vec();
+        payload.extend_from_slice(self.reason.as_bytes());
+        payload
+    }
+}
+
+/// A websocket session's handle on its [Broadcast][crate::queue::Broadcast] or [Topics][crate::queue::Topics]
+/// subscription.
+///
+/// Closing the session drops the subscription, which unregisters it from the broker on its next
+/// broadcast or reap, and returns the frame to send to the client.
+///
+/// ### Example
+///
+/// ```ignore
+/// let mut session = SocketSession::new(topics.subscribe("prices"));
+/// loop {
+///     tokio::select! {
+///         Some(message) = socket.recv() => {
+///             if LIMIT.acquire("ws", &user_id).is_err() {
+///                 let frame = session.rate_limited();
+///                 let frame = ws::CloseFrame { code: frame.code.into(), reason: frame.reason.into() };
+///                 socket.send(Message::Close(Some(frame))).await.ok();
+///                 break;
+///             }
+///         }
+///         Ok(Some(update)) = session.subscriber().unwrap().poll_queue() => { /* ... */ }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SocketSession<M> {
+    subscriber: Option<Subscriber<M>>,
+    closed: Option<CloseFrame>,
+}
+
+impl<M> SocketSession<M> {
+    pub fn new(subscriber: Subscriber<M>) -> Self {
+        Self {
+            subscriber: Some(subscriber),
+            closed: None,
+        }
+    }
+
+    /// The subscription, `None` once the session is closed.
+    pub fn subscriber(&mut self) -> Option<&mut Subscriber<M>> {
+        self.subscriber.as_mut()
+    }
+
+    /// The frame the session was closed with.
+    pub fn closed(&self) -> Option<&CloseFrame> {
+        self.closed.as_ref()
+    }
+
+    /// Unregisters the session and returns the close frame. Closing an already closed session
+    /// returns the original frame.
+    pub fn close_with(&mut self, code: CloseCode, reason: &str) -> CloseFrame {
+        self.subscriber = None;
+        self.closed
+            .get_or_insert_with(|| CloseFrame::new(code, reason))
+            .clone()
+    }
+
+    /// Closes with `1013 Try Again Later`.
+    pub fn rate_limited(&mut self) -> CloseFrame {
+        self.close_with(CloseCode::TryAgainLater, "Rate limit exceeded")
+    }
+
+    /// Closes with `1008 Policy Violation`, e.g. when the session's token expires.
+    pub fn unauthorized(&mut self) -> CloseFrame {
+        self.close_with(CloseCode::Policy, "Unauthorized")
+    }
+
+    /// Closes with `1001 Going Away`, used when the server is shutting down.
+    pub fn going_away(&mut self) -> CloseFrame {
+        self.close_with(CloseCode::GoingAway, "Server shutting down")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::Broadcast;
+
+    #[test]
+    fn close_frame_carries_code_and_reason() {
+        let broadcast = Broadcast::<String>::new(8);
+        let mut session = SocketSession::new(broadcast.subscribe());
+        let _other = broadcast.subscribe();
+        assert_eq!(broadcast.subscribers(), 2);
+
+        let frame = session.unauthorized();
+        assert_eq!(frame.code, CloseCode::Policy);
+        assert_eq!(frame.reason, "Unauthorized");
+        assert_eq!(&frame.payload()[..2], &1008u16.to_be_bytes());
+        assert_eq!(&frame.payload()[2..], b"Unauthorized");
+
+        assert!(session.subscriber().is_none());
+        broadcast.reap();
+        assert_eq!(broadcast.subscribers(), 1);
+
+        // Closing again keeps the original frame
+        assert_eq!(session.going_away(), frame);
+        assert_eq!(session.closed(), Some(&frame));
+    }
+
+    #[test]
+    fn policy_closes() {
+        let broadcast = Broadcast::<String>::new(8);
+
+        let frame = SocketSession::new(broadcast.subscribe()).rate_limited();
+        assert_eq!(u16::from(frame.code), 1013);
+
+        let frame = SocketSession::new(broadcast.subscribe()).going_away();
+        assert_eq!(u16::from(frame.code), 1001);
+
+        let frame = SocketSession::new(broadcast.subscribe()).close_with(4001.into(), "Kicked");
+        assert_eq!(frame.code, CloseCode::Other(4001));
+        assert_eq!(broadcast.broadcast("ping".to_string()), 0);
+    }
+
+    #[test]
+    fn truncates_long_reasons() {
+        let reason = "ž".repeat(100);
+        let frame = CloseFrame::new(CloseCode::Normal, &reason);
+        assert_eq!(frame.reason.len(), 122);
+        assert!(frame.payload().len() <= 125);
+    }
+}
@@ -0,0 +1,7 @@
+#[test]
+fn cache_keyable() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/cache_key_single.rs");
+    t.pass("tests/ui/cache_key_composite.rs");
+    t.compile_fail("tests/ui/cache_key_missing.rs");
+}
@@ -0,0 +1,60 @@
+use hextacy::contract;
+
+#[allow(dead_code)]
+struct UserAdapter;
+
+#[contract(crate, mock)]
+impl UserAdapter {
+    async fn get_by_id(&self, id: &str) -> Result<Option<String>, String> {
+        Ok(Some(id.to_string()))
+    }
+
+    async fn rename(&self, id: &str, username: String) -> Result<(), String> {
+        let _ = (id, username);
+        Ok(())
+    }
+}
+
+struct UserService<R> {
+    users: R,
+}
+
+impl<R: UserAdapterContract + Send + Sync> UserService<R> {
+    async fn rename(&self, id: &str, username: &str) -> Result<(), String> {
+        match self.users.get_by_id(id).await? {
+            Some(_) => self.users.rename(id, username.to_lowercase()).await,
+            None => Err(format!("User {id} not found")),
+        }
+    }
+}
+
+#[tokio::test]
+async fn mock_records_calls_and_arguments() {
+    let mut users = MockUserAdapterContract::new();
+    users
+        .expect_get_by_id(|id| Ok((id == "1").then(|| "Alice".to_string())))
+        .expect_rename(|id, username| {
+            assert_eq!(id, "1");
+            assert_eq!(username, "bob");
+            Ok(())
+        });
+    let service = UserService { users };
+
+    assert_eq!(service.rename("1", "Bob").await, Ok(()));
+    assert_eq!(
+        service.rename("2", "Bob").await,
+        Err("User 2 not found".to_string())
+    );
+
+    assert_eq!(service.users.get_by_id_calls(), 2);
+    assert_eq!(service.users.rename_calls(), 1);
+}
+
+#[tokio::test]
+#[should_panic(expected = "MockUserAdapterContract::get_by_id called without an expectation")]
+async fn unexpected_calls_panic() {
+    let service = UserService {
+        users: MockUserAdapterContract::new(),
+    };
+    let _ = service.rename("1", "Bob").await;
+}
@@ -0,0 +1,22 @@
+use hextacy::CacheKeyable;
+
+#[derive(CacheKeyable)]
+#[cache_key(namespace = "session")]
+struct Session<'a> {
+    #[cache_key]
+    user_id: &'a str,
+    #[allow(dead_code)]
+    csrf: &'a str,
+    #[cache_key]
+    id: u32,
+}
+
+fn main() {
+    let session = Session {
+        user_id: "a:b",
+        csrf: "token",
+        id: 7,
+    };
+    let key: String = session.cache_key().into();
+    assert_eq!(key, "session:a%3Ab:7");
+}
@@ -0,0 +1,8 @@
+use hextacy::CacheKeyable;
+
+#[derive(CacheKeyable)]
+struct Session {
+    id: u32,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use hextacy::CacheKeyable;
+
+#[derive(CacheKeyable)]
+struct UserProfile {
+    #[cache_key]
+    id: u64,
+    #[allow(dead_code)]
+    username: String,
+}
+
+fn main() {
+    let user = UserProfile {
+        id: 42,
+        username: "foo:bar".to_string(),
+    };
+    assert_eq!(user.cache_key().as_str(), "user_profile:42");
+}
@@ -0,0 +1,62 @@
+use crate::component::pascal_to_snake;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{spanned::Spanned, DeriveInput, LitStr};
+
+pub fn impl_cache_keyable(input: DeriveInput) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let syn::Data::Struct(ref strct) = input.data else {
+        abort!(
+            input.span(),
+            "CacheKeyable derive only works for structs with named fields"
+        );
+    };
+
+    let ident = &input.ident;
+    let (im, ty, wh) = input.generics.split_for_impl();
+
+    let mut namespace = pascal_to_snake(&ident.to_string());
+
+    for attr in input.attrs.iter() {
+        if !attr.path().is_ident("cache_key") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("namespace") {
+                namespace = meta.value()?.parse::<LitStr>()?.value();
+                return Ok(());
+            }
+            Err(meta.error("expected `namespace = \"...\"`"))
+        })?;
+    }
+
+    let mut segments = vec![];
+
+    for field in strct.fields.iter() {
+        if !field.attrs.iter().any(|a| a.path().is_ident("cache_key")) {
+            continue;
+        }
+        let Some(ref field_ident) = field.ident else {
+            abort!(
+                field.span(),
+                "CacheKeyable derive only works for structs with named fields"
+            );
+        };
+        segments.push(quote!(.segment(&self.#field_ident)));
+    }
+
+    if segments.is_empty() {
+        abort!(
+            ident.span(),
+            "CacheKeyable requires at least one field annotated with `#[cache_key]`"
+        );
+    }
+
+    Ok(quote!(
+        impl #im #ident #ty #wh {
+            /// Autogenerated by the [CacheKeyable][hextacy::CacheKeyable] derive
+            pub fn cache_key(&self) -> hextacy::adapters::cache::CacheKey {
+                hextacy::adapters::cache::CacheKey::new(#namespace) #(#segments)*
+            }
+        }
+    ))
+}
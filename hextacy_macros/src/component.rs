@@ -394,7 +394,7 @@ impl Parse for DriverStruct {
     }
 }
 
-fn pascal_to_snake(pascal_string: &str) -> String {
+pub(crate) fn pascal_to_snake(pascal_string: &str) -> String {
     pascal_string
         .chars()
         .enumerate()
@@ -1,7 +1,9 @@
 use proc_macro_error::{abort, proc_macro_error};
 
+mod cache_key;
 mod component;
 mod configuration;
+mod mock;
 mod response;
 
 /// Intended to be used on configuration/state structs that need to instantiate themselves using variables obtained
@@ -157,6 +159,38 @@ pub fn derive_response(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
         .into()
 }
 
+/// Generates a `cache_key(&self) -> CacheKey` method from the fields annotated with `#[cache_key]`,
+/// in order of declaration. The fields must implement `Display`.
+///
+/// The key is namespaced with the struct name in snake_case. A custom namespace can be provided with
+/// `#[cache_key(namespace = "...")]` on the struct.
+///
+/// ### Example
+///
+/// ```ignore
+/// #[derive(CacheKeyable)]
+/// #[cache_key(namespace = "session")]
+/// struct Session {
+///     #[cache_key]
+///     user_id: Uuid,
+///     #[cache_key]
+///     id: Uuid,
+///     csrf: Uuid,
+/// }
+///
+/// // session:<user_id>:<id>
+/// cache.set_json(&session.cache_key(), &session, None).await?;
+/// ```
+#[proc_macro_derive(CacheKeyable, attributes(cache_key))]
+#[proc_macro_error]
+pub fn derive_cache_keyable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: syn::DeriveInput = syn::parse(input).unwrap();
+    match cache_key::impl_cache_keyable(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 #[proc_macro_attribute]
 #[proc_macro_error]
 /// Used to create structs with drivers and custom access traits.
@@ -243,6 +277,22 @@ pub fn component(
 ///
 /// Visibility can be provided for the generated trait, e.g. `#[contract(crate)]`
 ///
+/// With `#[contract(mock)]`, or `#[contract(crate, mock)]`, a `Mock<Trait>` struct implementing the trait is
+/// generated for tests instead of the `mockall` mock. Each method gets an `expect_<method>` setter taking
+/// the closure to call in its place, in which the arguments can be asserted, and a `<method>_calls`
+/// getter returning how many times it was called. Calling a method without an expectation panics.
+///
+/// ```ignore
+/// let mut users = MockUserAdapterContract::new();
+/// users.expect_get_by_id(|id| {
+///     assert_eq!(id, "1");
+///     Ok(None)
+/// });
+/// let service = UserService { users };
+/// assert!(service.get("1").await.is_err());
+/// assert_eq!(service.users.get_by_id_calls(), 1);
+/// ```
+///
 /// A contract defines a set of interactions with an underlying data source or client and
 /// clearly defines how the service interacts with it. Contracts are also an important part
 /// of unit testing since they can easily be mocked and the service verified for correctness. They also
@@ -269,6 +319,7 @@ pub fn contract(
     let trait_ident = format_ident!("{struct_name}Contract");
 
     let mut fn_defs = vec![];
+    let mut sigs = vec![];
 
     let original_fns = item_impl
         .items
@@ -281,18 +332,41 @@ pub fn contract(
             let sig = &func.sig;
             let tokens = quote!(#sig ;);
             fn_defs.push(tokens);
+            sigs.push(sig);
             func
         })
         .collect::<Vec<_>>();
 
-    let visibility: Option<proc_macro2::TokenStream> = (!attr.is_empty()).then(|| {
-        let attr: proc_macro2::TokenStream = attr.into();
-        quote! { (in #attr) }
-    });
+    type Args = syn::punctuated::Punctuated<syn::Path, syn::Token![,]>;
+    let args = syn::parse_macro_input!(attr with Args::parse_terminated);
+
+    let mut mock = false;
+    let mut path = vec![];
+    for arg in args {
+        if arg.is_ident("mock") {
+            mock = true;
+        } else {
+            path.push(arg);
+        }
+    }
+    if path.len() > 1 {
+        abort!(path[1].span(), "contract accepts a single visibility")
+    }
+    let visibility: Option<proc_macro2::TokenStream> =
+        path.first().map(|path| quote! { (in #path) });
+
+    let (automock, mock) = if mock {
+        (
+            None,
+            Some(mock::impl_mock(&trait_ident, &visibility, &sigs)),
+        )
+    } else {
+        (Some(quote!(#[cfg_attr(test, mockall::automock)])), None)
+    };
 
     quote!(
         /// Autogenerated by the [contract][hextacy::contract] macro
-        #[cfg_attr(test, mockall::automock)]
+        #automock
         #[async_trait::async_trait]
         pub #visibility trait #trait_ident {
             #(#fn_defs)*
@@ -302,6 +376,8 @@ pub fn contract(
         impl #impl_generics #trait_ident for #_self #where_clause {
             #(#original_fns)*
         }
+
+        #mock
     )
     .into()
 }
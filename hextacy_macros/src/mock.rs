@@ -0,0 +1,109 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, FnArg, ReturnType, Signature};
+
+/// Generates `Mock<Trait>` with a closure and a call counter per contract method.
+///
+/// Each method gets an `expect_<method>` setter for its closure and a `<method>_calls` getter.
+/// Calling a method without an expectation panics.
+pub(crate) fn impl_mock(
+    trait_ident: &syn::Ident,
+    visibility: &Option<TokenStream>,
+    sigs: &[&Signature],
+) -> TokenStream {
+    let mock_ident = format_ident!("Mock{trait_ident}");
+
+    let mut fields = vec![];
+    let mut setters = vec![];
+    let mut impls = vec![];
+
+    for sig in sigs {
+        if !sig.generics.params.is_empty() {
+            abort!(
+                sig.generics.span(),
+                "mocked contracts cannot have generic methods"
+            )
+        }
+
+        let name = &sig.ident;
+        let calls = format_ident!("{name}_calls");
+        let expect = format_ident!("expect_{name}");
+
+        let mut arg_types = vec![];
+        let mut arg_names = vec![];
+        let mut mock_sig = (*sig).clone();
+        for (i, input) in mock_sig.inputs.iter_mut().enumerate() {
+            let FnArg::Typed(arg) = input else {
+                continue;
+            };
+            if let syn::Type::ImplTrait(_) = *arg.ty {
+                abort!(
+                    arg.ty.span(),
+                    "mocked contracts cannot take `impl Trait` arguments"
+                )
+            }
+            let ident = format_ident!("arg{i}");
+            *arg.pat = syn::parse_quote!(#ident);
+            arg_types.push(arg.ty.clone());
+            arg_names.push(ident);
+        }
+
+        let output = match &sig.output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ty) => quote!(#ty),
+        };
+        let closure = quote!(dyn Fn(#(#arg_types),*) -> #output + Send + Sync);
+
+        let panic = format!("{mock_ident}::{name} called without an expectation");
+
+        fields.push(quote!(#name: (std::sync::atomic::AtomicUsize, Option<Box<#closure>>)));
+
+        setters.push(quote!(
+            /// Set the closure called in place of the method. Assert the arguments within it.
+            pub fn #expect(&mut self, f: impl Fn(#(#arg_types),*) -> #output + Send + Sync + 'static) -> &mut Self {
+                self.#name.1 = Some(Box::new(f));
+                self
+            }
+
+            /// How many times the method was called.
+            pub fn #calls(&self) -> usize {
+                self.#name.0.load(std::sync::atomic::Ordering::SeqCst)
+            }
+        ));
+
+        impls.push(quote!(
+            #mock_sig {
+                self.#name.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let Some(ref f) = self.#name.1 else {
+                    panic!(#panic)
+                };
+                f(#(#arg_names),*)
+            }
+        ));
+    }
+
+    quote!(
+        /// Autogenerated by the [contract][hextacy::contract] macro
+        #[cfg(test)]
+        #[derive(Default)]
+        pub #visibility struct #mock_ident {
+            #(#fields),*
+        }
+
+        #[cfg(test)]
+        impl #mock_ident {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            #(#setters)*
+        }
+
+        #[cfg(test)]
+        #[async_trait::async_trait]
+        impl #trait_ident for #mock_ident {
+            #(#impls)*
+        }
+    )
+}
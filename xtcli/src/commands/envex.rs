@@ -1,5 +1,6 @@
 //! Generate an env example file from the .env file in the root
 use clap::Args;
+use std::collections::BTreeSet;
 use std::fmt::Write;
 
 /// Create an `.env.example` file from the .env file in the root or the specified path
@@ -8,6 +9,11 @@ pub struct EnvExOptions {
     /// If provided xtc will search for a .env file in the given directory and generate a .env.example there
     #[arg(short, long)]
     pub path: Option<String>,
+
+    /// Instead of generating, check the keys in the .env against the existing .env.example
+    /// and exit with a non-zero code if they differ
+    #[arg(short, long)]
+    pub check: bool,
 }
 
 pub fn envex(path: Option<String>) {
@@ -37,3 +43,109 @@ pub fn envex(path: Option<String>) {
 
     std::fs::write(&path, example).unwrap();
 }
+
+/// Compares the keys in the .env file against its .env.example and prints any differences.
+/// Returns `false` if the keys differ.
+pub fn check(path: Option<String>) -> bool {
+    let path = path.unwrap_or_else(|| String::from("./.env"));
+    let example_path = format!("{path}.example");
+
+    let env_file = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Couldn't load .env file at {path}"));
+    let example_file = std::fs::read_to_string(&example_path)
+        .unwrap_or_else(|_| panic!("Couldn't load .env.example file at {example_path}"));
+
+    let diff = EnvDiff::new(&env_file, &example_file);
+
+    if diff.is_empty() {
+        println!("{path} matches {example_path}");
+        return true;
+    }
+
+    print!("{diff}");
+    false
+}
+
+/// The difference in keys between a .env and its .env.example.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EnvDiff<'a> {
+    /// Keys in the example that are not in the env
+    pub missing: Vec<&'a str>,
+    /// Keys in the env that are not in the example
+    pub extra: Vec<&'a str>,
+}
+
+impl<'a> EnvDiff<'a> {
+    pub fn new(env: &'a str, example: &'a str) -> Self {
+        let env = keys(env);
+        let example = keys(example);
+        Self {
+            missing: example.difference(&env).copied().collect(),
+            extra: env.difference(&example).copied().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl std::fmt::Display for EnvDiff<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for key in self.missing.iter() {
+            writeln!(f, "Missing from .env: {key}")?;
+        }
+        for key in self.extra.iter() {
+            writeln!(f, "Missing from .env.example: {key}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the keys of all the variables declared in the file, ignoring comments.
+fn keys(file: &str) -> BTreeSet<&str> {
+    file.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, _)| key.trim().trim_start_matches("export ").trim())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+HOST = 127.0.0.1
+PORT =
+
+# RD_PASSWORD =
+DATABASE_URL = "postgresql://${PG_USER}@${PG_HOST}"
+"#;
+
+    #[test]
+    fn matching_keys() {
+        let env = "HOST = 0.0.0.0\nexport PORT=8000\nDATABASE_URL=\"postgresql://a@b\"";
+        let diff = EnvDiff::new(env, EXAMPLE);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "");
+    }
+
+    #[test]
+    fn missing_and_extra_keys() {
+        let env = "HOST = 0.0.0.0\nRD_PASSWORD = secret\nSECRET=foo";
+        let diff = EnvDiff::new(env, EXAMPLE);
+
+        assert_eq!(diff.missing, ["DATABASE_URL", "PORT"]);
+        assert_eq!(diff.extra, ["RD_PASSWORD", "SECRET"]);
+        assert_eq!(
+            diff.to_string(),
+            "Missing from .env: DATABASE_URL\n\
+             Missing from .env: PORT\n\
+             Missing from .env.example: RD_PASSWORD\n\
+             Missing from .env.example: SECRET\n"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Generate code from project files
+use clap::{Args, Subcommand};
+use std::fmt::Write;
+
+/// Code generation.
+#[derive(Debug, Args)]
+pub struct Generate {
+    #[clap(subcommand)]
+    pub action: GenerateSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GenerateSubcommand {
+    /// Generate a typed `Config` struct with a `from_env` loader from a .env.example
+    Config(ConfigOptions),
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigOptions {
+    /// The path to the .env.example to generate the config from
+    #[arg(short, long, default_value = "./.env.example")]
+    pub path: String,
+
+    /// Where to write the generated file
+    #[arg(short, long, default_value = "./config.rs")]
+    pub out: String,
+}
+
+pub fn write_config(opts: ConfigOptions) {
+    let ConfigOptions { path, out } = opts;
+
+    let example = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Couldn't load .env.example file at {path}"));
+
+    std::fs::write(&out, config(&example))
+        .unwrap_or_else(|e| panic!("Couldn't write config to {out}: {e}"));
+
+    println!("Config written to {out}");
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Var<'a> {
+    key: &'a str,
+    ty: &'static str,
+}
+
+/// Either a top level variable or a group of variables sharing a prefix.
+#[derive(Debug)]
+enum Entry<'a> {
+    Var(Var<'a>),
+    Group(&'a str, Vec<Var<'a>>),
+}
+
+/// Generates the source of a `Config` struct holding a field for every variable in the .env.example.
+///
+/// Variables sharing a prefix, e.g. `PG_*`, are grouped into a nested `PgConfig` struct.
+/// Field types are inferred from the key and example value, `String` otherwise.
+/// Variables without an example value become optional.
+pub fn config(example: &str) -> String {
+    let entries = group(vars(example));
+
+    let mut out = String::new();
+    writeln!(out, "//! Generated by `xtc generate config`").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use hextacy::env::{{self, EnvError}};").unwrap();
+
+    let top_level = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Var(var) => Some(var),
+            Entry::Group(..) => None,
+        })
+        .collect::<Vec<_>>();
+    let groups = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Group(prefix, vars) => Some((*prefix, vars)),
+            Entry::Var(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    writeln!(out).unwrap();
+    writeln!(out, "#[derive(Debug, Clone)]").unwrap();
+    writeln!(out, "pub struct Config {{").unwrap();
+    for var in top_level.iter() {
+        writeln!(out, "    pub {}: {},", field(var.key), var.ty).unwrap();
+    }
+    for (prefix, _) in groups.iter() {
+        writeln!(out, "    pub {}: {},", field(prefix), struct_name(prefix)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    writeln!(out).unwrap();
+    writeln!(out, "impl Config {{").unwrap();
+    writeln!(out, "    pub fn from_env() -> Result<Self, EnvError> {{").unwrap();
+    writeln!(out, "        Ok(Self {{").unwrap();
+    for var in top_level.iter() {
+        writeln!(out, "            {}: {},", field(var.key), loader(var)).unwrap();
+    }
+    for (prefix, _) in groups.iter() {
+        writeln!(
+            out,
+            "            {}: {}::from_env()?,",
+            field(prefix),
+            struct_name(prefix)
+        )
+        .unwrap();
+    }
+    writeln!(out, "        }})").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    for (prefix, vars) in groups {
+        let name = struct_name(prefix);
+        writeln!(out).unwrap();
+        writeln!(out, "#[derive(Debug, Clone)]").unwrap();
+        writeln!(out, "pub struct {name} {{").unwrap();
+        for var in vars.iter() {
+            let key = &var.key[prefix.len() + 1..];
+            writeln!(out, "    pub {}: {},", field(key), var.ty).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+
+        writeln!(out).unwrap();
+        writeln!(out, "impl {name} {{").unwrap();
+        writeln!(out, "    pub fn from_env() -> Result<Self, EnvError> {{").unwrap();
+        writeln!(out, "        Ok(Self {{").unwrap();
+        for var in vars.iter() {
+            let key = &var.key[prefix.len() + 1..];
+            writeln!(out, "            {}: {},", field(key), loader(var)).unwrap();
+        }
+        writeln!(out, "        }})").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+
+    out
+}
+
+/// Parses the declared variables in order, ignoring comments.
+fn vars(example: &str) -> Vec<Var<'_>> {
+    example
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let key = key.trim().trim_start_matches("export ").trim();
+            let value = value.trim().trim_matches('"');
+            (key, value)
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| Var {
+            key,
+            ty: infer(key, value),
+        })
+        .collect()
+}
+
+fn infer(key: &str, value: &str) -> &'static str {
+    if value.is_empty() {
+        return "Option<String>";
+    }
+    if key == "PORT" || key.ends_with("_PORT") {
+        return "u16";
+    }
+    if value.parse::<bool>().is_ok() {
+        return "bool";
+    }
+    if value.parse::<i64>().is_ok() {
+        return "i64";
+    }
+    "String"
+}
+
+/// Groups variables whose prefix (up to the first `_`) is shared with at least one other variable,
+/// keeping the order in which they first appear.
+fn group(vars: Vec<Var<'_>>) -> Vec<Entry<'_>> {
+    fn prefix(key: &str) -> Option<&str> {
+        key.split_once('_').map(|(prefix, _)| prefix)
+    }
+
+    let shared = |p: &str| vars.iter().filter(|var| prefix(var.key) == Some(p)).count() > 1;
+    let prefixes = vars
+        .iter()
+        .map(|var| prefix(var.key).filter(|p| shared(p)))
+        .collect::<Vec<_>>();
+
+    let mut entries: Vec<Entry<'_>> = vec![];
+    for (var, prefix) in vars.into_iter().zip(prefixes) {
+        let Some(prefix) = prefix else {
+            entries.push(Entry::Var(var));
+            continue;
+        };
+
+        let group = entries.iter_mut().find_map(|entry| match entry {
+            Entry::Group(p, vars) if *p == prefix => Some(vars),
+            _ => None,
+        });
+
+        match group {
+            Some(vars) => vars.push(var),
+            None => entries.push(Entry::Group(prefix, vec![var])),
+        }
+    }
+    entries
+}
+
+fn loader(var: &Var) -> String {
+    if var.ty.starts_with("Option") {
+        format!("env::get(\"{}\").ok()", var.key)
+    } else {
+        format!("env::get_parsed(\"{}\")?", var.key)
+    }
+}
+
+fn field(key: &str) -> String {
+    key.to_lowercase()
+}
+
+fn struct_name(prefix: &str) -> String {
+    let prefix = prefix.to_lowercase();
+    format!("{}{}Config", prefix[..1].to_uppercase(), &prefix[1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden() {
+        let example = include_str!("../../testdata/env.example");
+        let expected = include_str!("../../testdata/config.rs.golden");
+        assert_eq!(config(example), expected);
+    }
+
+    #[test]
+    fn inference() {
+        assert_eq!(infer("PORT", "8000"), "u16");
+        assert_eq!(infer("PG_PORT", "5432"), "u16");
+        assert_eq!(infer("RD_DATABASE", "0"), "i64");
+        assert_eq!(infer("DEBUG", "true"), "bool");
+        assert_eq!(infer("HOST", "127.0.0.1"), "String");
+        assert_eq!(infer("RD_PASSWORD", ""), "Option<String>");
+    }
+}
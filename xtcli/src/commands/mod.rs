@@ -1,5 +1,6 @@
 pub mod crypto;
 pub mod envex;
+pub mod generate;
 pub mod init;
 pub mod interactive;
 pub mod xtc;
@@ -1,4 +1,4 @@
-use super::{crypto::Crypto, envex::EnvExOptions};
+use super::{crypto::Crypto, envex::EnvExOptions, generate::Generate};
 use clap::{Parser, Subcommand};
 use std::fmt::Display;
 
@@ -14,6 +14,10 @@ pub enum Command {
     // .env.example
     Envex(EnvExOptions),
 
+    // code generation
+    Generate(Generate),
+    G(Generate),
+
     // crypto utils
     Crypto(Crypto),
     C(Crypto),
@@ -28,7 +32,9 @@ pub enum Command {
 impl Display for Command {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Command::Envex(opts) if opts.check => write!(f, "Checking .env against .env.example"),
             Command::Envex(_) => write!(f, "Generating .env.example"),
+            Command::G(_) | Command::Generate(_) => write!(f, "Generating"),
             Command::C(_) | Command::Crypto(_) => write!(f, "Cryptographying"),
             Command::Interactive | Command::I => write!(f, "Initiating interactive session"),
             Command::Init => write!(f, "Initialising 6tc template"),
@@ -18,8 +18,17 @@ pub fn main() -> Result<(), std::io::Error> {
     println!("{}", xtc.command);
     match xtc.command {
         Command::Envex(args) => {
-            commands::envex::envex(args.path);
+            if !args.check {
+                commands::envex::envex(args.path);
+            } else if !commands::envex::check(args.path) {
+                std::process::exit(1);
+            }
         }
+        Command::Generate(sc) | Command::G(sc) => match sc.action {
+            commands::generate::GenerateSubcommand::Config(opts) => {
+                commands::generate::write_config(opts)
+            }
+        },
         Command::Crypto(sc) | Command::C(sc) => match sc.action {
             commands::crypto::CryptoSubcommand::PW(opts) => write_pw(opts),
             commands::crypto::CryptoSubcommand::Rsa => {